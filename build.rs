@@ -0,0 +1,33 @@
+use std::{env, fs};
+
+/// Manifest of the rust-sdk path dependency, relative to this package
+const SDK_MANIFEST: &str = "../rust-sdk/Cargo.toml";
+
+/// Version in the `[package]` section of a manifest
+fn package_version(manifest: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if let (true, Some(value)) = (in_package, line.strip_prefix("version")) {
+            let value = value.trim_start().strip_prefix('=')?.trim();
+            return Some(value.trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+// Records the version of the rust-sdk the CLI is built against for `dice --version`, and the
+// target triple `dice --self-update` downloads the release binary of
+fn main() {
+    println!("cargo:rerun-if-changed={}", SDK_MANIFEST);
+    let version = fs::read_to_string(SDK_MANIFEST)
+        .ok()
+        .and_then(|manifest| package_version(&manifest))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=RUST_SDK_VERSION={}", version);
+    println!(
+        "cargo:rustc-env=DICE_TARGET={}",
+        env::var("TARGET").unwrap_or_default()
+    );
+}
@@ -0,0 +1,648 @@
+use clap::ValueEnum;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde_derive::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Component, Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use crate::{cleanup, error::DiceCliError};
+
+/// Tag recording how the contents of an artifact are compressed
+pub const COMPRESSION_TAG: &str = "compression";
+
+/// First bytes of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// First bytes of a zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+const DEFAULT_GZIP_LEVEL: i32 = 6;
+
+const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// How the archives the CLI creates are compressed, set once from `--compression` and
+/// `--compression-level`
+static COMPRESSION: OnceLock<ArchiveCompression> = OnceLock::new();
+
+/// Compression of the archives the CLI creates; extraction detects it from the first bytes
+#[derive(ValueEnum, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionFormat {
+    /// A plain tarball, for contents that are already compressed
+    None,
+    Gzip,
+    /// Smaller and faster than gzip, for text and other compressible contents
+    Zstd,
+}
+
+impl CompressionFormat {
+    /// Format of an archive starting with `start`, telling plain tarballs by the lack of a
+    /// compression header
+    pub fn detect(start: &[u8]) -> CompressionFormat {
+        if start.starts_with(&GZIP_MAGIC) {
+            CompressionFormat::Gzip
+        } else if start.starts_with(&ZSTD_MAGIC) {
+            CompressionFormat::Zstd
+        } else {
+            CompressionFormat::None
+        }
+    }
+
+    /// Content type archives of this format are uploaded with
+    pub fn content_type(self) -> &'static str {
+        match self {
+            CompressionFormat::None => "application/x-tar",
+            CompressionFormat::Gzip => "application/gzip",
+            CompressionFormat::Zstd => "application/zstd",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ArchiveCompression {
+    pub format: CompressionFormat,
+    level: i32,
+}
+
+impl Default for ArchiveCompression {
+    fn default() -> Self {
+        ArchiveCompression {
+            format: CompressionFormat::Gzip,
+            level: DEFAULT_GZIP_LEVEL,
+        }
+    }
+}
+
+impl ArchiveCompression {
+    /// Extension of the archives, e.g. `tar.gz`
+    pub fn extension(&self) -> &'static str {
+        match self.format {
+            CompressionFormat::None => "tar",
+            CompressionFormat::Gzip => "tar.gz",
+            CompressionFormat::Zstd => "tar.zst",
+        }
+    }
+
+    /// Content type the archives are uploaded with
+    pub fn content_type(&self) -> &'static str {
+        self.format.content_type()
+    }
+
+    /// Value of the compression tag of the artifacts
+    pub fn tag(&self) -> &'static str {
+        match self.format {
+            CompressionFormat::None => "none",
+            CompressionFormat::Gzip => "gzip",
+            CompressionFormat::Zstd => "zstd",
+        }
+    }
+}
+
+/// Sets how archives are compressed, checking that the level suits the format; only the first
+/// call has an effect
+pub fn configure_compression(
+    format: CompressionFormat,
+    level: Option<i32>,
+) -> Result<(), DiceCliError> {
+    let (levels, default_level) = match format {
+        CompressionFormat::None => (0..=0, 0),
+        CompressionFormat::Gzip => (0..=9, DEFAULT_GZIP_LEVEL),
+        CompressionFormat::Zstd => (1..=22, DEFAULT_ZSTD_LEVEL),
+    };
+    let level = level.unwrap_or(default_level);
+    if !levels.contains(&level) {
+        return Err(DiceCliError::InvalidArgument {
+            message: format!(
+                "--compression-level {} is not supported by {:?} compression, which takes {} to {}",
+                level,
+                format,
+                levels.start(),
+                levels.end()
+            )
+            .to_lowercase(),
+        });
+    }
+
+    let _ = COMPRESSION.set(ArchiveCompression { format, level });
+    Ok(())
+}
+
+pub fn compression() -> ArchiveCompression {
+    COMPRESSION.get().copied().unwrap_or_default()
+}
+
+/// Characters that are not allowed in file names on NTFS
+const NTFS_INVALID_CHARACTERS: [char; 7] = ['<', '>', ':', '"', '|', '?', '*'];
+
+/// Top-level entries extracted into a shared directory, with the artifact each came from, so
+/// artifacts extracted side by side never silently replace each other's files
+#[derive(Default, Debug)]
+pub struct ClaimedEntries(Mutex<HashMap<PathBuf, String>>);
+
+impl ClaimedEntries {
+    /// Claims every entry of `staging` in `destination` for `artifact_id`, failing without
+    /// claiming any when another artifact already has one of them
+    fn claim(
+        &self,
+        staging: &Path,
+        destination: &Path,
+        artifact_id: &str,
+    ) -> Result<(), DiceCliError> {
+        let mut targets = vec![];
+        for entry in fs::read_dir(staging).map_err(io_error("read", staging))? {
+            let entry = entry.map_err(io_error("read", staging))?;
+            targets.push(destination.join(entry.file_name()));
+        }
+
+        let mut claimed = self.0.lock().unwrap();
+        for target in &targets {
+            match claimed.get(target) {
+                Some(other_artifact_id) if other_artifact_id != artifact_id => {
+                    return Err(DiceCliError::FileCollision {
+                        path: target.clone(),
+                        artifact_id: artifact_id.to_string(),
+                        other_artifact_id: other_artifact_id.clone(),
+                    })
+                }
+                _ => (),
+            }
+        }
+        for target in targets {
+            claimed.insert(target, artifact_id.to_string());
+        }
+
+        Ok(())
+    }
+}
+
+fn io_error(action: &'static str, path: &Path) -> impl FnOnce(io::Error) -> DiceCliError {
+    let path = path.to_path_buf();
+    move |source| DiceCliError::Io {
+        action,
+        path,
+        source,
+    }
+}
+
+/// Writes a tarball of `source`, stored under `name`, and returns the writer it wrote to
+fn write_tarball<W: Write>(writer: W, source: &Path, name: &Path) -> Result<W, DiceCliError> {
+    let mut builder = tar::Builder::new(writer);
+    if source.is_dir() {
+        builder.append_dir_all(name, source)
+    } else {
+        builder.append_path_with_name(source, name)
+    }
+    .map_err(io_error("archive", source))?;
+
+    builder.into_inner().map_err(io_error("archive", source))
+}
+
+/// Packs a file or directory into a tarball stored under its own name, compressed as
+/// configured with `--compression`
+pub fn create_archive(source: &Path, archive_path: &Path) -> Result<(), DiceCliError> {
+    write_archive(source, archive_path, compression())
+}
+
+fn write_archive(
+    source: &Path,
+    archive_path: &Path,
+    compression: ArchiveCompression,
+) -> Result<(), DiceCliError> {
+    let name = source
+        .file_name()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("artifact"));
+
+    let archive_file = File::create(archive_path).map_err(io_error("create", archive_path))?;
+    let written = match compression.format {
+        CompressionFormat::None => write_tarball(archive_file, source, &name)?.flush(),
+        CompressionFormat::Gzip => {
+            let level = Compression::new(compression.level as u32);
+            write_tarball(GzEncoder::new(archive_file, level), source, &name)?
+                .finish()
+                .map(|_| ())
+        }
+        CompressionFormat::Zstd => {
+            let encoder = zstd::Encoder::new(archive_file, compression.level)
+                .map_err(io_error("write", archive_path))?;
+            write_tarball(encoder, source, &name)?.finish().map(|_| ())
+        }
+    };
+    written.map_err(io_error("write", archive_path))?;
+
+    Ok(())
+}
+
+fn hash_path(hasher: &mut Sha256, root: &Path, path: &Path) -> Result<(), DiceCliError> {
+    if path.is_dir() {
+        let mut entries = fs::read_dir(path)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| Ok(entry?.path()))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .map_err(io_error("read", path))?;
+        entries.sort();
+        for entry in entries {
+            hash_path(hasher, root, &entry)?;
+        }
+    } else {
+        // Names are hashed too, so that moving a file within a directory changes the checksum
+        let relative_path = path.strip_prefix(root).unwrap_or(path);
+        hasher.update(relative_path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        let mut file = File::open(path).map_err(io_error("open", path))?;
+        io::copy(&mut file, hasher).map_err(io_error("read", path))?;
+    }
+
+    Ok(())
+}
+
+/// SHA-256 of the contents of a file or directory, independent of when it was archived
+pub fn checksum(source: &Path) -> Result<String, DiceCliError> {
+    let mut hasher = Sha256::new();
+    hash_path(&mut hasher, source, source)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Replaces characters that cannot be used in a file name on this platform
+fn sanitize_component(component: &str) -> String {
+    if !cfg!(windows) {
+        return component.to_string();
+    }
+
+    let sanitized: String = component
+        .chars()
+        .map(|c| {
+            if NTFS_INVALID_CHARACTERS.contains(&c) || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    // Windows silently drops trailing dots and spaces, which could merge distinct entries
+    let trimmed = sanitized.trim_end_matches(['.', ' ']);
+    if trimmed.len() == sanitized.len() {
+        sanitized
+    } else {
+        format!("{}_", trimmed)
+    }
+}
+
+/// Turns a free-form name into a single path component that is valid on this platform
+pub fn safe_file_name(name: &str) -> String {
+    let name = sanitize_component(&name.replace(['/', '\\'], "_"));
+    match name.as_str() {
+        "" | "." | ".." => "_".to_string(),
+        _ => name,
+    }
+}
+
+/// Path an archive entry is extracted to and whether it had to be renamed,
+/// or `None` if it would escape the destination
+fn entry_destination(entry_path: &Path) -> Option<(PathBuf, bool)> {
+    let mut destination = PathBuf::new();
+    let mut renamed = false;
+    for component in entry_path.components() {
+        match component {
+            Component::Normal(name) => {
+                let name = name.to_string_lossy();
+                let sanitized = sanitize_component(&name);
+                renamed |= sanitized != name;
+                destination.push(sanitized);
+            }
+            Component::CurDir => (),
+            _ => return None,
+        }
+    }
+
+    Some((destination, renamed))
+}
+
+/// Wraps a tarball stream, plain, gzipped or zstd compressed, into a reader of the plain
+/// tarball. The format is told from the first bytes, not from tags, so archives uploaded by
+/// other tools are read as well.
+fn decompress<'a, R: Read + 'a>(reader: R) -> io::Result<Box<dyn Read + 'a>> {
+    let mut reader = BufReader::new(reader);
+    let start = reader.fill_buf()?;
+
+    Ok(match CompressionFormat::detect(start) {
+        CompressionFormat::Gzip => Box::new(GzDecoder::new(reader)),
+        CompressionFormat::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+        CompressionFormat::None => Box::new(reader),
+    })
+}
+
+/// Opens a tarball for reading, compressed or not
+pub fn open_archive(archive_path: &Path) -> Result<tar::Archive<Box<dyn Read>>, DiceCliError> {
+    let file = File::open(archive_path).map_err(io_error("open", archive_path))?;
+    let reader = decompress(file).map_err(io_error("read", archive_path))?;
+
+    Ok(tar::Archive::new(reader))
+}
+
+/// An entry of a tarball, as listed without extracting it
+#[derive(Serialize, Debug)]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch
+    pub modified: u64,
+    pub is_dir: bool,
+}
+
+/// Lists the entries of a tarball stream, gzipped or not, reading it only once
+pub fn list_entries<R: Read>(reader: R) -> io::Result<Vec<ArchiveEntry>> {
+    let mut archive = tar::Archive::new(decompress(reader)?);
+
+    archive
+        .entries()?
+        .map(|entry| {
+            let entry = entry?;
+            let header = entry.header();
+            Ok(ArchiveEntry {
+                path: entry.path()?.display().to_string(),
+                size: header.size()?,
+                modified: header.mtime()?,
+                is_dir: header.entry_type().is_dir(),
+            })
+        })
+        .collect()
+}
+
+/// Extracts a tarball, gzipped or not, into `destination`.
+/// Entry names that are not valid on this platform are sanitized with a warning.
+pub fn extract_archive(archive_path: &Path, destination: &Path) -> Result<(), DiceCliError> {
+    let mut archive = open_archive(archive_path)?;
+    for entry in archive.entries().map_err(io_error("read", archive_path))? {
+        let mut entry = entry.map_err(io_error("read", archive_path))?;
+        let entry_path = entry
+            .path()
+            .map_err(io_error("read", archive_path))?
+            .into_owned();
+
+        let relative_path = match entry_destination(&entry_path) {
+            Some((relative_path, renamed)) => {
+                if renamed {
+                    eprintln!(
+                        "Warning: extracting archive entry {} as {}",
+                        entry_path.display(),
+                        relative_path.display()
+                    );
+                }
+                relative_path
+            }
+            None => {
+                eprintln!(
+                    "Warning: skipping archive entry {} outside of the output directory",
+                    entry_path.display()
+                );
+                continue;
+            }
+        };
+
+        let target = destination.join(relative_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(io_error("create", parent))?;
+        }
+        entry
+            .unpack(&target)
+            .map_err(io_error("extract", &target))?;
+    }
+
+    Ok(())
+}
+
+/// Moves a fully extracted staging directory into place, either replacing `destination` as a
+/// whole or, with `merge`, replacing only the entries of the same name
+fn move_into_place(staging: &Path, destination: &Path, merge: bool) -> Result<(), DiceCliError> {
+    if merge {
+        fs::create_dir_all(destination).map_err(io_error("create", destination))?;
+        for entry in fs::read_dir(staging).map_err(io_error("read", staging))? {
+            let entry = entry.map_err(io_error("read", staging))?;
+            let target = destination.join(entry.file_name());
+            if target.is_dir() {
+                fs::remove_dir_all(&target).map_err(io_error("replace", &target))?;
+            } else if target.exists() {
+                fs::remove_file(&target).map_err(io_error("replace", &target))?;
+            }
+            fs::rename(entry.path(), &target)
+                .map_err(io_error("move extracted files to", &target))?;
+        }
+        return Ok(());
+    }
+
+    if !destination.exists() {
+        return fs::rename(staging, destination)
+            .map_err(io_error("move extracted files to", destination));
+    }
+
+    // The previous contents stay in place until the new ones can take their name
+    let file_name = destination
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let replaced = destination.with_file_name(format!(".{}.replaced", file_name));
+    fs::rename(destination, &replaced).map_err(io_error("replace", destination))?;
+    if let Err(source) = fs::rename(staging, destination) {
+        let _ = fs::rename(&replaced, destination);
+        return Err(DiceCliError::Io {
+            action: "move extracted files to",
+            path: destination.to_path_buf(),
+            source,
+        });
+    }
+    fs::remove_dir_all(&replaced).map_err(io_error("delete", &replaced))
+}
+
+/// Extracts an archive into a hidden sibling of `destination` named after `artifact_id`,
+/// only moving it into place once every entry unpacked, so a failed extraction never leaves
+/// a mixture of old and new files behind. With `shared`, the entries are merged into a
+/// `destination` other artifacts are extracted into too, and must not be claimed by them.
+pub fn extract_archive_atomically(
+    archive_path: &Path,
+    destination: &Path,
+    artifact_id: &str,
+    shared: Option<&ClaimedEntries>,
+) -> Result<(), DiceCliError> {
+    let staging = destination.with_file_name(format!(".{}.extracting", artifact_id));
+    // Left over when a previous run was killed
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(io_error("delete", &staging))?;
+    }
+    fs::create_dir_all(&staging).map_err(io_error("create", &staging))?;
+    cleanup::register_temp_file(&staging);
+
+    let result = extract_archive(archive_path, &staging).and_then(|()| match shared {
+        Some(claimed) => claimed
+            .claim(&staging, destination, artifact_id)
+            .and_then(|()| move_into_place(&staging, destination, true)),
+        None => move_into_place(&staging, destination, false),
+    });
+    if staging.exists() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    cleanup::unregister_temp_file(&staging);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMATS: [(CompressionFormat, i32); 3] = [
+        (CompressionFormat::None, 0),
+        (CompressionFormat::Gzip, DEFAULT_GZIP_LEVEL),
+        (CompressionFormat::Zstd, DEFAULT_ZSTD_LEVEL),
+    ];
+
+    fn write_source(root: &Path) -> PathBuf {
+        let source = root.join("results");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("summary.txt"), "total: 42\n").unwrap();
+        fs::write(source.join("nested").join("data.bin"), [0u8, 1, 2, 255]).unwrap();
+        source
+    }
+
+    #[test]
+    fn round_trips_a_directory_in_every_format() {
+        for (format, level) in FORMATS {
+            let root = tempfile::tempdir().unwrap();
+            let source = write_source(root.path());
+            let compression = ArchiveCompression { format, level };
+            let archive_path = root.path().join(format!("out.{}", compression.extension()));
+
+            write_archive(&source, &archive_path, compression).unwrap();
+            let bytes = fs::read(&archive_path).unwrap();
+            assert_eq!(CompressionFormat::detect(&bytes), format);
+
+            let destination = root.path().join("extracted");
+            extract_archive(&archive_path, &destination).unwrap();
+            let extracted = destination.join("results");
+            assert_eq!(
+                fs::read_to_string(extracted.join("summary.txt")).unwrap(),
+                "total: 42\n",
+                "{:?}",
+                format
+            );
+            assert_eq!(
+                fs::read(extracted.join("nested").join("data.bin")).unwrap(),
+                [0u8, 1, 2, 255],
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_a_file_in_every_format() {
+        for (format, level) in FORMATS {
+            let root = tempfile::tempdir().unwrap();
+            let source = root.path().join("input.csv");
+            fs::write(&source, "a,b\n1,2\n").unwrap();
+            let archive_path = root.path().join("input.archive");
+
+            write_archive(&source, &archive_path, ArchiveCompression { format, level }).unwrap();
+            let entries = list_entries(File::open(&archive_path).unwrap()).unwrap();
+            assert_eq!(entries.len(), 1, "{:?}", format);
+            assert_eq!(entries[0].path, "input.csv");
+            assert_eq!(entries[0].size, 8);
+
+            let destination = root.path().join("extracted");
+            extract_archive(&archive_path, &destination).unwrap();
+            assert_eq!(
+                fs::read_to_string(destination.join("input.csv")).unwrap(),
+                "a,b\n1,2\n"
+            );
+        }
+    }
+
+    #[test]
+    fn skips_entries_that_escape_the_destination() {
+        assert_eq!(entry_destination(Path::new("../outside.txt")), None);
+        assert_eq!(
+            entry_destination(Path::new("results/../../outside.txt")),
+            None
+        );
+        assert_eq!(entry_destination(Path::new("/etc/passwd")), None);
+        assert_eq!(
+            entry_destination(Path::new("./results/./summary.txt")),
+            Some((Path::new("results").join("summary.txt"), false))
+        );
+    }
+
+    #[test]
+    fn makes_free_form_names_a_single_component() {
+        assert_eq!(safe_file_name("runs/2024\\01"), "runs_2024_01");
+        assert_eq!(safe_file_name(".."), "_");
+        assert_eq!(safe_file_name(""), "_");
+        assert_eq!(safe_file_name("input.csv"), "input.csv");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn keeps_names_windows_rejects_elsewhere() {
+        assert_eq!(sanitize_component("a<b>:c?.txt"), "a<b>:c?.txt");
+        assert_eq!(
+            entry_destination(Path::new("results/trailing. ")),
+            Some((Path::new("results").join("trailing. "), false))
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn replaces_characters_ntfs_rejects() {
+        assert_eq!(sanitize_component("a<b>:c\"d|e?f*"), "a_b__c_d_e_f_");
+        assert_eq!(sanitize_component("tab\there"), "tab_here");
+        assert_eq!(sanitize_component("plain.txt"), "plain.txt");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn keeps_names_with_trailing_dots_and_spaces_distinct() {
+        assert_eq!(sanitize_component("report."), "report_");
+        assert_eq!(sanitize_component("report. ."), "report_");
+        assert_ne!(sanitize_component("report."), sanitize_component("report"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn renames_entries_windows_cannot_store() {
+        assert_eq!(
+            entry_destination(Path::new("results/12:00?.log")),
+            Some((Path::new("results").join("12_00_.log"), true))
+        );
+        assert_eq!(
+            entry_destination(Path::new("results\\summary.txt")),
+            Some((Path::new("results").join("summary.txt"), false))
+        );
+        assert_eq!(entry_destination(Path::new("C:\\Windows\\win.ini")), None);
+        assert_eq!(safe_file_name("a:b"), "a_b");
+    }
+
+    #[test]
+    fn names_the_content_type_of_each_format() {
+        assert_eq!(
+            CompressionFormat::detect(&[0x1f, 0x8b, 0x08]).content_type(),
+            "application/gzip"
+        );
+        assert_eq!(
+            CompressionFormat::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x00]).content_type(),
+            "application/zstd"
+        );
+        assert_eq!(
+            CompressionFormat::detect(b"results/").content_type(),
+            "application/x-tar"
+        );
+        assert_eq!(CompressionFormat::detect(&[]), CompressionFormat::None);
+    }
+}
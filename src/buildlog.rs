@@ -0,0 +1,108 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use crate::{error::DiceCliError, output};
+
+/// Output of the last runtime build, kept in the runtime repository
+pub const BUILD_LOG: &str = ".dice-build.log";
+
+/// Log lines printed when a build fails
+const FAILURE_TAIL_LINES: usize = 30;
+
+/// Collects the output of the build commands in a log file, echoing it live unless quiet
+pub struct BuildLog {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+    quiet: bool,
+}
+
+/// Copies each line of `output` to the log, and to `echo` unless it is `None`
+fn copy_lines(
+    output: impl Read + Send + 'static,
+    log: Arc<Mutex<File>>,
+    mut echo: Option<Box<dyn Write + Send>>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for line in BufReader::new(output).lines().map_while(Result::ok) {
+            let _ = writeln!(log.lock().unwrap(), "{}", line);
+            if let Some(echo) = echo.as_mut() {
+                let _ = writeln!(echo, "{}", line);
+            }
+        }
+    })
+}
+
+impl BuildLog {
+    /// Starts a new log, replacing the one of the previous build
+    pub fn create(path: &Path, quiet: bool) -> Result<Self, DiceCliError> {
+        let file = File::create(path).map_err(|source| DiceCliError::Io {
+            action: "write",
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        Ok(BuildLog {
+            path: path.to_path_buf(),
+            file: Arc::new(Mutex::new(file)),
+            quiet,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Runs a build command, with its stdout and stderr both going to the log
+    pub fn run(&self, command: &mut Command) -> Result<ExitStatus, DiceCliError> {
+        let program = command.get_program().to_string_lossy().into_owned();
+        let _ = writeln!(self.file.lock().unwrap(), "$ {:?}", command);
+
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|source| DiceCliError::ProgramNotFound { program, source })?;
+
+        let echo = |stream: Box<dyn Write + Send>| (!self.quiet).then_some(stream);
+        let readers = [
+            child.stdout.take().map(|stdout| {
+                copy_lines(stdout, self.file.clone(), echo(Box::new(output::Output)))
+            }),
+            child
+                .stderr
+                .take()
+                .map(|stderr| copy_lines(stderr, self.file.clone(), echo(Box::new(io::stderr())))),
+        ];
+        let status = child.wait().map_err(|err| DiceCliError::Internal {
+            message: format!("could not wait for the build: {}", err),
+        })?;
+        for reader in readers.into_iter().flatten() {
+            let _ = reader.join();
+        }
+
+        Ok(status)
+    }
+
+    /// Prints the end of the log to stderr, so a failed build can be told apart from later steps
+    pub fn print_tail(&self) {
+        let contents = fs::read_to_string(&self.path).unwrap_or_default();
+        let lines: Vec<&str> = contents.lines().collect();
+        let tail = &lines[lines.len().saturating_sub(FAILURE_TAIL_LINES)..];
+
+        eprintln!(
+            "---- last {} lines of {} ----",
+            tail.len(),
+            self.path.display()
+        );
+        for line in tail {
+            eprintln!("{}", line);
+        }
+        eprintln!("----");
+    }
+}
@@ -0,0 +1,149 @@
+use directories::ProjectDirs;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+use crate::{error::DiceCliError, output::outln};
+
+/// Resolves the directory used for intermediate archives, which is only created once an
+/// archive is written there. Defaults to `$XDG_CACHE_HOME/dice` (or the platform equivalent).
+pub fn cache_dir(temp_dir: Option<&Path>) -> PathBuf {
+    match temp_dir {
+        Some(temp_dir) => temp_dir.to_path_buf(),
+        None => ProjectDirs::from("", "", "dice")
+            .map(|dirs| dirs.cache_dir().to_path_buf())
+            .unwrap_or_else(|| env::temp_dir().join("dice")),
+    }
+}
+
+/// Returns a path in the cache directory that no other CLI instance will use, creating the
+/// cache directory if needed
+pub fn temp_file_path(cache_dir: &Path, name: &str) -> Result<PathBuf, DiceCliError> {
+    fs::create_dir_all(cache_dir).map_err(|source| DiceCliError::Io {
+        action: "create",
+        path: cache_dir.to_path_buf(),
+        source,
+    })?;
+
+    Ok(cache_dir.join(format!("{}-{}", Uuid::new_v4(), name)))
+}
+
+/// Number of files under `path` and their total size
+pub fn usage(path: &Path) -> io::Result<(u64, u64)> {
+    let mut files = 0;
+    let mut bytes = 0;
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            let (dir_files, dir_bytes) = usage(&entry.path())?;
+            files += dir_files;
+            bytes += dir_bytes;
+        } else {
+            files += 1;
+            bytes += metadata.len();
+        }
+    }
+
+    Ok((files, bytes))
+}
+
+/// Number of files in the cache directory and their total size, none when it was never
+/// created
+fn cache_usage(cache_dir: &Path) -> Result<(u64, u64), DiceCliError> {
+    if !cache_dir.exists() {
+        return Ok((0, 0));
+    }
+    usage(cache_dir).map_err(|source| read_failed(cache_dir, source))
+}
+
+fn read_failed(cache_dir: &Path, source: io::Error) -> DiceCliError {
+    DiceCliError::Io {
+        action: "read",
+        path: cache_dir.to_path_buf(),
+        source,
+    }
+}
+
+pub fn info(cache_dir: &Path) -> Result<(), DiceCliError> {
+    let (files, bytes) = cache_usage(cache_dir)?;
+
+    outln!("Cache directory: {}", cache_dir.display());
+    outln!("Files: {}", files);
+    outln!("Size: {:.1} MB", bytes as f64 / 1024.0 / 1024.0);
+
+    Ok(())
+}
+
+pub fn clean(cache_dir: &Path) -> Result<(), DiceCliError> {
+    let (files, bytes) = cache_usage(cache_dir)?;
+
+    let entries = if cache_dir.exists() {
+        fs::read_dir(cache_dir)
+            .and_then(|entries| entries.collect::<io::Result<Vec<_>>>())
+            .map_err(|source| read_failed(cache_dir, source))?
+    } else {
+        Vec::new()
+    };
+    for entry in entries {
+        let path = entry.path();
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if let Err(err) = result {
+            eprintln!("Could not remove {}: {}", path.display(), err);
+        }
+    }
+
+    outln!(
+        "Removed {} files ({:.1} MB) from {}",
+        files,
+        bytes as f64 / 1024.0 / 1024.0,
+        cache_dir.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creates_the_cache_directory_only_to_write_to_it() {
+        let root = tempfile::tempdir().unwrap();
+        let cache_dir = cache_dir(Some(&root.path().join("cache")));
+
+        info(&cache_dir).unwrap();
+        clean(&cache_dir).unwrap();
+        assert!(!cache_dir.exists());
+
+        let path = temp_file_path(&cache_dir, "runtime.tar.gz").unwrap();
+        assert_eq!(path.parent(), Some(cache_dir.as_path()));
+        assert!(cache_dir.is_dir());
+    }
+
+    #[test]
+    fn reports_a_cache_path_that_is_not_a_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let file = root.path().join("cache");
+        fs::write(&file, "").unwrap();
+
+        assert!(matches!(
+            info(&file),
+            Err(DiceCliError::Io { action: "read", .. })
+        ));
+        assert!(matches!(
+            temp_file_path(&file, "runtime.tar.gz"),
+            Err(DiceCliError::Io {
+                action: "create",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+};
+
+use crate::output;
+
+/// Temporary files and directories created by the running command that should not outlive it
+static TEMP_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Resource records that were created but not yet finalized, as (kind, id) pairs
+static PENDING_RESOURCES: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Exit code used when the CLI is interrupted by a signal
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+pub fn register_temp_file(path: &Path) {
+    TEMP_FILES.lock().unwrap().push(path.to_path_buf());
+}
+
+pub fn unregister_temp_file(path: &Path) {
+    TEMP_FILES
+        .lock()
+        .unwrap()
+        .retain(|temp_file| temp_file != path);
+}
+
+pub fn register_pending_resource(kind: &str, id: &str) {
+    PENDING_RESOURCES
+        .lock()
+        .unwrap()
+        .push((kind.to_string(), id.to_string()));
+}
+
+pub fn unregister_pending_resource(id: &str) {
+    PENDING_RESOURCES
+        .lock()
+        .unwrap()
+        .retain(|(_, pending_id)| pending_id != id);
+}
+
+/// Removes temporary files and reports resource records left in an unfinished state
+pub fn cleanup() {
+    for path in TEMP_FILES.lock().unwrap().drain(..) {
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        match removed {
+            Ok(_) => eprintln!("Removed temporary file {}", path.display()),
+            Err(err) => eprintln!(
+                "Could not remove temporary file {}: {}",
+                path.display(),
+                err
+            ),
+        }
+    }
+
+    for (kind, id) in PENDING_RESOURCES.lock().unwrap().drain(..) {
+        eprintln!(
+            "Created {} {} was not finalized and may need to be cleaned up",
+            kind, id
+        );
+    }
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate =
+            signal(SignalKind::terminate()).expect("Could not install SIGTERM handler");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {},
+            _ = terminate.recv() => {},
+        }
+    }
+
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Could not install Ctrl-C handler");
+}
+
+/// Runs an action, cancelling it and cleaning up partial state on SIGINT/SIGTERM.
+/// A second signal while cleaning up exits immediately.
+pub async fn run_until_interrupted<F: Future>(action: F) -> F::Output {
+    tokio::select! {
+        output = action => output,
+        _ = shutdown_signal() => {
+            eprintln!("Interrupted, cleaning up (interrupt again to exit immediately)");
+
+            tokio::spawn(async {
+                shutdown_signal().await;
+                process::exit(INTERRUPTED_EXIT_CODE);
+            });
+
+            tokio::task::spawn_blocking(cleanup)
+                .await
+                .expect("Could not clean up");
+            output::flush();
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use comfy_table::{Cell, Color};
+use console::{style, StyledObject};
+use rust_sdk::model::{
+    artifact::Status as ArtifactStatus, job_execution::Status as JobExecutionStatus,
+    runtime::Status as RuntimeStatus,
+};
+use std::env;
+
+/// Turns colors off for `--no-color` or a non-empty `NO_COLOR`, and for the output when it goes
+/// to `--output-file`; otherwise they are only used when the output is a terminal, so piped
+/// output stays plain
+pub fn configure(no_color: bool, output_to_file: bool) {
+    let no_color_env = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    if no_color || no_color_env {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    } else if output_to_file {
+        console::set_colors_enabled(false);
+    }
+}
+
+/// A job execution status, green when completed, red when failed and yellow while unfinished
+pub fn job_execution_status(status: &JobExecutionStatus) -> StyledObject<String> {
+    let name = format!("{:?}", status);
+    match status {
+        JobExecutionStatus::Completed => style(name).green(),
+        JobExecutionStatus::Failed => style(name).red(),
+        JobExecutionStatus::Pending | JobExecutionStatus::Running => style(name).yellow(),
+    }
+}
+
+/// A job execution status as a table cell, colored like [`job_execution_status`] when colors
+/// are enabled
+pub fn job_execution_status_cell(status: &JobExecutionStatus) -> Cell {
+    let cell = Cell::new(format!("{:?}", status));
+    if !console::colors_enabled() {
+        return cell;
+    }
+    match status {
+        JobExecutionStatus::Completed => cell.fg(Color::Green),
+        JobExecutionStatus::Failed => cell.fg(Color::Red),
+        JobExecutionStatus::Pending | JobExecutionStatus::Running => cell.fg(Color::Yellow),
+    }
+}
+
+/// A runtime status, green when active and yellow otherwise
+pub fn runtime_status(status: &RuntimeStatus) -> StyledObject<String> {
+    let name = format!("{:?}", status);
+    match status {
+        RuntimeStatus::Active => style(name).green(),
+        _ => style(name).yellow(),
+    }
+}
+
+/// An artifact status, green when active and yellow otherwise
+pub fn artifact_status(status: &ArtifactStatus) -> StyledObject<String> {
+    let name = format!("{:?}", status);
+    match status {
+        ArtifactStatus::Active => style(name).green(),
+        _ => style(name).yellow(),
+    }
+}
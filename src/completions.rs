@@ -0,0 +1,188 @@
+use clap::{Command, ValueEnum};
+use clap_complete::Shell;
+use mongodb::bson::{doc, Document};
+use std::{io::Write, time::Duration};
+
+use crate::{output, output::outln, try_sdk};
+
+/// Resources whose ids `--complete-ids` prints for shell completion
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdResource {
+    Projects,
+    Jobs,
+    Runtimes,
+    JobExecutions,
+}
+
+impl IdResource {
+    /// Flag whose values are ids of this resource
+    fn flag(self) -> &'static str {
+        match self {
+            IdResource::Projects => "--project-id",
+            IdResource::Jobs => "--job-id",
+            IdResource::Runtimes => "--runtime-id",
+            IdResource::JobExecutions => "--job-execution-id",
+        }
+    }
+
+    fn name(self) -> String {
+        self.to_possible_value().unwrap().get_name().to_string()
+    }
+}
+
+/// Most ids offered for a single completion
+const COMPLETION_LIMIT: usize = 50;
+
+/// Longest a completion may wait on the API before giving up, so the shell never hangs
+const COMPLETION_TIMEOUT: Duration = Duration::from_secs(2);
+
+const ID_RESOURCES: [IdResource; 4] = [
+    IdResource::Projects,
+    IdResource::Jobs,
+    IdResource::Runtimes,
+    IdResource::JobExecutions,
+];
+
+/// Writes the completion script for `shell`; bash and zsh scripts also complete id flags
+/// with the ids printed by `--complete-ids`
+pub fn print_completions(shell: Shell, command: &mut Command) {
+    let name = command.get_name().to_string();
+    let mut script = vec![];
+    clap_complete::generate(shell, command, &name, &mut script);
+    let _ = output::Output.write_all(&script);
+
+    // The generated function is wrapped, and its name varies between clap_complete versions
+    let script = String::from_utf8_lossy(&script);
+    let registration = match shell {
+        Shell::Bash => "complete -F ",
+        _ => "compdef ",
+    };
+    let Some(generated) = script
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix(registration))
+        .filter_map(|line| line.split_whitespace().next())
+        .next_back()
+    else {
+        return;
+    };
+
+    let cases: String = ID_RESOURCES
+        .iter()
+        .map(|resource| {
+            format!(
+                "        {}) resource={} ;;\n",
+                resource.flag(),
+                resource.name()
+            )
+        })
+        .collect();
+    match shell {
+        Shell::Bash => outln!(
+            r#"
+_{function}_ids() {{
+    local resource
+    case "${{COMP_WORDS[COMP_CWORD-1]}}" in
+{cases}    esac
+    if [[ -n $resource ]]; then
+        COMPREPLY=($(compgen -W "$("${{COMP_WORDS[0]}}" --complete-ids "$resource" 2>/dev/null | cut -f1)" -- "${{COMP_WORDS[COMP_CWORD]}}"))
+        return
+    fi
+    {generated} "$@"
+}}
+complete -F _{function}_ids -o bashdefault -o default {name}"#,
+            function = name.replace('-', "_"),
+        ),
+        Shell::Zsh => outln!(
+            r#"
+_{function}_ids() {{
+    local resource
+    case $words[CURRENT-1] in
+{cases}    esac
+    if [[ -n $resource ]]; then
+        local -a ids
+        ids=(${{(f)"$($words[1] --complete-ids $resource 2>/dev/null)"}})
+        _describe -t ids $resource "${{(@)ids//$'\t'/:}}"
+        return
+    fi
+    {generated} "$@"
+}}
+compdef _{function}_ids {name}"#,
+            function = name.replace('-', "_"),
+        ),
+        _ => (),
+    }
+}
+
+async fn list_ids(resource: IdResource, project_id: Option<String>) -> Vec<(String, String)> {
+    let filter: Document = match project_id {
+        Some(project_id) if resource != IdResource::Projects => doc! { "project_id": project_id },
+        _ => doc! {},
+    };
+
+    let ids = match resource {
+        IdResource::Projects => try_sdk("list projects", || {
+            rust_sdk::api::project::list(filter.clone())
+        })
+        .await
+        .map(|projects| {
+            projects
+                .into_iter()
+                .map(|project| (project.id, project.description))
+                .collect()
+        }),
+        IdResource::Jobs => try_sdk("list jobs", || rust_sdk::api::job::list(filter.clone()))
+            .await
+            .map(|jobs| {
+                jobs.into_iter()
+                    .map(|job| (job.id, format!("runtime {}", job.runtime_id)))
+                    .collect()
+            }),
+        IdResource::Runtimes => try_sdk("list runtimes", || {
+            rust_sdk::api::runtime::list(filter.clone())
+        })
+        .await
+        .map(|runtimes| {
+            runtimes
+                .into_iter()
+                .map(|runtime| {
+                    (
+                        runtime.id,
+                        format!("{} ({:?})", runtime.name, runtime.status),
+                    )
+                })
+                .collect()
+        }),
+        // Executions have no project, so the most recent ones of any job are offered
+        IdResource::JobExecutions => try_sdk("list job executions", || {
+            rust_sdk::api::job_execution::list(doc! {})
+        })
+        .await
+        .map(|job_executions| {
+            job_executions
+                .into_iter()
+                .map(|job_execution| (job_execution.id, format!("{:?}", job_execution.status)))
+                .collect()
+        }),
+    };
+
+    let mut ids: Vec<_> = ids.unwrap_or_default();
+    ids.sort_by(|(a, _), (b, _)| b.cmp(a));
+    ids.into_iter()
+        .take(COMPLETION_LIMIT)
+        .map(|(id, description)| (id.to_string(), description))
+        .collect()
+}
+
+/// Prints the ids of `resource`, each followed by a tab and a short description, printing
+/// nothing at all when the API cannot be reached in time
+pub async fn complete_ids(resource: IdResource, project_id: Option<String>) {
+    // Completion runs inside the shell, where error output would garble the prompt
+    std::panic::set_hook(Box::new(|_| {}));
+
+    if let Ok(ids) = tokio::time::timeout(COMPLETION_TIMEOUT, list_ids(resource, project_id)).await
+    {
+        for (id, description) in ids {
+            outln!("{}\t{}", id, description.replace(['\t', '\n'], " "));
+        }
+    }
+}
@@ -0,0 +1,818 @@
+use clap::{parser::ValueSource, ArgMatches};
+use dialoguer::{Confirm, Input, Password};
+use directories::BaseDirs;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+use tokio::sync::RwLock;
+
+use crate::{
+    doctor, error::DiceCliError, keychain, limits::Limits, output::outln, parse_error, print_table,
+    read_to_string, write_atomically,
+};
+
+/// Name of the profile used when none is selected
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Location of the project settings, relative to a project directory
+const PROJECT_CONFIG_PATH: [&str; 2] = [".dice", "project.toml"];
+
+/// Profile selected for this invocation, set once when the config is loaded
+static ACTIVE_PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Variables the SDK reads the API location and key from
+const API_VARIABLES: [&str; 2] = ["API_URL", "API_KEY"];
+
+/// Held for reading by every SDK call and for writing by [`with_profile`] while it changes
+/// [`API_VARIABLES`], so no call reads the environment while it is being written
+pub static API_SETTINGS: RwLock<()> = RwLock::const_new(());
+
+/// Settings of a single profile
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Config {
+    pub api_url: Option<String>,
+    pub api_key: Option<String>,
+    pub default_project_id: Option<String>,
+    /// Set to false to stop comparing the server version with the CLI before each command that
+    /// uses the API
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_check: Option<bool>,
+    /// Most artifacts uploaded at the same time unless `--max-parallel-uploads` is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_parallel_uploads: Option<usize>,
+    /// Tags added to every resource the CLI creates; `--tag` wins on the same key
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub default_tags: HashMap<String, String>,
+    /// Runtime `--smoke-test` runs unless `--runtime-id` is given, one that copies its input
+    /// to its output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smoke_test_runtime_id: Option<String>,
+    /// Client-side limits on names, descriptions, tags and input artifacts; a profile's table
+    /// replaces the default one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limits: Option<Limits>,
+}
+
+/// Layout of `~/.dice/config.toml`: a `[default]` section with fallback values
+/// and one `[profiles.<name>]` section per named profile
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct ConfigFile {
+    #[serde(default)]
+    pub default: Config,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Config>,
+}
+
+/// Settings of the project a directory belongs to, read from `.dice/project.toml`.
+/// They are used when the matching flag and variable are not set, and win over the profile.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct ProjectConfig {
+    pub project_id: Option<String>,
+    pub runtime_id: Option<String>,
+    /// Tags added to every resource created from the project directory
+    #[serde(default)]
+    pub default_tags: HashMap<String, String>,
+}
+
+impl Config {
+    /// Fills any unset value from the fallback settings
+    fn or(self, fallback: &Config) -> Config {
+        Config {
+            api_url: self.api_url.or_else(|| fallback.api_url.clone()),
+            api_key: self.api_key.or_else(|| fallback.api_key.clone()),
+            default_project_id: self
+                .default_project_id
+                .or_else(|| fallback.default_project_id.clone()),
+            version_check: self.version_check.or(fallback.version_check),
+            max_parallel_uploads: self.max_parallel_uploads.or(fallback.max_parallel_uploads),
+            smoke_test_runtime_id: self
+                .smoke_test_runtime_id
+                .or_else(|| fallback.smoke_test_runtime_id.clone()),
+            limits: self.limits.or(fallback.limits),
+            // Tags are merged key by key, the profile's winning over the fallback's
+            default_tags: fallback
+                .default_tags
+                .clone()
+                .into_iter()
+                .chain(self.default_tags)
+                .collect(),
+        }
+    }
+
+    /// Prefers the API key `--login` stored in the system keychain over the config file's
+    fn with_keychain_key(self, profile: &str) -> Config {
+        Config {
+            api_key: keychain::api_key(profile).or(self.api_key),
+            ..self
+        }
+    }
+
+    /// Exports the API settings for the SDK. Values given with a flag or `DICE_` variable
+    /// win over the SDK's own `API_URL`/`API_KEY` variables, which win over the config file.
+    /// The environment is shared by every thread, so this runs before the async runtime starts.
+    pub fn apply(&self, api_url: Option<String>, api_key: Option<String>) {
+        for (variable, value, configured) in [
+            ("API_URL", api_url, &self.api_url),
+            ("API_KEY", api_key, &self.api_key),
+        ] {
+            match (value, env::var(variable), configured) {
+                (Some(value), _, _) => env::set_var(variable, value),
+                (None, Err(_), Some(configured)) => env::set_var(variable, configured),
+                _ => (),
+            }
+        }
+    }
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    BaseDirs::new().map(|dirs| dirs.home_dir().join(".dice").join("config.toml"))
+}
+
+/// Finds `.dice/project.toml` in the current directory or its ancestors, stopping at the
+/// home directory
+pub fn find_project_config() -> Option<PathBuf> {
+    let home = BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf());
+    let cwd = env::current_dir().ok()?;
+
+    for dir in cwd.ancestors() {
+        let path: PathBuf = PROJECT_CONFIG_PATH
+            .iter()
+            .fold(dir.to_path_buf(), |path, part| path.join(part));
+        if path.is_file() {
+            return Some(path);
+        }
+        if Some(dir) == home.as_deref() {
+            break;
+        }
+    }
+
+    None
+}
+
+pub fn load_project_config() -> Result<ProjectConfig, DiceCliError> {
+    match find_project_config() {
+        Some(path) => {
+            toml::from_str(&read_to_string(&path)?).map_err(|err| parse_error(&path, err))
+        }
+        None => Ok(ProjectConfig::default()),
+    }
+}
+
+/// Creates `.dice/project.toml` in the current directory
+pub fn init_project(project_config: ProjectConfig) -> Result<(), DiceCliError> {
+    let path: PathBuf = PROJECT_CONFIG_PATH.iter().collect();
+    if path.exists() {
+        return Err(DiceCliError::AlreadyExists { path });
+    }
+
+    let write_failed = |source| DiceCliError::Io {
+        action: "write",
+        path: path.clone(),
+        source,
+    };
+    fs::create_dir_all(path.parent().unwrap_or(Path::new("."))).map_err(write_failed)?;
+    fs::write(&path, toml::to_string(&project_config).unwrap()).map_err(write_failed)?;
+
+    outln!("Created project settings: {}", path.display());
+
+    Ok(())
+}
+
+pub fn active_profile() -> &'static str {
+    ACTIVE_PROFILE
+        .get()
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_PROFILE)
+}
+
+fn load_file() -> Result<ConfigFile, DiceCliError> {
+    let path = match config_path() {
+        Some(path) if path.exists() => path,
+        _ => return Ok(ConfigFile::default()),
+    };
+
+    toml::from_str(&read_to_string(&path)?).map_err(|err| parse_error(&path, err))
+}
+
+fn save_file(config_file: &ConfigFile) -> Result<(), DiceCliError> {
+    let path = config_path().ok_or(DiceCliError::NoHomeDirectory)?;
+    let write_failed = |source| DiceCliError::Io {
+        action: "write",
+        path: path.clone(),
+        source,
+    };
+
+    fs::create_dir_all(path.parent().unwrap_or(Path::new("."))).map_err(write_failed)?;
+    write_atomically(&path, toml::to_string(config_file).unwrap().as_bytes()).map_err(write_failed)
+}
+
+/// Settings of a profile merged over the `[default]` section
+fn profile_config(mut config_file: ConfigFile, profile: &str) -> Result<Config, DiceCliError> {
+    let default = config_file.default.with_keychain_key(DEFAULT_PROFILE);
+    if profile == DEFAULT_PROFILE {
+        return Ok(default);
+    }
+
+    config_file
+        .profiles
+        .remove(profile)
+        .map(|config| config.with_keychain_key(profile).or(&default))
+        .ok_or_else(|| DiceCliError::ProfileNotFound {
+            profile: profile.to_string(),
+        })
+}
+
+/// Loads the settings of the selected profile, falling back to the `[default]` section
+pub fn load(profile: Option<&str>) -> Result<Config, DiceCliError> {
+    let profile = profile.unwrap_or(DEFAULT_PROFILE);
+    let _ = ACTIVE_PROFILE.set(profile.to_string());
+
+    profile_config(load_file()?, profile)
+}
+
+/// Sets [`API_VARIABLES`] to the values `values` derives from the current ones, unsetting
+/// those without a value, and returns the previous values. The write lock waits for the
+/// running SDK calls and holds back new ones until the variables are set.
+async fn swap_api_variables(
+    values: impl FnOnce([Option<String>; 2]) -> [Option<String>; 2],
+) -> [Option<String>; 2] {
+    let _settings = API_SETTINGS.write().await;
+    let previous = API_VARIABLES.map(|variable| env::var(variable).ok());
+    for (variable, value) in API_VARIABLES.into_iter().zip(values(previous.clone())) {
+        match value {
+            Some(value) => env::set_var(variable, value),
+            None => env::remove_var(variable),
+        }
+    }
+
+    previous
+}
+
+/// Runs `task` with the SDK pointed at the deployment of another profile, for commands that
+/// read from one deployment and write to another. Settings the profile lacks are kept.
+pub async fn with_profile<T>(
+    profile: &str,
+    task: impl Future<Output = Result<T, DiceCliError>>,
+) -> Result<T, DiceCliError> {
+    let [api_url, api_key] =
+        profile_config(load_file()?, profile).map(|config| [config.api_url, config.api_key])?;
+    let previous = swap_api_variables(|[current_url, current_key]| {
+        [api_url.or(current_url), api_key.or(current_key)]
+    })
+    .await;
+
+    let result = task.await;
+    swap_api_variables(|_| previous).await;
+    result
+}
+
+/// Masks all but the last 4 characters of a secret
+pub fn mask_secret(secret: &str) -> String {
+    let visible: String = secret
+        .chars()
+        .rev()
+        .take(4)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+
+    format!("****{}", visible)
+}
+
+fn show_file_values(config: &Config) {
+    outln!("api_url = {}", config.api_url.as_deref().unwrap_or(""));
+    outln!(
+        "api_key = {}",
+        config
+            .api_key
+            .as_deref()
+            .map(mask_secret)
+            .unwrap_or_default()
+    );
+    outln!(
+        "default_project_id = {}",
+        config.default_project_id.as_deref().unwrap_or("")
+    );
+}
+
+/// Exports API settings to validate for the SDK, before the async runtime starts
+fn export_api_settings(api_url: &str, api_key: &str) {
+    env::set_var("API_URL", api_url);
+    env::set_var("API_KEY", api_key);
+}
+
+/// Error for a prompt that could not be shown or answered, e.g. without a terminal
+fn prompt_failed(prompt: &'static str) -> impl FnOnce(io::Error) -> DiceCliError {
+    move |source| DiceCliError::Prompt { prompt, source }
+}
+
+/// Prompts for the `[default]` section of `~/.dice/config.toml` and exports its API settings
+/// for [`init`] to validate, or returns `None` when an existing file is kept
+pub fn prompt_init() -> Result<Option<Config>, DiceCliError> {
+    let path = config_path().ok_or(DiceCliError::NoHomeDirectory)?;
+
+    let config_file = load_file()?;
+    if path.exists() {
+        outln!("{} already exists:", path.display());
+        show_file_values(&config_file.default);
+
+        let overwrite = Confirm::new()
+            .with_prompt("Overwrite it?")
+            .default(false)
+            .interact()
+            .map_err(prompt_failed("confirmation"))?;
+        if !overwrite {
+            return Ok(None);
+        }
+    }
+
+    let existing = config_file.default;
+    let api_url: String = Input::new()
+        .with_prompt("API_URL")
+        .with_initial_text(existing.api_url.unwrap_or_default())
+        .interact_text()
+        .map_err(prompt_failed("API_URL"))?;
+    let api_key = Password::new()
+        .with_prompt("API_KEY")
+        .interact()
+        .map_err(prompt_failed("API_KEY"))?;
+    let default_project_id: String = Input::new()
+        .with_prompt("default_project_id (optional)")
+        .with_initial_text(existing.default_project_id.unwrap_or_default())
+        .allow_empty(true)
+        .interact_text()
+        .map_err(prompt_failed("default_project_id"))?;
+
+    export_api_settings(&api_url, &api_key);
+
+    Ok(Some(Config {
+        api_url: Some(api_url),
+        api_key: Some(api_key),
+        default_project_id: Some(default_project_id).filter(|id| !id.is_empty()),
+        ..existing
+    }))
+}
+
+/// Validates the settings [`prompt_init`] exported against the API, then saves them as the
+/// `[default]` section of `~/.dice/config.toml`
+pub async fn init(config: Config) -> Result<(), DiceCliError> {
+    let path = config_path().ok_or(DiceCliError::NoHomeDirectory)?;
+    doctor::ping().await?;
+
+    let mut config_file = load_file()?;
+    config_file.default = config;
+    save_file(&config_file)?;
+
+    outln!("Wrote {}", path.display());
+
+    Ok(())
+}
+
+/// API location and key for [`login`] to validate, prompting for the values not given with a
+/// flag or variable, and exported for the SDK before the async runtime starts
+pub fn login_credentials(
+    config: &Config,
+    api_url: Option<String>,
+    api_key: Option<String>,
+) -> Result<(String, String), DiceCliError> {
+    let api_url = match api_url
+        .or_else(|| env::var("API_URL").ok())
+        .or_else(|| config.api_url.clone())
+    {
+        Some(api_url) => api_url,
+        None => Input::new()
+            .with_prompt("API_URL")
+            .interact_text()
+            .map_err(prompt_failed("API_URL"))?,
+    };
+    let api_key = match api_key {
+        Some(api_key) => api_key,
+        None => Password::new()
+            .with_prompt(format!("API key for {}", api_url))
+            .interact()
+            .map_err(prompt_failed("API_KEY"))?,
+    };
+    export_api_settings(&api_url, &api_key);
+
+    Ok((api_url, api_key))
+}
+
+/// Validates the API key [`login_credentials`] exported against the API and stores it in the
+/// system keychain for the active profile. A plain text `api_key` of the profile is removed
+/// from the config file.
+pub async fn login(api_url: String, api_key: String) -> Result<(), DiceCliError> {
+    let profile = active_profile();
+    doctor::ping().await?;
+    keychain::store_api_key(profile, &api_key)?;
+
+    let path = config_path().ok_or(DiceCliError::NoHomeDirectory)?;
+    let mut config_file = load_file()?;
+    let section = match profile {
+        DEFAULT_PROFILE => &mut config_file.default,
+        profile => config_file.profiles.entry(profile.to_string()).or_default(),
+    };
+    section.api_url = Some(api_url.clone());
+    let had_plain_key = section.api_key.take().is_some();
+    save_file(&config_file)?;
+
+    outln!(
+        "Logged in to {} with profile {}, the API key is stored in the system keychain",
+        api_url,
+        profile
+    );
+    if had_plain_key {
+        outln!(
+            "Removed the plain text api_key of profile {} from {}",
+            profile,
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Removes the API key of the active profile from the system keychain
+pub fn logout() -> Result<(), DiceCliError> {
+    let profile = active_profile();
+    if keychain::delete_api_key(profile)? {
+        outln!("Logged out of profile {}", profile);
+    } else {
+        outln!(
+            "No API key of profile {} is stored in the system keychain",
+            profile
+        );
+    }
+
+    Ok(())
+}
+
+pub fn list_profiles() -> Result<(), DiceCliError> {
+    let config_file = load_file()?;
+    let profiles = [(DEFAULT_PROFILE, &config_file.default)].into_iter().chain(
+        config_file
+            .profiles
+            .iter()
+            .map(|(name, config)| (name.as_str(), config)),
+    );
+
+    print_table(
+        &["PROFILE", "API URL", "API KEY", "DEFAULT PROJECT"],
+        profiles
+            .map(|(name, config)| {
+                vec![
+                    name.to_string(),
+                    config.api_url.clone().unwrap_or_default(),
+                    config
+                        .api_key
+                        .as_deref()
+                        .map(mask_secret)
+                        .unwrap_or_default(),
+                    config.default_project_id.clone().unwrap_or_default(),
+                ]
+            })
+            .collect(),
+    );
+
+    Ok(())
+}
+
+pub fn add_profile(name: String, config: Config) -> Result<(), DiceCliError> {
+    let mut config_file = load_file()?;
+
+    if name == DEFAULT_PROFILE {
+        config_file.default = config;
+    } else {
+        config_file.profiles.insert(name.clone(), config);
+    }
+    save_file(&config_file)?;
+
+    outln!("Saved profile {}", name);
+
+    Ok(())
+}
+
+pub fn delete_profile(name: String) -> Result<(), DiceCliError> {
+    let mut config_file = load_file()?;
+
+    if config_file.profiles.remove(&name).is_none() {
+        return Err(DiceCliError::ProfileNotFound { profile: name });
+    }
+    save_file(&config_file)?;
+
+    outln!("Deleted profile {}", name);
+
+    Ok(())
+}
+
+/// Finds the effective value of a setting and describes where it came from
+fn resolve(
+    matches: &ArgMatches,
+    arg_id: &str,
+    dice_variable: &str,
+    sdk_variable: Option<&str>,
+    configured: Option<(String, String)>,
+) -> Option<(String, String)> {
+    let value = matches.get_one::<String>(arg_id).cloned();
+    match (matches.value_source(arg_id), value) {
+        (Some(ValueSource::CommandLine), Some(value)) => {
+            return Some((value, format!("from --{}", arg_id.replace('_', "-"))))
+        }
+        (Some(ValueSource::EnvVariable), Some(value)) => {
+            return Some((value, format!("from env {}", dice_variable)))
+        }
+        _ => (),
+    }
+
+    if let Some(sdk_variable) = sdk_variable {
+        if let Ok(value) = env::var(sdk_variable) {
+            return Some((value, format!("from env {}", sdk_variable)));
+        }
+    }
+
+    configured
+}
+
+/// A value of the config file with its source
+fn from_file(configured: &Option<String>) -> Option<(String, String)> {
+    let path = config_path()?;
+    configured.clone().map(|value| {
+        (
+            value,
+            format!("from {} profile {}", path.display(), active_profile()),
+        )
+    })
+}
+
+/// The API key with its source, telling keys stored by `--login` apart from the config file's
+fn configured_api_key(config: &Config) -> Option<(String, String)> {
+    let api_key = config.api_key.as_ref()?;
+    [active_profile(), DEFAULT_PROFILE]
+        .into_iter()
+        .find(|profile| keychain::api_key(profile).as_ref() == Some(api_key))
+        .map(|profile| {
+            (
+                api_key.clone(),
+                format!("from the system keychain profile {}", profile),
+            )
+        })
+        .or_else(|| from_file(&config.api_key))
+}
+
+/// Prints the resolved configuration as TOML, annotating each value with its source
+pub fn show(config: &Config, matches: &ArgMatches) {
+    let settings = [
+        (
+            "api_url",
+            resolve(
+                matches,
+                "api_url",
+                "DICE_API_URL",
+                Some("API_URL"),
+                from_file(&config.api_url),
+            ),
+        ),
+        (
+            "api_key",
+            resolve(
+                matches,
+                "api_key",
+                "DICE_API_KEY",
+                Some("API_KEY"),
+                configured_api_key(config),
+            )
+            .map(|(value, source)| (mask_secret(&value), source)),
+        ),
+        (
+            "default_project_id",
+            resolve(
+                matches,
+                "project_id",
+                "DICE_PROJECT_ID",
+                None,
+                from_file(&config.default_project_id),
+            ),
+        ),
+    ];
+
+    outln!("# profile: {}", active_profile());
+    for (key, setting) in settings {
+        match setting {
+            Some((value, source)) => outln!("{} = {:?} # ({})", key, value, source),
+            None => outln!("# {} is not set", key),
+        }
+    }
+
+    // The default and profile tags merged; project settings add theirs on top
+    if matches.get_flag("no_default_tags") {
+        outln!("# default_tags are disabled by --no-default-tags");
+    } else if config.default_tags.is_empty() {
+        outln!("# default_tags is not set");
+    } else {
+        outln!("[default_tags]");
+        let tags: BTreeMap<_, _> = config.default_tags.iter().collect();
+        for (key, value) in tags {
+            outln!("{} = {:?}", key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Arguments;
+    use clap::{CommandFactory, Parser};
+    use std::sync::Mutex;
+
+    /// Serializes the tests that set the API variables, which the whole process shares
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    const VARIABLES: [&str; 4] = ["DICE_API_URL", "API_URL", "DICE_API_KEY", "API_KEY"];
+
+    /// Runs `f` with only the given variables and none of the API ones set, restoring them
+    /// afterwards
+    fn with_env(variables: &[(&str, &str)], f: impl FnOnce()) {
+        let _lock = ENV_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let previous: Vec<_> = VARIABLES
+            .iter()
+            .chain(variables.iter().map(|(variable, _)| variable))
+            .map(|variable| (*variable, env::var(variable).ok()))
+            .collect();
+        for variable in VARIABLES {
+            env::remove_var(variable);
+        }
+        for (variable, value) in variables {
+            env::set_var(variable, value);
+        }
+
+        f();
+
+        for (variable, value) in previous.into_iter().rev() {
+            match value {
+                Some(value) => env::set_var(variable, value),
+                None => env::remove_var(variable),
+            }
+        }
+    }
+
+    fn matches(args: &[&str]) -> ArgMatches {
+        Arguments::command()
+            .try_get_matches_from([&["dice", "--show-config"], args].concat())
+            .unwrap()
+    }
+
+    fn resolve_api_url(args: &[&str], file: Option<&str>) -> Option<(String, String)> {
+        resolve(
+            &matches(args),
+            "api_url",
+            "DICE_API_URL",
+            Some("API_URL"),
+            file.map(|value| (value.to_string(), "from the config file".to_string())),
+        )
+    }
+
+    fn resolved(value: &str, source: &str) -> Option<(String, String)> {
+        Some((value.to_string(), source.to_string()))
+    }
+
+    #[test]
+    fn resolves_the_flag_over_the_variables_and_the_file() {
+        let variables = [
+            ("DICE_API_URL", "https://dice.example"),
+            ("API_URL", "https://sdk.example"),
+        ];
+        with_env(&variables, || {
+            assert_eq!(
+                resolve_api_url(&["--api-url", "https://flag.example"], Some("https://file")),
+                resolved("https://flag.example", "from --api-url")
+            );
+        });
+    }
+
+    #[test]
+    fn resolves_the_dice_variable_over_the_sdk_variable_and_the_file() {
+        let variables = [
+            ("DICE_API_URL", "https://dice.example"),
+            ("API_URL", "https://sdk.example"),
+        ];
+        with_env(&variables, || {
+            assert_eq!(
+                resolve_api_url(&[], Some("https://file")),
+                resolved("https://dice.example", "from env DICE_API_URL")
+            );
+        });
+        with_env(&[("API_URL", "https://sdk.example")], || {
+            assert_eq!(
+                resolve_api_url(&[], Some("https://file")),
+                resolved("https://sdk.example", "from env API_URL")
+            );
+        });
+    }
+
+    #[test]
+    fn resolves_the_file_over_the_default() {
+        with_env(&[], || {
+            assert_eq!(
+                resolve_api_url(&[], Some("https://file")),
+                resolved("https://file", "from the config file")
+            );
+            // Nothing has a built-in default, so an unset value stays unset
+            assert_eq!(resolve_api_url(&[], None), None);
+        });
+    }
+
+    #[test]
+    fn parses_flags_over_variables_over_defaults() {
+        let format = |args: &[&str]| {
+            *matches(args)
+                .get_one::<crate::OutputFormat>("format")
+                .unwrap()
+        };
+
+        with_env(&[], || {
+            assert_eq!(format(&[]), crate::OutputFormat::Table);
+        });
+        with_env(&[("DICE_OUTPUT_FORMAT", "json")], || {
+            assert_eq!(format(&[]), crate::OutputFormat::Json);
+            assert_eq!(format(&["--format", "raw"]), crate::OutputFormat::Raw);
+        });
+    }
+
+    #[test]
+    fn splits_the_variables_of_repeatable_flags_on_commas() {
+        let tags = |args: &[&str]| {
+            Arguments::try_parse_from([&["dice"], args].concat())
+                .unwrap()
+                .tags
+        };
+        let tag = |key: &str, value: &str| (key.to_string(), value.to_string());
+
+        with_env(&[("DICE_TAGS", "team=genomics,stage=test")], || {
+            assert_eq!(
+                tags(&[]),
+                vec![tag("team", "genomics"), tag("stage", "test")]
+            );
+            assert_eq!(tags(&["--tag", "team=render"]), vec![tag("team", "render")]);
+        });
+    }
+
+    #[test]
+    fn exports_the_winning_api_settings_for_the_sdk() {
+        let config = Config {
+            api_url: Some("https://file".to_string()),
+            api_key: Some("file-key".to_string()),
+            ..Config::default()
+        };
+
+        with_env(&[("API_URL", "https://sdk.example")], || {
+            config.apply(Some("https://flag.example".to_string()), None);
+            assert_eq!(env::var("API_URL").unwrap(), "https://flag.example");
+            assert_eq!(env::var("API_KEY").unwrap(), "file-key");
+        });
+        with_env(&[("API_URL", "https://sdk.example")], || {
+            config.apply(None, None);
+            assert_eq!(env::var("API_URL").unwrap(), "https://sdk.example");
+        });
+        with_env(&[], || {
+            Config::default().apply(None, None);
+            assert!(env::var("API_URL").is_err());
+            assert!(env::var("API_KEY").is_err());
+        });
+    }
+
+    #[test]
+    fn fills_a_profile_from_the_default_section() {
+        let default = Config {
+            api_url: Some("https://default".to_string()),
+            default_project_id: Some("default-project".to_string()),
+            default_tags: HashMap::from([
+                ("team".to_string(), "default".to_string()),
+                ("owner".to_string(), "ops".to_string()),
+            ]),
+            ..Config::default()
+        };
+        let profile = Config {
+            api_url: Some("https://staging".to_string()),
+            default_tags: HashMap::from([("team".to_string(), "staging".to_string())]),
+            ..Config::default()
+        };
+
+        let merged = profile.or(&default);
+        assert_eq!(merged.api_url.as_deref(), Some("https://staging"));
+        assert_eq!(
+            merged.default_project_id.as_deref(),
+            Some("default-project")
+        );
+        assert_eq!(merged.api_key, None);
+        assert_eq!(merged.default_tags["team"], "staging");
+        assert_eq!(merged.default_tags["owner"], "ops");
+    }
+}
@@ -0,0 +1,110 @@
+use mongodb::bson::doc;
+use rust_sdk::model::artifact::{ArtifactType, Status as ArtifactStatus};
+use std::collections::BTreeMap;
+
+use crate::{delete, enum_to_string, error::DiceCliError, history, output::outln, try_sdk};
+
+/// Tag holding the SHA-256 of the uploaded contents, set when an artifact is created from a file
+pub const CHECKSUM_TAG: &str = "sha256";
+
+/// Active input artifact with the contents hashing to `checksum`, within the project unless
+/// `project_id` is `None`
+pub async fn find_duplicate(
+    project_id: Option<&str>,
+    checksum: &str,
+) -> Result<Option<String>, DiceCliError> {
+    let mut filter = doc! {
+        "artifact_type": enum_to_string(&ArtifactType::Input),
+        "status": enum_to_string(&ArtifactStatus::Active),
+        format!("tags.{}", CHECKSUM_TAG): checksum,
+    };
+    if let Some(project_id) = project_id {
+        filter.insert("entity_id", project_id);
+    }
+
+    let artifacts = try_sdk("list input artifacts", || {
+        rust_sdk::api::artifact::list(filter.clone())
+    })
+    .await?;
+
+    // The newest one is reused, IDs sort by creation time
+    Ok(artifacts
+        .into_iter()
+        .map(|artifact| artifact.id)
+        .max()
+        .map(|id| id.to_string()))
+}
+
+pub async fn deduplicate_artifacts(
+    project_id: String,
+    delete_duplicates: bool,
+) -> Result<(), DiceCliError> {
+    let artifacts = try_sdk("list input artifacts", || {
+        rust_sdk::api::artifact::list(doc! {
+            "entity_id": project_id.clone(),
+            "artifact_type": enum_to_string(&ArtifactType::Input),
+            "status": enum_to_string(&ArtifactStatus::Active),
+        })
+    })
+    .await?;
+
+    let mut groups = BTreeMap::new();
+    let mut without_checksum = 0;
+    for artifact in artifacts {
+        match artifact.tags.get(CHECKSUM_TAG) {
+            Some(checksum) => groups
+                .entry(checksum.clone())
+                .or_insert_with(Vec::new)
+                .push(artifact.id),
+            None => without_checksum += 1,
+        }
+    }
+    if without_checksum > 0 {
+        outln!(
+            "{} artifacts have no checksum and were skipped, they were uploaded from a URL, stdin or an older version of the CLI",
+            without_checksum
+        );
+    }
+
+    // Keep the newest artifact of each group, IDs sort by creation time
+    let mut duplicates = vec![];
+    for (checksum, mut ids) in groups {
+        if ids.len() < 2 {
+            continue;
+        }
+        ids.sort();
+        let newest = ids.pop().unwrap();
+        outln!("{}:", checksum);
+        outln!("  {} (newest)", newest);
+        for id in &ids {
+            outln!("  {}", id);
+        }
+        duplicates.extend(ids.into_iter().map(|id| id.to_string()));
+    }
+
+    if duplicates.is_empty() {
+        outln!("No duplicate artifacts found");
+        return Ok(());
+    }
+    if !delete_duplicates {
+        outln!(
+            "{} duplicate artifacts, use --delete-duplicates to delete them",
+            duplicates.len()
+        );
+        return Ok(());
+    }
+
+    for (index, id) in duplicates.iter().enumerate() {
+        if let Err(err) = delete::delete_resource("artifact", id.clone()).await {
+            eprintln!("Could not delete artifact {}: {}", id, err);
+            return Err(DiceCliError::PartiallyDeleted {
+                deleted: index,
+                total: duplicates.len(),
+            });
+        }
+        outln!("Deleted artifact {}", id);
+        history::record("delete-artifact", &[("artifact", id)]);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use mongodb::bson::doc;
+
+use crate::{error::DiceCliError, history, output::outln, stats, try_sdk};
+
+/// A resource scheduled for deletion, as (kind, id)
+type Resource = (&'static str, String);
+
+async fn artifacts_of(entity_id: String) -> Result<Vec<Resource>, DiceCliError> {
+    Ok(try_sdk("list artifacts", || {
+        rust_sdk::api::artifact::list(doc! { "entity_id": entity_id.clone() })
+    })
+    .await?
+    .into_iter()
+    .map(|artifact| ("artifact", artifact.id.to_string()))
+    .collect())
+}
+
+/// Dependents of a job, ordered so that every resource is deleted before its parent
+async fn job_dependents(job_id: String) -> Result<Vec<Resource>, DiceCliError> {
+    let mut dependents = vec![];
+
+    let executions = try_sdk("list job executions", || {
+        rust_sdk::api::job_execution::list(doc! { "job_id": job_id.clone() })
+    })
+    .await?;
+    for execution in executions {
+        dependents.extend(artifacts_of(execution.id.to_string()).await?);
+        dependents.push(("job execution", execution.id.to_string()));
+    }
+    dependents.extend(artifacts_of(job_id).await?);
+
+    Ok(dependents)
+}
+
+async fn project_dependents(project_id: String) -> Result<Vec<Resource>, DiceCliError> {
+    let mut dependents = vec![];
+
+    let jobs = try_sdk("list jobs", || {
+        rust_sdk::api::job::list(doc! { "project_id": project_id.clone() })
+    })
+    .await?;
+    for job in jobs {
+        dependents.extend(job_dependents(job.id.to_string()).await?);
+        dependents.push(("job", job.id.to_string()));
+    }
+
+    let runtimes = try_sdk("list runtimes", || {
+        rust_sdk::api::runtime::list(doc! { "project_id": project_id.clone() })
+    })
+    .await?;
+    dependents.extend(
+        runtimes
+            .into_iter()
+            .map(|runtime| ("runtime", runtime.id.to_string())),
+    );
+    dependents.extend(artifacts_of(project_id).await?);
+
+    Ok(dependents)
+}
+
+pub async fn delete_resource(kind: &str, id: String) -> Result<(), DiceCliError> {
+    match kind {
+        "artifact" => {
+            try_sdk("delete artifact", || {
+                rust_sdk::api::artifact::delete(id.clone())
+            })
+            .await
+        }
+        "job execution" => {
+            try_sdk("delete job execution", || {
+                rust_sdk::api::job_execution::delete(id.clone())
+            })
+            .await
+        }
+        "job" => try_sdk("delete job", || rust_sdk::api::job::delete(id.clone())).await,
+        "runtime" => {
+            try_sdk("delete runtime", || {
+                rust_sdk::api::runtime::delete(id.clone())
+            })
+            .await
+        }
+        "project" => {
+            try_sdk("delete project", || {
+                rust_sdk::api::project::delete(id.clone())
+            })
+            .await
+        }
+        _ => unreachable!("unknown resource kind {}", kind),
+    }
+}
+
+async fn delete_with_dependents(
+    target: Resource,
+    dependents: Vec<Resource>,
+    yes: bool,
+    cascade: bool,
+    dry_run: bool,
+) -> Result<(), DiceCliError> {
+    outln!("Deleting {} {} would delete:", target.0, target.1);
+    for (kind, id) in dependents.iter().chain([&target]) {
+        outln!("  {} {}", kind, id);
+    }
+    if dry_run {
+        return Ok(());
+    }
+
+    if !dependents.is_empty() && !cascade {
+        return Err(DiceCliError::HasDependents {
+            target: format!("{} {}", target.0, target.1),
+            count: dependents.len(),
+        });
+    }
+    if dependents.is_empty() && !yes && !cascade {
+        return Err(DiceCliError::NotConfirmed {
+            target: format!("{} {}", target.0, target.1),
+        });
+    }
+
+    let plan: Vec<Resource> = dependents.into_iter().chain([target]).collect();
+    for (index, (kind, id)) in plan.iter().enumerate() {
+        match delete_resource(kind, id.clone()).await {
+            Ok(_) => {
+                outln!("Deleted {} {}", kind, id);
+                history::record(&format!("delete-{}", kind.replace(' ', "-")), &[(kind, id)]);
+            }
+            Err(err) => {
+                eprintln!("Could not delete {} {}: {}", kind, id, err);
+                eprintln!("Not deleted:");
+                for (kind, id) in &plan[index..] {
+                    eprintln!("  {} {}", kind, id);
+                }
+                return Err(DiceCliError::PartiallyDeleted {
+                    deleted: index,
+                    total: plan.len(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn delete_job(
+    job_id: String,
+    yes: bool,
+    cascade: bool,
+    dry_run: bool,
+) -> Result<(), DiceCliError> {
+    let dependents = job_dependents(job_id.clone()).await?;
+
+    delete_with_dependents(("job", job_id), dependents, yes, cascade, dry_run).await
+}
+
+pub async fn delete_runtime(
+    runtime_id: String,
+    yes: bool,
+    cascade: bool,
+    dry_run: bool,
+) -> Result<(), DiceCliError> {
+    stats::runtime_usage(runtime_id.clone())
+        .await?
+        .warn_unfinished();
+
+    let mut dependents = vec![];
+    let jobs = try_sdk("list jobs", || {
+        rust_sdk::api::job::list(doc! { "runtime_id": runtime_id.clone() })
+    })
+    .await?;
+    for job in jobs {
+        dependents.extend(job_dependents(job.id.to_string()).await?);
+        dependents.push(("job", job.id.to_string()));
+    }
+
+    delete_with_dependents(("runtime", runtime_id), dependents, yes, cascade, dry_run).await
+}
+
+pub async fn delete_project(
+    project_id: String,
+    yes: bool,
+    cascade: bool,
+    dry_run: bool,
+) -> Result<(), DiceCliError> {
+    let dependents = project_dependents(project_id.clone()).await?;
+
+    delete_with_dependents(("project", project_id), dependents, yes, cascade, dry_run).await
+}
@@ -0,0 +1,198 @@
+use console::style;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use uuid::Uuid;
+
+use crate::{
+    download_output_artifacts,
+    error::DiceCliError,
+    format_bytes,
+    output::{out, outln},
+    DownloadOptions,
+};
+
+/// Files under `dir` by their path relative to `root`, with their size
+fn list_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut BTreeMap<PathBuf, u64>,
+) -> Result<(), DiceCliError> {
+    let read_failed = |source| DiceCliError::Io {
+        action: "read",
+        path: dir.to_path_buf(),
+        source,
+    };
+
+    for entry in fs::read_dir(dir).map_err(read_failed)? {
+        let entry = entry.map_err(read_failed)?;
+        let path = entry.path();
+        let metadata = entry.metadata().map_err(read_failed)?;
+        if metadata.is_dir() {
+            list_files(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            files.insert(relative, metadata.len());
+        }
+    }
+
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, DiceCliError> {
+    fs::read(path).map_err(|source| DiceCliError::Io {
+        action: "read",
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Size change from `a` to `b`, e.g. `+1.5 KB`
+fn size_change(a: u64, b: u64) -> String {
+    if b >= a {
+        format!("+{}", format_bytes(b - a))
+    } else {
+        format!("-{}", format_bytes(a - b))
+    }
+}
+
+/// Prints the line diff of two files with the system `diff`
+fn print_file_diff(a: &Path, b: &Path) -> Result<(), DiceCliError> {
+    let output = Command::new("diff")
+        .arg("-u")
+        .arg(a)
+        .arg(b)
+        .output()
+        .map_err(|source| DiceCliError::ProgramNotFound {
+            program: "diff".to_string(),
+            source,
+        })?;
+    out!("{}", String::from_utf8_lossy(&output.stdout));
+    Ok(())
+}
+
+/// Downloads the outputs of both executions and compares them file by file. Artifacts are
+/// matched by name, as each is extracted into a directory named after it. With `verbose` the
+/// line diff of each changed file is printed too.
+async fn compare(
+    id_a: &str,
+    id_b: &str,
+    work_dir: &Path,
+    options: &DownloadOptions,
+) -> Result<(), DiceCliError> {
+    let (dir_a, dir_b) = (work_dir.join("a"), work_dir.join("b"));
+    // Each artifact needs its own directory to be matched, and an execution without
+    // outputs is compared as empty
+    let options = DownloadOptions {
+        flatten: false,
+        allow_empty: true,
+        force: true,
+        ..options.clone()
+    };
+    for (id, dir) in [(id_a, &dir_a), (id_b, &dir_b)] {
+        download_output_artifacts(
+            id.to_string(),
+            dir.clone(),
+            work_dir.to_path_buf(),
+            options.clone(),
+            vec![],
+            None,
+            None,
+        )
+        .await?;
+    }
+
+    let mut files_a = BTreeMap::new();
+    let mut files_b = BTreeMap::new();
+    for (dir, files) in [(&dir_a, &mut files_a), (&dir_b, &mut files_b)] {
+        if dir.exists() {
+            list_files(dir, dir, files)?;
+        }
+    }
+
+    let paths: BTreeSet<&PathBuf> = files_a.keys().chain(files_b.keys()).collect();
+    let mut only_a = vec![];
+    let mut only_b = vec![];
+    let mut changed = vec![];
+    let mut identical = 0;
+    for path in paths {
+        match (files_a.get(path), files_b.get(path)) {
+            (Some(&size), None) => only_a.push((path, size)),
+            (None, Some(&size)) => only_b.push((path, size)),
+            (Some(&size_a), Some(&size_b)) => {
+                if size_a == size_b && read(&dir_a.join(path))? == read(&dir_b.join(path))? {
+                    identical += 1;
+                } else {
+                    changed.push((path, size_a, size_b));
+                }
+            }
+            (None, None) => (),
+        }
+    }
+
+    outln!();
+    outln!("A: job execution {}", id_a);
+    outln!("B: job execution {}", id_b);
+    if !only_a.is_empty() {
+        outln!("Only in A ({}):", only_a.len());
+        for (path, size) in &only_a {
+            let line = format!("- {} ({})", path.display(), format_bytes(*size));
+            outln!("  {}", style(line).red());
+        }
+    }
+    if !only_b.is_empty() {
+        outln!("Only in B ({}):", only_b.len());
+        for (path, size) in &only_b {
+            let line = format!("+ {} ({})", path.display(), format_bytes(*size));
+            outln!("  {}", style(line).green());
+        }
+    }
+    if !changed.is_empty() {
+        outln!("Changed ({}):", changed.len());
+        for (path, size_a, size_b) in &changed {
+            outln!(
+                "  {} {} {} -> {} ({})",
+                style("~").yellow(),
+                path.display(),
+                format_bytes(*size_a),
+                format_bytes(*size_b),
+                size_change(*size_a, *size_b)
+            );
+        }
+    }
+    outln!("Identical: {} file(s)", identical);
+
+    if options.verbose {
+        for (path, _, _) in &changed {
+            outln!();
+            print_file_diff(&dir_a.join(path), &dir_b.join(path))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Compares the output artifacts of two job executions, listing the files only one of them
+/// produced and those both produced with different contents. The downloads are deleted
+/// afterwards unless `keep` is set.
+pub async fn diff_job_executions(
+    id_a: String,
+    id_b: String,
+    temp_dir: PathBuf,
+    options: DownloadOptions,
+    keep: bool,
+) -> Result<(), DiceCliError> {
+    let work_dir = temp_dir.join(format!("diff-{}", Uuid::new_v4()));
+    let result = compare(&id_a, &id_b, &work_dir, &options).await;
+
+    if keep {
+        outln!("Kept the downloaded outputs in {}", work_dir.display());
+    } else {
+        let _ = fs::remove_dir_all(&work_dir);
+    }
+
+    result
+}
@@ -0,0 +1,280 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::doc;
+use serde_derive::Serialize;
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use crate::{
+    config, error::DiceCliError, is_directory_dice_runtime, make_program, output::outln, try_sdk,
+    OutputFormat,
+};
+
+/// Free space below which a directory is reported as a warning
+const LOW_DISK_SPACE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Clock difference with the server above which a warning is reported
+const MAX_CLOCK_SKEW_SECONDS: i64 = 60;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Serialize, Debug)]
+struct Check {
+    name: String,
+    status: CheckStatus,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &str, message: String) -> Self {
+        Check {
+            name: name.to_string(),
+            status: CheckStatus::Pass,
+            message,
+            hint: None,
+        }
+    }
+
+    fn warn(name: &str, message: String, hint: &str) -> Self {
+        Check {
+            name: name.to_string(),
+            status: CheckStatus::Warn,
+            message,
+            hint: Some(hint.to_string()),
+        }
+    }
+
+    fn fail(name: &str, message: String, hint: &str) -> Self {
+        Check {
+            name: name.to_string(),
+            status: CheckStatus::Fail,
+            message,
+            hint: Some(hint.to_string()),
+        }
+    }
+}
+
+fn check_configuration() -> Vec<Check> {
+    let mut checks = vec![match config::config_path() {
+        Some(path) if path.exists() => {
+            Check::pass("configuration", format!("loaded {}", path.display()))
+        }
+        _ => Check::warn(
+            "configuration",
+            "no configuration file found".to_string(),
+            "run `dice --init-config` to create one",
+        ),
+    }];
+
+    checks.extend(
+        ["API_URL", "API_KEY"]
+            .iter()
+            .map(|variable| match env::var(variable) {
+                Ok(_) => Check::pass("configuration", format!("{} is set", variable)),
+                Err(_) => Check::fail(
+                    "configuration",
+                    format!("{} is not set", variable),
+                    "run `dice --init-config` or export the DICE API settings in your shell",
+                ),
+            }),
+    );
+
+    checks
+}
+
+async fn check_api() -> Vec<Check> {
+    let api_url = match env::var("API_URL") {
+        Ok(api_url) => api_url,
+        Err(_) => {
+            return vec![Check::fail(
+                "api",
+                "no API endpoint configured".to_string(),
+                "set API_URL to the DICE API endpoint",
+            )]
+        }
+    };
+
+    let mut checks = vec![];
+
+    // Any response at all means the endpoint is reachable
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap();
+    match client.get(&api_url).send().await {
+        Ok(response) => {
+            checks.push(Check::pass(
+                "api",
+                format!("{} is reachable ({})", api_url, response.status()),
+            ));
+            checks.push(check_clock_skew(
+                response.headers().get(reqwest::header::DATE),
+            ));
+        }
+        Err(err) => {
+            checks.push(Check::fail(
+                "api",
+                format!("{} is not reachable: {}", api_url, err),
+                "check API_URL, your network connection and any proxy settings",
+            ));
+            return checks;
+        }
+    }
+
+    checks.push(match ping().await {
+        Ok(_) => Check::pass("authentication", "API credentials accepted".to_string()),
+        Err(_) => Check::fail(
+            "authentication",
+            "an authenticated API call failed".to_string(),
+            "check that API_KEY is valid for this endpoint",
+        ),
+    });
+
+    checks
+}
+
+/// Makes a cheap authenticated call to check the configured endpoint and credentials
+pub async fn ping() -> Result<(), DiceCliError> {
+    try_sdk("reach the DICE API", || async {
+        rust_sdk::api::project::list(doc! { "_id": "" }).await;
+    })
+    .await
+}
+
+fn check_clock_skew(date_header: Option<&reqwest::header::HeaderValue>) -> Check {
+    let server_time = date_header
+        .and_then(|date| date.to_str().ok())
+        .and_then(|date| DateTime::parse_from_rfc2822(date).ok());
+
+    match server_time {
+        Some(server_time) => {
+            let skew = (Utc::now() - server_time.with_timezone(&Utc)).num_seconds();
+            if skew.abs() > MAX_CLOCK_SKEW_SECONDS {
+                Check::warn(
+                    "clock",
+                    format!("local clock differs from the server by {}s", skew),
+                    "synchronize your system clock, presigned upload links are time sensitive",
+                )
+            } else {
+                Check::pass("clock", format!("clock skew is {}s", skew))
+            }
+        }
+        None => Check::warn(
+            "clock",
+            "server did not report its time".to_string(),
+            "clock skew could not be measured, nothing to do",
+        ),
+    }
+}
+
+fn check_runtime_environment() -> Vec<Check> {
+    if !is_directory_dice_runtime(".") {
+        return vec![Check::pass(
+            "runtime",
+            "current directory is not a DICE runtime, skipping build checks".to_string(),
+        )];
+    }
+
+    let mut checks = vec![Check::pass(
+        "runtime",
+        "current directory is a DICE runtime".to_string(),
+    )];
+
+    checks.push(if let Some(make) = make_program() {
+        Check::pass("build tool", format!("{} is installed", make))
+    } else {
+        Check::fail(
+            "build tool",
+            "make was not found on PATH".to_string(),
+            "install make to build runtimes, or pass --build-command",
+        )
+    });
+
+    let installed_targets = Command::new("rustup")
+        .args(["target", "list", "--installed"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned());
+    checks.push(match installed_targets {
+        Ok(targets) if targets.lines().any(|target| target.trim() == "wasm32-wasi") => {
+            Check::pass("wasm target", "wasm32-wasi target is installed".to_string())
+        }
+        Ok(_) => Check::fail(
+            "wasm target",
+            "wasm32-wasi target is not installed".to_string(),
+            "run `rustup target add wasm32-wasi`",
+        ),
+        Err(_) => Check::warn(
+            "wasm target",
+            "rustup was not found, installed targets are unknown".to_string(),
+            "install rustup or make sure the wasm32-wasi target is available",
+        ),
+    });
+
+    checks
+}
+
+fn check_disk_space(name: &str, path: &Path) -> Check {
+    match fs2::available_space(path) {
+        Ok(available) if available < LOW_DISK_SPACE_BYTES => Check::warn(
+            name,
+            format!(
+                "{} has only {} MB free",
+                path.display(),
+                available / 1024 / 1024
+            ),
+            "free up disk space before downloading or building large artifacts",
+        ),
+        Ok(available) => Check::pass(
+            name,
+            format!("{} has {} MB free", path.display(), available / 1024 / 1024),
+        ),
+        Err(err) => Check::warn(
+            name,
+            format!(
+                "could not determine free space of {}: {}",
+                path.display(),
+                err
+            ),
+            "make sure the directory exists and is accessible",
+        ),
+    }
+}
+
+/// Runs the environment and connectivity diagnostics, returning whether all checks passed
+pub async fn doctor(output_dir: PathBuf, format: OutputFormat) -> bool {
+    let mut checks = check_configuration();
+    checks.extend(check_api().await);
+    checks.extend(check_runtime_environment());
+    checks.push(check_disk_space("temp disk space", &env::temp_dir()));
+    checks.push(check_disk_space("output disk space", &output_dir));
+
+    match format {
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&checks).unwrap()),
+        OutputFormat::Table | OutputFormat::Raw => {
+            for check in &checks {
+                let status = match check.status {
+                    CheckStatus::Pass => "PASS",
+                    CheckStatus::Warn => "WARN",
+                    CheckStatus::Fail => "FAIL",
+                };
+                outln!("[{}] {}: {}", status, check.name, check.message);
+                if let Some(hint) = &check.hint {
+                    outln!("       hint: {}", hint);
+                }
+            }
+        }
+    }
+
+    checks.iter().all(|check| check.status != CheckStatus::Fail)
+}
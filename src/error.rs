@@ -0,0 +1,630 @@
+use serde_json::{json, Map, Value};
+use std::{io, path::PathBuf, time::Duration};
+use thiserror::Error;
+
+/// Exit status of the CLI, so scripts can tell failures apart; `dice --help-exit-codes` lists
+/// them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,
+    /// Any failure without a more specific code
+    Failure = 1,
+    InvalidArguments = 2,
+    ApiError = 3,
+    NetworkError = 4,
+    NotInDiceRuntime = 5,
+    BuildFailed = 6,
+    AuthError = 7,
+}
+
+impl ExitCode {
+    pub const ALL: [ExitCode; 8] = [
+        ExitCode::Success,
+        ExitCode::Failure,
+        ExitCode::InvalidArguments,
+        ExitCode::ApiError,
+        ExitCode::NetworkError,
+        ExitCode::NotInDiceRuntime,
+        ExitCode::BuildFailed,
+        ExitCode::AuthError,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ExitCode::Success => "the command succeeded",
+            ExitCode::Failure => "the command failed for a reason without its own code, e.g. a file could not be written or part of a batch failed",
+            ExitCode::InvalidArguments => "an argument is missing, malformed or over a limit, or the profile does not exist",
+            ExitCode::ApiError => "the DICE API rejected the request or could not complete it",
+            ExitCode::NetworkError => "the DICE API or the artifact storage could not be reached",
+            ExitCode::NotInDiceRuntime => "the directory is not a DICE runtime or runtime workspace",
+            ExitCode::BuildFailed => "building a runtime failed",
+            ExitCode::AuthError => "the API key was rejected",
+        }
+    }
+}
+
+/// Errors reported to the user, each followed by a hint on what to check
+#[derive(Error, Debug)]
+pub enum DiceCliError {
+    #[error("{flag} is required\nhint: run `dice --help` to see the arguments of each command")]
+    MissingArgument { flag: &'static str },
+
+    #[error("{message}\nhint: run `dice --help` to see the accepted values")]
+    InvalidArgument { message: String },
+
+    #[error("{} is not a DICE runtime\nhint: run this command from the root of a runtime repository, it contains a .dice/runtime.toml manifest that `dice --init-runtime` creates", cwd.display())]
+    NotInDiceRuntime { cwd: PathBuf },
+
+    #[error("{} is not a DICE runtime workspace\nhint: --all reads the runtime directories from a .dice-workspace file with `members = [\"runtimes/a\", ...]`, run it from the workspace root or give the root with --runtime-dir", root.display())]
+    NotInDiceWorkspace { root: PathBuf },
+
+    #[error("the runtime build failed: `{command}` exited with code {exit_code}\nhint: the full build output is in {}, check that `make build` works in the runtime repository", log.display())]
+    BuildFailed {
+        command: String,
+        exit_code: i32,
+        log: PathBuf,
+    },
+
+    #[error("could not run {program}: {source}\nhint: make sure {program} is installed and on your PATH")]
+    ProgramNotFound { program: String, source: io::Error },
+
+    #[error("could not connect to {uri_host}: {source}\nhint: check your network connection and any proxy settings")]
+    ConnectionFailed {
+        uri_host: String,
+        source: reqwest::Error,
+    },
+
+    #[error("upload to {uri_host} was rejected with status {status}: {body}\nhint: a 403 usually means the upload link expired or the local clock is off, `dice --doctor` checks the clock")]
+    UploadFailed {
+        uri_host: String,
+        status: u16,
+        body: String,
+    },
+
+    #[error("download from {uri_host} failed with status {status}\nhint: download links expire, run the command again to get fresh ones")]
+    DownloadFailed { uri_host: String, status: u16 },
+
+    #[error("could not {action}: {message}\nhint: check API_URL and API_KEY, `dice --doctor` verifies both")]
+    Api { action: String, message: String },
+
+    #[error("{uri_host} is rate limiting requests\nhint: lower --concurrency or set --rps, or raise --retries and --max-backoff")]
+    RateLimited {
+        uri_host: String,
+        retry_after: Option<u64>,
+    },
+
+    #[error("could not {action} the system keychain: {source}\nhint: check that the keychain is unlocked, or keep the API key in the config file with `dice --init-config`")]
+    Keychain {
+        action: &'static str,
+        source: keyring::Error,
+    },
+
+    #[error("could not {action} {}: {source}\nhint: check that the path exists and that you have access to it", path.display())]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+        source: io::Error,
+    },
+
+    #[error("could not parse {}: {message}\nhint: check the contents of the file against the documentation of the flag", path.display())]
+    Parse { path: PathBuf, message: String },
+
+    #[error("{flag} {} does not exist\nhint: relative paths are resolved from the current directory, and ~ and $VARIABLES are expanded", path.display())]
+    PathNotFound { flag: &'static str, path: PathBuf },
+
+    #[error("{} already exists\nhint: edit the existing file or remove it first", path.display())]
+    AlreadyExists { path: PathBuf },
+
+    #[error("could not determine the home directory\nhint: set HOME, the config file is kept in ~/.dice/config.toml")]
+    NoHomeDirectory,
+
+    #[error("profile {profile} does not exist\nhint: add it with --add-profile --name {profile}, --list-profiles shows the existing ones")]
+    ProfileNotFound { profile: String },
+
+    #[error("no data was piped to stdin\nhint: pipe the artifact contents, e.g. `generate_data | dice --create-artifact-from-stdin --project-id <id>`")]
+    NoStdin,
+
+    #[error("could not prompt for {prompt}: {source}\nhint: without a terminal, pass --api-url and --api-key or set DICE_API_URL and DICE_API_KEY; `dice --add-profile --name default` writes the config file without prompts")]
+    Prompt {
+        prompt: &'static str,
+        source: io::Error,
+    },
+
+    #[error("runtime {runtime_id} has {running} running job executions\nhint: wait for them to finish or use --force to replace the runtime anyway")]
+    RuntimeInUse { runtime_id: String, running: usize },
+
+    #[error("{} is not a valid runtime: {reason}\nhint: check that the build produced a wasm32-wasi binary, or use --skip-validation for other runtime formats", path.display())]
+    InvalidRuntime { path: PathBuf, reason: String },
+
+    #[error("{} does not exist, the runtime has not been built\nhint: run the build first, or drop --skip-build to build it now", path.display())]
+    RuntimeNotBuilt { path: PathBuf },
+
+    #[error("input artifact {artifact_id} {reason}\nhint: upload it again or pick another one, `dice --list-input-artifacts` lists the active input artifacts of a project")]
+    ArtifactUnavailable { artifact_id: String, reason: String },
+
+    #[error("the job refers to {} unusable IDs:\n  {}\nhint: `dice --list-input-artifacts` lists the active input artifacts of a project, --no-precheck leaves the check to the API", problems.len(), problems.join("\n  "))]
+    InvalidReferences { problems: Vec<String> },
+
+    #[error("{} is in both artifact {other_artifact_id} and artifact {artifact_id}\nhint: drop --flatten to extract each artifact into a directory of its own", path.display())]
+    FileCollision {
+        path: PathBuf,
+        artifact_id: String,
+        other_artifact_id: String,
+    },
+
+    #[error("could not read the archive of artifact {artifact_id}: {message}\nhint: the artifact may not be a tar archive, or the download was interrupted")]
+    UnreadableArchive {
+        artifact_id: String,
+        message: String,
+    },
+
+    #[error("job execution {id} is {status}, not Completed\nhint: use --wait to download once it finishes, or --allow-status to download the artifacts it has produced so far")]
+    JobExecutionNotCompleted { id: String, status: String },
+
+    #[error("job execution {id} was still {status} after {seconds} seconds\nhint: raise --wait-timeout, `dice --get-job-execution` shows its status")]
+    WaitTimedOut {
+        id: String,
+        status: String,
+        seconds: u64,
+    },
+
+    #[error("not enough disk space in {}: {needed} needed, {available} available\nhint: free up disk space, choose another --output-dir or use --ignore-space-check", path.display())]
+    NotEnoughDiskSpace {
+        path: PathBuf,
+        needed: String,
+        available: String,
+    },
+
+    #[error("{failed} of {total} job executions could not be created\nhint: see the errors above, only the failed job executions need to be retried")]
+    JobExecutionsFailed { failed: usize, total: usize },
+
+    #[error("none of the {listed} output artifacts match the filters\nhint: check --filter-tag and --filter-name, or pass --allow-empty when no match is expected")]
+    NoArtifactsMatched { listed: usize },
+
+    #[error("{failed} of {total} artifacts could not be downloaded\nhint: see the errors above, --on-error retry retries each failed download")]
+    DownloadsFailed { failed: usize, total: usize },
+
+    #[error("{} is {}, over the upload limit of {}\nhint: raise --artifact-size-limit if the upload is expected to be this large", path.display(), crate::format_bytes(*bytes), crate::format_bytes(*limit))]
+    UploadTooLarge {
+        path: PathBuf,
+        bytes: u64,
+        limit: u64,
+    },
+
+    #[error("{failed} of {total} files could not be uploaded\nhint: see the errors above, run the command again for the failed files")]
+    UploadsFailed { failed: usize, total: usize },
+
+    #[error("{} changed since the upload was queued\nhint: upload the current file without the queue, then delete the queued operation listed by `dice --list-queue`", path.display())]
+    QueuedFileChanged { path: PathBuf },
+
+    #[error("{failed} of {total} queued operations could not be replayed\nhint: see the errors above, the failed operations stay queued until they are deleted")]
+    QueueFlushFailed { failed: usize, total: usize },
+
+    #[error("{failed} of {total} artifacts could not be updated\nhint: see the errors above, the other artifacts were updated")]
+    ArtifactUpdatesFailed { failed: usize, total: usize },
+
+    #[error("{failed} of {total} runtimes of the workspace could not be created\nhint: see the errors above, rerun with --if-not-exists to only create the missing ones")]
+    WorkspaceRuntimesFailed { failed: usize, total: usize },
+
+    #[error("{target} has {count} dependent resources\nhint: use --cascade to delete them too, --dry-run lists them")]
+    HasDependents { target: String, count: usize },
+
+    #[error("deletion of {target} was not confirmed\nhint: use --yes to confirm the deletion")]
+    NotConfirmed { target: String },
+
+    #[error("deleted {deleted} of {total} resources\nhint: the resources listed above were not deleted, run the command again to retry")]
+    PartiallyDeleted { deleted: usize, total: usize },
+
+    #[error("project {project_id} has the same description and tags as the imported one\nhint: use --force to import it again anyway")]
+    ProjectExists { project_id: String },
+
+    #[error("unsupported export schema version {found} (expected {expected})\nhint: export the project again with this version of the CLI")]
+    UnsupportedSchemaVersion { found: u32, expected: u32 },
+
+    #[error("could not determine the DICE API version: {message}\nhint: check API_URL, older DICE deployments may not report their version")]
+    ServerVersionUnavailable { message: String },
+
+    #[error("could not fetch the CLI release: {message}\nhint: check the version given with --release, or set DICE_RELEASES_URL to reach a mirror of the releases")]
+    ReleaseUnavailable { message: String },
+
+    #[error("the download of dice-cli {version} does not match its checksum (expected {expected}, got {actual})\nhint: the installed CLI was left unchanged, try again or download the release by hand")]
+    UpdateChecksumMismatch {
+        version: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("{what} is {length} characters long, more than the limit of {limit}\nhint: check for a shell expansion gone wrong, or raise limits.{setting} in the config file if the API accepts more")]
+    TooLong {
+        what: String,
+        length: usize,
+        limit: usize,
+        setting: &'static str,
+    },
+
+    #[error("{count} {what} given, more than the limit of {limit}\nhint: check for a shell expansion gone wrong, or raise limits.{setting} in the config file if the API accepts more")]
+    TooMany {
+        what: &'static str,
+        count: usize,
+        limit: usize,
+        setting: &'static str,
+    },
+
+    #[error("{} is not a usable CA certificate: {message}\nhint: --ca-cert and DICE_CA_CERT_PATH take a PEM file with one or more `-----BEGIN CERTIFICATE-----` blocks, ask the administrator of the DICE deployment for its CA certificate", path.display())]
+    InvalidCaCert { path: PathBuf, message: String },
+
+    #[error("some environment checks failed\nhint: follow the hints of the failed checks above")]
+    ChecksFailed,
+
+    #[error("no output file of the smoke test matches its input\nhint: the test runtime must copy its input to its output, the downloaded output is in {}", path.display())]
+    SmokeTestOutputMismatch { path: PathBuf },
+
+    #[error("{message}\nhint: this is a bug in the CLI, please report it")]
+    Internal { message: String },
+
+    #[error("artifact {artifact_id}: {source}")]
+    Artifact {
+        artifact_id: String,
+        source: Box<DiceCliError>,
+    },
+}
+
+impl DiceCliError {
+    /// Whether the API or storage could not be reached at all: no connection or a timeout
+    pub fn is_unreachable(&self) -> bool {
+        match self {
+            DiceCliError::ConnectionFailed { source, .. } => {
+                source.is_connect() || source.is_timeout()
+            }
+            // The SDK panics with the message of the underlying reqwest error
+            DiceCliError::Api { message, .. } => {
+                message.contains("error sending request") || message.contains("operation timed out")
+            }
+            DiceCliError::Artifact { source, .. } => source.is_unreachable(),
+            _ => false,
+        }
+    }
+
+    /// Whether the API rejected the credentials
+    pub fn is_unauthorized(&self) -> bool {
+        match self {
+            DiceCliError::Api { message, .. } => matches!(api_status(message), Some(401 | 403)),
+            DiceCliError::Artifact { source, .. } => source.is_unauthorized(),
+            _ => false,
+        }
+    }
+
+    /// Exit status the CLI ends with after this error
+    pub fn exit_code(&self) -> ExitCode {
+        if self.is_unauthorized() {
+            return ExitCode::AuthError;
+        }
+        if self.is_unreachable() {
+            return ExitCode::NetworkError;
+        }
+        match self {
+            DiceCliError::MissingArgument { .. }
+            | DiceCliError::InvalidArgument { .. }
+            | DiceCliError::PathNotFound { .. }
+            | DiceCliError::TooLong { .. }
+            | DiceCliError::TooMany { .. }
+            | DiceCliError::InvalidCaCert { .. }
+            | DiceCliError::ProfileNotFound { .. }
+            | DiceCliError::Prompt { .. } => ExitCode::InvalidArguments,
+            DiceCliError::Api { .. }
+            | DiceCliError::RateLimited { .. }
+            | DiceCliError::InvalidReferences { .. } => ExitCode::ApiError,
+            DiceCliError::ConnectionFailed { .. }
+            | DiceCliError::UploadFailed { .. }
+            | DiceCliError::DownloadFailed { .. } => ExitCode::NetworkError,
+            DiceCliError::NotInDiceRuntime { .. } | DiceCliError::NotInDiceWorkspace { .. } => {
+                ExitCode::NotInDiceRuntime
+            }
+            DiceCliError::BuildFailed { .. } => ExitCode::BuildFailed,
+            DiceCliError::Artifact { source, .. } => source.exit_code(),
+            _ => ExitCode::Failure,
+        }
+    }
+
+    /// Whether the failure is transient: a connection problem, a timeout or a 5xx response
+    pub fn is_retryable(&self) -> bool {
+        if self.is_unreachable() {
+            return true;
+        }
+        match self {
+            DiceCliError::UploadFailed { status, .. }
+            | DiceCliError::DownloadFailed { status, .. } => (500..600).contains(status),
+            DiceCliError::RateLimited { .. } => true,
+            DiceCliError::Api { message, .. } => {
+                matches!(api_status(message), Some(429 | 500..=599))
+            }
+            DiceCliError::Artifact { source, .. } => source.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Whether a create can be sent again without risking a duplicate: the connection was
+    /// never made, or the server refused the request for the rate limit. A timeout or a 5xx
+    /// may come after the server already created the resource.
+    pub fn is_retryable_create(&self) -> bool {
+        match self {
+            DiceCliError::ConnectionFailed { source, .. } => source.is_connect(),
+            DiceCliError::RateLimited { .. } => true,
+            // The SDK panics with the message of the underlying reqwest error
+            DiceCliError::Api { message, .. } => {
+                message.contains("error trying to connect") || api_status(message) == Some(429)
+            }
+            DiceCliError::Artifact { source, .. } => source.is_retryable_create(),
+            _ => false,
+        }
+    }
+
+    /// Whether the server asked for fewer requests, so retries should back off
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            DiceCliError::RateLimited { .. } => true,
+            DiceCliError::Api { message, .. } => api_status(message) == Some(429),
+            DiceCliError::Artifact { source, .. } => source.is_rate_limited(),
+            _ => false,
+        }
+    }
+
+    /// How long the server asked to wait before the next request, from `Retry-After`
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            DiceCliError::RateLimited { retry_after, .. } => retry_after.map(Duration::from_secs),
+            DiceCliError::Artifact { source, .. } => source.retry_after(),
+            _ => None,
+        }
+    }
+
+    /// Stable identifier of the kind of failure, for machine consumers
+    pub fn code(&self) -> &'static str {
+        match self {
+            DiceCliError::MissingArgument { .. } => "missing_argument",
+            DiceCliError::InvalidArgument { .. } => "invalid_argument",
+            DiceCliError::NotInDiceRuntime { .. } => "not_in_dice_runtime",
+            DiceCliError::NotInDiceWorkspace { .. } => "not_in_dice_workspace",
+            DiceCliError::BuildFailed { .. } => "build_failed",
+            DiceCliError::ProgramNotFound { .. } => "program_not_found",
+            DiceCliError::ConnectionFailed { .. } => "connection_failed",
+            DiceCliError::UploadFailed { .. } => "upload_failed",
+            DiceCliError::DownloadFailed { .. } => "download_failed",
+            DiceCliError::Api { .. } => "api_error",
+            DiceCliError::RateLimited { .. } => "rate_limited",
+            DiceCliError::Keychain { .. } => "keychain_error",
+            DiceCliError::Io { .. } => "io_error",
+            DiceCliError::Parse { .. } => "parse_error",
+            DiceCliError::PathNotFound { .. } => "path_not_found",
+            DiceCliError::AlreadyExists { .. } => "already_exists",
+            DiceCliError::NoHomeDirectory => "no_home_directory",
+            DiceCliError::ProfileNotFound { .. } => "profile_not_found",
+            DiceCliError::NoStdin => "no_stdin",
+            DiceCliError::Prompt { .. } => "prompt_failed",
+            DiceCliError::RuntimeInUse { .. } => "runtime_in_use",
+            DiceCliError::InvalidRuntime { .. } => "invalid_runtime",
+            DiceCliError::RuntimeNotBuilt { .. } => "runtime_not_built",
+            DiceCliError::ArtifactUnavailable { .. } => "artifact_unavailable",
+            DiceCliError::InvalidReferences { .. } => "invalid_references",
+            DiceCliError::UnreadableArchive { .. } => "unreadable_archive",
+            DiceCliError::FileCollision { .. } => "file_collision",
+            DiceCliError::JobExecutionNotCompleted { .. } => "job_execution_not_completed",
+            DiceCliError::WaitTimedOut { .. } => "wait_timed_out",
+            DiceCliError::NotEnoughDiskSpace { .. } => "not_enough_disk_space",
+            DiceCliError::JobExecutionsFailed { .. } => "job_executions_failed",
+            DiceCliError::DownloadsFailed { .. } => "downloads_failed",
+            DiceCliError::UploadsFailed { .. } => "uploads_failed",
+            DiceCliError::QueuedFileChanged { .. } => "queued_file_changed",
+            DiceCliError::QueueFlushFailed { .. } => "queue_flush_failed",
+            DiceCliError::ArtifactUpdatesFailed { .. } => "artifact_updates_failed",
+            DiceCliError::WorkspaceRuntimesFailed { .. } => "workspace_runtimes_failed",
+            DiceCliError::UploadTooLarge { .. } => "upload_too_large",
+            DiceCliError::NoArtifactsMatched { .. } => "no_artifacts_matched",
+            DiceCliError::HasDependents { .. } => "has_dependents",
+            DiceCliError::NotConfirmed { .. } => "not_confirmed",
+            DiceCliError::PartiallyDeleted { .. } => "partially_deleted",
+            DiceCliError::UnsupportedSchemaVersion { .. } => "unsupported_schema_version",
+            DiceCliError::ServerVersionUnavailable { .. } => "server_version_unavailable",
+            DiceCliError::ReleaseUnavailable { .. } => "release_unavailable",
+            DiceCliError::UpdateChecksumMismatch { .. } => "update_checksum_mismatch",
+            DiceCliError::ProjectExists { .. } => "project_exists",
+            DiceCliError::ChecksFailed => "checks_failed",
+            DiceCliError::SmokeTestOutputMismatch { .. } => "smoke_test_output_mismatch",
+            DiceCliError::TooLong { .. } => "too_long",
+            DiceCliError::TooMany { .. } => "too_many",
+            DiceCliError::InvalidCaCert { .. } => "invalid_ca_cert",
+            DiceCliError::Internal { .. } => "internal_error",
+            // The failure itself is more useful than the fact that an artifact was involved
+            DiceCliError::Artifact { source, .. } => source.code(),
+        }
+    }
+
+    /// Identifiers of the resources and files involved in the failure
+    fn ids(&self, ids: &mut Map<String, Value>) {
+        let mut insert = |key: &str, value: String| {
+            ids.insert(key.to_string(), Value::String(value));
+        };
+        match self {
+            DiceCliError::NotInDiceRuntime { cwd: path }
+            | DiceCliError::NotInDiceWorkspace { root: path }
+            | DiceCliError::Io { path, .. }
+            | DiceCliError::Parse { path, .. }
+            | DiceCliError::AlreadyExists { path }
+            | DiceCliError::PathNotFound { path, .. }
+            | DiceCliError::InvalidRuntime { path, .. }
+            | DiceCliError::RuntimeNotBuilt { path }
+            | DiceCliError::UploadTooLarge { path, .. }
+            | DiceCliError::QueuedFileChanged { path }
+            | DiceCliError::SmokeTestOutputMismatch { path }
+            | DiceCliError::InvalidCaCert { path, .. }
+            | DiceCliError::NotEnoughDiskSpace { path, .. } => {
+                insert("path", path.display().to_string())
+            }
+            DiceCliError::ConnectionFailed { uri_host, .. }
+            | DiceCliError::UploadFailed { uri_host, .. }
+            | DiceCliError::DownloadFailed { uri_host, .. }
+            | DiceCliError::RateLimited { uri_host, .. } => insert("uri_host", uri_host.clone()),
+            DiceCliError::RuntimeInUse { runtime_id, .. } => {
+                insert("runtime_id", runtime_id.clone())
+            }
+            DiceCliError::JobExecutionNotCompleted { id, .. }
+            | DiceCliError::WaitTimedOut { id, .. } => insert("job_execution_id", id.clone()),
+            DiceCliError::HasDependents { target, .. } | DiceCliError::NotConfirmed { target } => {
+                insert("target", target.clone())
+            }
+            DiceCliError::ProjectExists { project_id } => insert("project_id", project_id.clone()),
+            DiceCliError::ProfileNotFound { profile } => insert("profile", profile.clone()),
+            DiceCliError::FileCollision {
+                path, artifact_id, ..
+            } => {
+                insert("path", path.display().to_string());
+                insert("artifact_id", artifact_id.clone());
+            }
+            DiceCliError::UnreadableArchive { artifact_id, .. }
+            | DiceCliError::ArtifactUnavailable { artifact_id, .. } => {
+                insert("artifact_id", artifact_id.clone())
+            }
+            DiceCliError::Artifact {
+                artifact_id,
+                source,
+            } => {
+                insert("artifact_id", artifact_id.clone());
+                source.ids(ids);
+            }
+            _ => (),
+        }
+    }
+
+    /// The error as a JSON object under an `error` key, with the message and hint split apart
+    pub fn to_json(&self) -> Value {
+        let rendered = self.to_string();
+        let (message, hint) = match rendered.rsplit_once("\nhint: ") {
+            Some((message, hint)) => (message, Some(hint)),
+            None => (rendered.as_str(), None),
+        };
+        let mut ids = Map::new();
+        self.ids(&mut ids);
+
+        json!({
+            "error": {
+                "code": self.code(),
+                "exit_code": self.exit_code() as i32,
+                "message": message,
+                "hint": hint,
+                "ids": ids,
+            }
+        })
+    }
+}
+
+/// Host of a URL, used to identify a storage backend without leaking presigned query strings
+pub fn uri_host(uri: &str) -> String {
+    reqwest::Url::parse(uri)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "unknown host".to_string())
+}
+
+/// Status code of the API response an SDK call failed with. The SDK panics with the
+/// underlying reqwest error, shown through `Display` (`HTTP status client error (429 Too Many
+/// Requests) for url ...`) or, when it unwraps the error, through `Debug` (`kind: Status(429)`).
+fn api_status(message: &str) -> Option<u16> {
+    let code = match message.split_once("HTTP status ") {
+        Some((_, rest)) => rest.split_once(" error (")?.1,
+        None => message.split_once("Status(")?.1,
+    };
+    code.get(..3)?.parse().ok()
+}
+
+/// Unwraps a required argument, naming the flag when it is missing
+pub fn required<T>(value: Option<T>, flag: &'static str) -> Result<T, DiceCliError> {
+    value.ok_or(DiceCliError::MissingArgument { flag })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server;
+
+    fn api_error(message: &str) -> DiceCliError {
+        DiceCliError::Api {
+            action: "create job".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn retries_creates_only_when_the_server_never_saw_them() {
+        let connect = api_error(
+            "error sending request for url (http://dice/jobs): error trying to connect: \
+             tcp connect error: Connection refused",
+        );
+        let timeout =
+            api_error("error sending request for url (http://dice/jobs): operation timed out");
+        let server_error = api_error("HTTP status server error (502 Bad Gateway)");
+        let rate_limited = api_error("HTTP status client error (429 Too Many Requests)");
+
+        assert!(connect.is_retryable_create());
+        assert!(rate_limited.is_retryable_create());
+        assert!(!timeout.is_retryable_create());
+        assert!(!server_error.is_retryable_create());
+        // Reads still retry all of them
+        assert!(connect.is_retryable() && timeout.is_retryable() && server_error.is_retryable());
+    }
+
+    /// The reqwest errors of a response with `status`, as the SDK panics with them: shown
+    /// through `Display`, and through `Debug` as `unwrap` does
+    async fn sdk_panic_messages(status: &str) -> [String; 2] {
+        let (listener, uri) = test_server::bind("/jobs").await;
+        let response = format!(
+            "HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            status
+        );
+        let server = tokio::spawn(async move {
+            let (socket, _) = test_server::accept(&listener).await;
+            test_server::respond(socket, &response).await;
+        });
+
+        let err = reqwest::get(uri)
+            .await
+            .unwrap()
+            .error_for_status()
+            .unwrap_err();
+        server.await.unwrap();
+        [
+            err.to_string(),
+            format!("called `Result::unwrap()` on an `Err` value: {:?}", err),
+        ]
+    }
+
+    #[tokio::test]
+    async fn classifies_sdk_failures_by_the_status_of_the_response() {
+        for message in sdk_panic_messages("429 Too Many Requests").await {
+            let err = api_error(&message);
+            assert!(err.is_rate_limited(), "{}", message);
+            assert!(
+                err.is_retryable() && err.is_retryable_create(),
+                "{}",
+                message
+            );
+        }
+        for message in sdk_panic_messages("503 Service Unavailable").await {
+            let err = api_error(&message);
+            assert!(err.is_retryable(), "{}", message);
+            assert!(
+                !err.is_rate_limited() && !err.is_retryable_create(),
+                "{}",
+                message
+            );
+        }
+        for message in sdk_panic_messages("401 Unauthorized").await {
+            let err = api_error(&message);
+            assert!(err.is_unauthorized(), "{}", message);
+            assert!(!err.is_retryable(), "{}", message);
+        }
+        for message in sdk_panic_messages("404 Not Found").await {
+            let err = api_error(&message);
+            assert!(!err.is_retryable() && !err.is_unauthorized(), "{}", message);
+        }
+    }
+}
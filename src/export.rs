@@ -0,0 +1,282 @@
+use dialoguer::Confirm;
+use mongodb::bson::doc;
+use rust_sdk::model::{
+    artifact::{Artifact, ArtifactType},
+    job::{CreateJobDTO, Job},
+    project::{CreateProjectDTO, Project},
+    runtime::{CreateRuntimeDTO, Runtime},
+};
+use serde_derive::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    config, create_input_artifact_from_body, error::DiceCliError, get_for_download, history, http,
+    output::outln, print_table, retry, try_sdk, try_sdk_create, write_atomically,
+};
+
+/// Version of the export file layout, bumped on incompatible changes
+const SCHEMA_VERSION: u32 = 1;
+
+/// Metadata snapshot of a project, without any artifact contents
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProjectExport {
+    pub schema_version: u32,
+    pub project: Project,
+    pub runtimes: Vec<Runtime>,
+    pub jobs: Vec<Job>,
+    pub artifacts: Vec<Artifact>,
+}
+
+async fn fetch_project(project_id: String) -> Result<ProjectExport, DiceCliError> {
+    let project = try_sdk("fetch project", || {
+        rust_sdk::api::project::get(project_id.clone())
+    })
+    .await?;
+    let runtimes = try_sdk("list runtimes", || {
+        rust_sdk::api::runtime::list(doc! { "project_id": project_id.clone() })
+    })
+    .await?;
+    let jobs = try_sdk("list jobs", || {
+        rust_sdk::api::job::list(doc! { "project_id": project_id.clone() })
+    })
+    .await?;
+    let artifacts = try_sdk("list artifacts", || {
+        rust_sdk::api::artifact::list(doc! { "entity_id": project_id.clone() })
+    })
+    .await?;
+
+    Ok(ProjectExport {
+        schema_version: SCHEMA_VERSION,
+        project,
+        runtimes,
+        jobs,
+        artifacts,
+    })
+}
+
+pub async fn export_project(project_id: String, output_path: &Path) -> Result<(), DiceCliError> {
+    let export = fetch_project(project_id).await?;
+
+    let contents = serde_json::to_string_pretty(&export).unwrap();
+    write_atomically(output_path, contents.as_bytes()).map_err(|source| DiceCliError::Io {
+        action: "write",
+        path: output_path.to_path_buf(),
+        source,
+    })?;
+
+    outln!(
+        "Exported project {} ({} runtimes, {} jobs, {} artifacts) to {}",
+        export.project.id,
+        export.runtimes.len(),
+        export.jobs.len(),
+        export.artifacts.len(),
+        output_path.display()
+    );
+
+    Ok(())
+}
+
+fn read_export(import_path: &Path) -> Result<ProjectExport, DiceCliError> {
+    let contents = fs::read_to_string(import_path).map_err(|source| DiceCliError::Io {
+        action: "read",
+        path: import_path.to_path_buf(),
+        source,
+    })?;
+    let export: ProjectExport =
+        serde_json::from_str(&contents).map_err(|err| DiceCliError::Parse {
+            path: import_path.to_path_buf(),
+            message: err.to_string(),
+        })?;
+
+    if export.schema_version != SCHEMA_VERSION {
+        return Err(DiceCliError::UnsupportedSchemaVersion {
+            found: export.schema_version,
+            expected: SCHEMA_VERSION,
+        });
+    }
+
+    Ok(export)
+}
+
+/// Fails, or asks on a terminal, when a project with the same description and tags exists
+async fn check_not_imported(project: &Project, force: bool) -> Result<(), DiceCliError> {
+    let existing = try_sdk("list projects", || {
+        rust_sdk::api::project::list(doc! { "description": project.description.clone() })
+    })
+    .await?
+    .into_iter()
+    .find(|existing| existing.tags == project.tags);
+    let Some(existing) = existing else {
+        return Ok(());
+    };
+    if force {
+        return Ok(());
+    }
+
+    if atty::is(atty::Stream::Stdin) {
+        let import = Confirm::new()
+            .with_prompt(format!(
+                "Project {} looks like the one being imported, import it again?",
+                existing.id
+            ))
+            .default(false)
+            .interact()
+            .unwrap();
+        if import {
+            return Ok(());
+        }
+    }
+
+    Err(DiceCliError::ProjectExists {
+        project_id: existing.id.to_string(),
+    })
+}
+
+/// Gets download links for the input artifacts of an export from the deployment of
+/// `source_profile`, or the current one. Presigned links work without credentials, so the
+/// artifacts can then be streamed into the current deployment.
+async fn artifact_download_links(
+    artifacts: &[Artifact],
+    source_profile: Option<&str>,
+) -> Result<Vec<(String, String)>, DiceCliError> {
+    match source_profile {
+        Some(profile) => config::with_profile(profile, download_links(artifacts)).await,
+        None => download_links(artifacts).await,
+    }
+}
+
+/// Download links for the input artifacts of an export from the current deployment
+async fn download_links(artifacts: &[Artifact]) -> Result<Vec<(String, String)>, DiceCliError> {
+    let mut links = vec![];
+    for artifact in artifacts {
+        if artifact.artifact_type != ArtifactType::Input {
+            continue;
+        }
+        let artifact_id = artifact.id.to_string();
+        let download_artifact_response = try_sdk("get a download link", || {
+            rust_sdk::api::artifact::download(artifact_id.clone())
+        })
+        .await?;
+        links.push((artifact_id, download_artifact_response.uri));
+    }
+
+    Ok(links)
+}
+
+pub async fn import_project(
+    import_path: &Path,
+    include_artifacts: bool,
+    source_profile: Option<String>,
+    force: bool,
+) -> Result<(), DiceCliError> {
+    let export = read_export(import_path)?;
+    let mut id_mapping: Vec<(&str, String, String)> = vec![];
+
+    // Links are resolved first, so an unreachable source does not leave a partial import
+    let artifact_links = if include_artifacts {
+        artifact_download_links(&export.artifacts, source_profile.as_deref()).await?
+    } else {
+        vec![]
+    };
+    check_not_imported(&export.project, force).await?;
+
+    // Utilizing the rust SDK, re-create the project
+    let project_id = try_sdk_create("create project", || {
+        rust_sdk::api::project::create(CreateProjectDTO {
+            description: export.project.description.clone(),
+            tags: export.project.tags.clone(),
+        })
+    })
+    .await?;
+    id_mapping.push(("project", export.project.id.to_string(), project_id.clone()));
+
+    // Re-create runtime records, their binaries have to be uploaded separately
+    let mut runtime_ids = HashMap::new();
+    for runtime in export.runtimes {
+        let create_runtime_response = try_sdk_create("create runtime", || {
+            rust_sdk::api::runtime::create(CreateRuntimeDTO {
+                name: runtime.name.clone(),
+                project_id: project_id.clone(),
+                tags: runtime.tags.clone(),
+            })
+        })
+        .await?;
+
+        runtime_ids.insert(runtime.id.to_string(), create_runtime_response.id.clone());
+        id_mapping.push((
+            "runtime",
+            runtime.id.to_string(),
+            create_runtime_response.id,
+        ));
+    }
+
+    // Stream the input artifacts from the source deployment into the new project
+    let mut artifact_ids = HashMap::new();
+    for (source_id, uri) in artifact_links {
+        let source_response =
+            retry::with_configured_retry(|| get_for_download(http::client(), &uri)).await?;
+        let content_type = source_response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let content_length = source_response.content_length();
+        let tags = export
+            .artifacts
+            .iter()
+            .find(|artifact| artifact.id.to_string() == source_id)
+            .map(|artifact| artifact.tags.clone())
+            .unwrap_or_default();
+
+        let artifact_id = create_input_artifact_from_body(
+            project_id.clone(),
+            reqwest::Body::wrap_stream(source_response.bytes_stream()),
+            content_type,
+            content_length,
+            tags,
+        )
+        .await?;
+        artifact_ids.insert(source_id.clone(), artifact_id.clone());
+        id_mapping.push(("artifact", source_id, artifact_id));
+    }
+
+    // Re-create jobs, pointing them at the re-created runtimes and artifacts
+    for job in export.jobs {
+        let runtime_id = runtime_ids
+            .get(&job.runtime_id)
+            .cloned()
+            .unwrap_or(job.runtime_id);
+        let input_artifact_ids: Vec<String> = job
+            .input_artifact_ids
+            .iter()
+            .map(|id| artifact_ids.get(id).unwrap_or(id).clone())
+            .collect();
+
+        let create_job_response = try_sdk_create("create job", || {
+            rust_sdk::api::job::create(CreateJobDTO {
+                project_id: project_id.clone(),
+                runtime_id: runtime_id.clone(),
+                input_artifact_ids: input_artifact_ids.clone(),
+                tags: job.tags.clone(),
+            })
+        })
+        .await?;
+
+        id_mapping.push(("job", job.id.to_string(), create_job_response.id));
+    }
+
+    history::record("import-project", &[("project", &project_id)]);
+
+    print_table(
+        &["TYPE", "OLD ID", "NEW ID"],
+        id_mapping
+            .into_iter()
+            .map(|(kind, old_id, new_id)| vec![kind.to_string(), old_id, new_id])
+            .collect(),
+    );
+    if !runtime_ids.is_empty() {
+        outln!("Runtime binaries were not copied, upload them with --upload-runtime");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,564 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate};
+use mongodb::bson::{Bson, Document};
+use std::fmt;
+
+use crate::{contains_text, creation_bound, error::DiceCliError, NAME_TAG};
+
+/// Fields a `--filter` expression can compare, listed when an unknown one is used
+const FIELDS: &str = "status, name, created, tags.<key>";
+
+/// Characters that start an operator and so end an unquoted word
+const OPERATOR_CHARS: &str = "=!<>~";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Field {
+    Status,
+    /// The name tag
+    Name,
+    /// Creation time, read from the timestamp in the ID
+    Created,
+    Tag(String),
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Field::Status => write!(f, "status"),
+            Field::Name => write!(f, "name"),
+            Field::Created => write!(f, "created"),
+            Field::Tag(key) => write!(f, "tags.{}", key),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    /// Contains the value, ignoring case
+    Contains,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Contains => "~",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+        }
+    }
+
+    fn from_symbol(symbol: &str) -> Option<Op> {
+        match symbol {
+            "=" | "==" => Some(Op::Eq),
+            "!=" => Some(Op::Ne),
+            "~" => Some(Op::Contains),
+            ">" => Some(Op::Gt),
+            ">=" => Some(Op::Ge),
+            "<" => Some(Op::Lt),
+            "<=" => Some(Op::Le),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Condition {
+    field: Field,
+    op: Op,
+    value: String,
+}
+
+/// Conditions a listed resource must all meet, from `--filter` and the dedicated filter flags
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Operator(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Word(text) | Token::Quoted(text) | Token::Operator(text) => {
+                write!(f, "\"{}\"", text)
+            }
+        }
+    }
+}
+
+fn invalid(message: String) -> DiceCliError {
+    DiceCliError::InvalidArgument {
+        message: format!("invalid --filter: {}", message),
+    }
+}
+
+/// Splits an expression into words, quoted values and operators
+fn tokenize(expression: &str) -> Result<Vec<Token>, DiceCliError> {
+    let mut tokens = vec![];
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some('\\') => match chars.next() {
+                        Some(escaped) => value.push(escaped),
+                        None => return Err(invalid("unterminated quoted value".to_string())),
+                    },
+                    Some(next) if next == c => break,
+                    Some(next) => value.push(next),
+                    None => return Err(invalid("unterminated quoted value".to_string())),
+                }
+            }
+            tokens.push(Token::Quoted(value));
+        } else if OPERATOR_CHARS.contains(c) {
+            let mut operator = String::new();
+            while let Some(&next) = chars.peek() {
+                if !OPERATOR_CHARS.contains(next) {
+                    break;
+                }
+                operator.push(next);
+                chars.next();
+            }
+            tokens.push(Token::Operator(operator));
+        } else {
+            let mut word = String::new();
+            while let Some(&next) = chars.peek() {
+                if next.is_whitespace() || OPERATOR_CHARS.contains(next) || "\"'".contains(next) {
+                    break;
+                }
+                word.push(next);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_field(name: &str) -> Result<Field, DiceCliError> {
+    match name {
+        "status" => Ok(Field::Status),
+        "name" => Ok(Field::Name),
+        "created" => Ok(Field::Created),
+        // Only the creation time is stored, in the ID
+        "updated" => Err(invalid(
+            "the API does not record when resources are updated, filter on created instead"
+                .to_string(),
+        )),
+        _ => match name.strip_prefix("tags.") {
+            Some(key) if !key.is_empty() => Ok(Field::Tag(key.to_string())),
+            _ => Err(invalid(format!(
+                "unknown field \"{}\", expected one of {}",
+                name, FIELDS
+            ))),
+        },
+    }
+}
+
+/// Reads an RFC 3339 date, or a plain `YYYY-MM-DD` day in UTC. A day compared with `<=` or
+/// `>` stands for its last second, so `created<=2024-01-31` includes the whole day.
+fn parse_date(value: &str, op: Op) -> Result<DateTime<FixedOffset>, DiceCliError> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date);
+    }
+    let day = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        invalid(format!(
+            "expected a date such as 2024-01-31 or 2024-01-31T12:00:00Z, got \"{}\"",
+            value
+        ))
+    })?;
+    let start = day.and_hms_opt(0, 0, 0).unwrap().and_utc().fixed_offset();
+    Ok(match op {
+        Op::Le | Op::Gt => start + Duration::days(1) - Duration::seconds(1),
+        _ => start,
+    })
+}
+
+impl Filter {
+    /// Parses conditions of the form `field op value` joined by `and`, e.g.
+    /// `status=active and tags.team=genomics and created>2024-01-01`
+    pub fn parse(expression: &str) -> Result<Filter, DiceCliError> {
+        let mut filter = Filter::default();
+        let mut tokens = tokenize(expression)?.into_iter();
+
+        loop {
+            let field = match tokens.next() {
+                Some(Token::Word(name)) => parse_field(&name)?,
+                Some(token) => return Err(invalid(format!("expected a field, got {}", token))),
+                None => return Err(invalid("expected a condition".to_string())),
+            };
+            let op = match tokens.next() {
+                Some(Token::Operator(symbol)) => Op::from_symbol(&symbol).ok_or_else(|| {
+                    invalid(format!(
+                        "unknown operator \"{}\", expected =, !=, ~, <, <=, > or >=",
+                        symbol
+                    ))
+                })?,
+                _ => return Err(invalid(format!("expected an operator after {}", field))),
+            };
+            let value = match tokens.next() {
+                Some(Token::Word(value)) | Some(Token::Quoted(value)) => value,
+                _ => {
+                    return Err(invalid(format!(
+                        "expected a value after {}{}",
+                        field,
+                        op.symbol()
+                    )))
+                }
+            };
+            filter = filter.and(field, op, value);
+
+            match tokens.next() {
+                None => return Ok(filter),
+                Some(Token::Word(word)) if word.eq_ignore_ascii_case("and") => (),
+                Some(token) => {
+                    return Err(invalid(format!(
+                        "expected \"and\" between conditions, got {}",
+                        token
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Adds a condition, as the dedicated filter flags do
+    pub fn and(mut self, field: Field, op: Op, value: impl Into<String>) -> Filter {
+        self.conditions.push(Condition {
+            field,
+            op,
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Whether any condition compares `field`
+    pub fn constrains(&self, field: &Field) -> bool {
+        self.conditions
+            .iter()
+            .any(|condition| &condition.field == field)
+    }
+
+    /// Compiles the conditions into a filter document for the SDK list functions; status values
+    /// are checked and spelled the way the API stores them by `status`
+    pub fn to_document(
+        &self,
+        status: impl Fn(&str) -> Result<String, String>,
+    ) -> Result<Document, DiceCliError> {
+        // Conditions on the same field share one document of operators
+        let mut fields: Vec<(String, Document)> = vec![];
+
+        for Condition { field, op, value } in &self.conditions {
+            let unsupported = || {
+                let expected = match field {
+                    Field::Status => "= or !=",
+                    Field::Created => "<, <=, > or >=",
+                    Field::Name | Field::Tag(_) => "=, != or ~",
+                };
+                invalid(format!(
+                    "{} cannot be compared with {}, use {}",
+                    field,
+                    op.symbol(),
+                    expected
+                ))
+            };
+            let operator = match op {
+                Op::Eq => "$eq",
+                Op::Ne => "$ne",
+                Op::Gt => "$gt",
+                Op::Ge => "$gte",
+                Op::Lt => "$lt",
+                Op::Le => "$lte",
+                Op::Contains => "$regex",
+            }
+            .to_string();
+
+            let key = match field {
+                Field::Status => "status".to_string(),
+                Field::Name => format!("tags.{}", NAME_TAG),
+                Field::Created => "_id".to_string(),
+                Field::Tag(key) => format!("tags.{}", key),
+            };
+            let operands: Vec<(String, Bson)> = match (field, op) {
+                (Field::Status, Op::Eq | Op::Ne) => {
+                    let status =
+                        status(value).map_err(|err| invalid(format!("{} for status", err)))?;
+                    vec![(operator, status.into())]
+                }
+                (Field::Name | Field::Tag(_), Op::Eq | Op::Ne) => {
+                    vec![(operator, value.clone().into())]
+                }
+                (Field::Name | Field::Tag(_), Op::Contains) => {
+                    contains_text(value).into_iter().collect()
+                }
+                // The whole second is included by `<=` and excluded by `>`
+                (Field::Created, Op::Gt | Op::Le) => {
+                    vec![(
+                        operator,
+                        creation_bound(parse_date(value, *op)?, 0xff).into(),
+                    )]
+                }
+                (Field::Created, Op::Ge | Op::Lt) => {
+                    vec![(
+                        operator,
+                        creation_bound(parse_date(value, *op)?, 0x00).into(),
+                    )]
+                }
+                _ => return Err(unsupported()),
+            };
+
+            let position = match fields.iter().position(|(existing, _)| *existing == key) {
+                Some(position) => position,
+                None => {
+                    fields.push((key, Document::new()));
+                    fields.len() - 1
+                }
+            };
+            for (name, operand) in operands {
+                if fields[position].1.insert(name, operand).is_some() {
+                    return Err(invalid(format!(
+                        "{} is compared with {} more than once",
+                        field,
+                        op.symbol()
+                    )));
+                }
+            }
+        }
+
+        Ok(fields
+            .into_iter()
+            .map(|(key, operators)| (key, Bson::Document(operators)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{doc, oid::ObjectId};
+
+    fn status(value: &str) -> Result<String, String> {
+        match value {
+            "active" => Ok("Active".to_string()),
+            "inactive" => Ok("Inactive".to_string()),
+            _ => Err(format!("unknown value \"{}\"", value)),
+        }
+    }
+
+    fn compile(expression: &str) -> Result<Document, DiceCliError> {
+        Filter::parse(expression)?.to_document(status)
+    }
+
+    fn error_message(result: Result<Document, DiceCliError>) -> String {
+        match result {
+            Err(DiceCliError::InvalidArgument { message }) => message,
+            other => panic!("expected an invalid argument, got {:?}", other),
+        }
+    }
+
+    fn bound(date: &str, fill: u8) -> ObjectId {
+        creation_bound(DateTime::parse_from_rfc3339(date).unwrap(), fill)
+    }
+
+    #[test]
+    fn compiles_equality_and_inequality() {
+        assert_eq!(
+            compile("status=active").unwrap(),
+            doc! { "status": { "$eq": "Active" } }
+        );
+        assert_eq!(
+            compile("status == inactive").unwrap(),
+            doc! { "status": { "$eq": "Inactive" } }
+        );
+        assert_eq!(
+            compile("tags.team!=genomics").unwrap(),
+            doc! { "tags.team": { "$ne": "genomics" } }
+        );
+    }
+
+    #[test]
+    fn compiles_contains_as_case_insensitive_regex() {
+        assert_eq!(
+            compile("name~run.1").unwrap(),
+            doc! { format!("tags.{}", NAME_TAG): { "$regex": "run\\.1", "$options": "i" } }
+        );
+    }
+
+    #[test]
+    fn chains_conditions_with_and() {
+        assert_eq!(
+            compile("status=active and tags.team=genomics AND name!=old").unwrap(),
+            doc! {
+                "status": { "$eq": "Active" },
+                "tags.team": { "$eq": "genomics" },
+                format!("tags.{}", NAME_TAG): { "$ne": "old" },
+            }
+        );
+        assert!(error_message(compile("status=active or name=a"))
+            .contains("expected \"and\" between conditions"));
+        assert!(error_message(compile("status=active and")).contains("expected a condition"));
+    }
+
+    #[test]
+    fn merges_operators_on_the_same_field() {
+        assert_eq!(
+            compile("tags.team=genomics and tags.team!=old").unwrap(),
+            doc! { "tags.team": { "$eq": "genomics", "$ne": "old" } }
+        );
+        let (start, end) = ("2024-01-01T00:00:00Z", "2024-02-01T00:00:00Z");
+        assert_eq!(
+            compile(&format!("created>={} and created<{}", start, end)).unwrap(),
+            doc! { "_id": {
+                "$gte": creation_bound(DateTime::parse_from_rfc3339(start).unwrap(), 0x00),
+                "$lt": creation_bound(DateTime::parse_from_rfc3339(end).unwrap(), 0x00),
+            } }
+        );
+    }
+
+    #[test]
+    fn rejects_updated_as_no_update_time_is_stored() {
+        for expression in [
+            "updated>2024-01-01",
+            "status=active and updated<=2024-01-31",
+        ] {
+            let message = error_message(compile(expression));
+            assert!(message.contains("filter on created instead"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn reads_quoted_values_with_spaces_and_escapes() {
+        assert_eq!(
+            compile("name=\"my run\" and tags.note='it\\'s done'").unwrap(),
+            doc! {
+                format!("tags.{}", NAME_TAG): { "$eq": "my run" },
+                "tags.note": { "$eq": "it's done" },
+            }
+        );
+        assert_eq!(
+            compile(r#"tags.path="C:\\data \"raw\"""#).unwrap(),
+            doc! { "tags.path": { "$eq": "C:\\data \"raw\"" } }
+        );
+        // Operators and the other quote are plain text inside quotes
+        assert_eq!(
+            compile("tags.expr='a>=b \"c\"'").unwrap(),
+            doc! { "tags.expr": { "$eq": "a>=b \"c\"" } }
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        for expression in ["name=\"my run", "name='my run", "name=\"my run\\"] {
+            assert!(
+                error_message(compile(expression)).contains("unterminated quoted value"),
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn lists_the_fields_on_an_unknown_field() {
+        let message = error_message(compile("owner=me"));
+        assert!(message.contains("unknown field \"owner\""), "{}", message);
+        assert!(message.contains(FIELDS), "{}", message);
+        assert!(error_message(compile("tags.=x")).contains(FIELDS));
+    }
+
+    #[test]
+    fn rejects_unknown_operators_and_missing_values() {
+        assert!(error_message(compile("name=~x")).contains("unknown operator \"=~\""));
+        assert!(error_message(compile("name")).contains("expected an operator after name"));
+        assert!(error_message(compile("name=")).contains("expected a value after name="));
+    }
+
+    #[test]
+    fn rejects_operators_a_field_does_not_support() {
+        assert!(error_message(compile("status~act")).contains("status cannot be compared with ~"));
+        assert!(error_message(compile("created=2024-01-01")).contains("use <, <=, > or >="));
+        assert!(error_message(compile("name>a")).contains("use =, != or ~"));
+        assert!(error_message(compile("status=sleeping")).contains("for status"));
+    }
+
+    #[test]
+    fn bounds_created_days_in_utc() {
+        let start = "2024-01-31T00:00:00Z";
+        let end = "2024-01-31T23:59:59Z";
+        assert_eq!(
+            compile("created>=2024-01-31").unwrap(),
+            doc! { "_id": { "$gte": bound(start, 0x00) } }
+        );
+        assert_eq!(
+            compile("created<2024-01-31").unwrap(),
+            doc! { "_id": { "$lt": bound(start, 0x00) } }
+        );
+        // `<=` and `>` stand for the last second of the day, which the ID bound then includes
+        assert_eq!(
+            compile("created<=2024-01-31").unwrap(),
+            doc! { "_id": { "$lte": bound(end, 0xff) } }
+        );
+        assert_eq!(
+            compile("created>2024-01-31").unwrap(),
+            doc! { "_id": { "$gt": bound(end, 0xff) } }
+        );
+        assert!(error_message(compile("created>yesterday")).contains("expected a date"));
+    }
+
+    #[test]
+    fn keeps_full_timestamps_as_given() {
+        assert_eq!(
+            compile("created<=2024-01-31T12:30:00+02:00").unwrap(),
+            doc! { "_id": { "$lte": bound("2024-01-31T10:30:00Z", 0xff) } }
+        );
+    }
+
+    #[test]
+    fn rejects_a_repeated_operator() {
+        for expression in [
+            "status=active and status=inactive",
+            "name~a and name~b",
+            "created>2024-01-01 and created>2024-02-01",
+        ] {
+            assert!(
+                error_message(compile(expression)).contains("more than once"),
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn adds_conditions_from_flags() {
+        let filter = Filter::parse("status=active").unwrap().and(
+            Field::Tag("team".to_string()),
+            Op::Eq,
+            "genomics",
+        );
+        assert!(filter.constrains(&Field::Status));
+        assert!(filter.constrains(&Field::Tag("team".to_string())));
+        assert!(!filter.constrains(&Field::Name));
+        assert_eq!(
+            filter.to_document(status).unwrap(),
+            doc! { "status": { "$eq": "Active" }, "tags.team": { "$eq": "genomics" } }
+        );
+    }
+}
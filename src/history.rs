@@ -0,0 +1,101 @@
+use chrono::Utc;
+use directories::ProjectDirs;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use crate::{config, output::outln, print_table, OutputFormat};
+
+/// A successful mutating operation, stored one per line in the history file
+#[derive(Serialize, Deserialize, Debug)]
+struct HistoryRecord {
+    timestamp: String,
+    action: String,
+    ids: BTreeMap<String, String>,
+    profile: String,
+}
+
+/// Location of the history file, `~/.local/share/dice/history.jsonl` on Linux
+fn history_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "dice").map(|dirs| dirs.data_dir().join("history.jsonl"))
+}
+
+fn append(record: &HistoryRecord) -> io::Result<()> {
+    let path = history_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Records a successful operation. This is best-effort and only warns on failure
+/// so an unwritable home directory never fails the operation itself.
+pub fn record(action: &str, ids: &[(&str, &str)]) {
+    let record = HistoryRecord {
+        timestamp: Utc::now().to_rfc3339(),
+        action: action.to_string(),
+        ids: ids
+            .iter()
+            .map(|(kind, id)| (kind.to_string(), id.to_string()))
+            .collect(),
+        profile: config::active_profile().to_string(),
+    };
+
+    if let Err(err) = append(&record) {
+        eprintln!("Warning: could not write to history: {}", err);
+    }
+}
+
+pub fn show_history(last: Option<usize>, action: Option<String>, format: OutputFormat) {
+    let contents = match history_path().map(fs::read_to_string) {
+        Some(Ok(contents)) => contents,
+        _ => String::new(),
+    };
+
+    let mut records: Vec<HistoryRecord> = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .filter(|record: &HistoryRecord| {
+            action.is_none() || action.as_deref() == Some(record.action.as_str())
+        })
+        .collect();
+    if let Some(last) = last {
+        records = records.split_off(records.len().saturating_sub(last));
+    }
+
+    match format {
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&records).unwrap()),
+        OutputFormat::Table | OutputFormat::Raw => print_table(
+            &["TIMESTAMP", "ACTION", "IDS", "PROFILE"],
+            records
+                .into_iter()
+                .map(|record| {
+                    let ids: Vec<String> = record
+                        .ids
+                        .iter()
+                        .map(|(kind, id)| format!("{}={}", kind, id))
+                        .collect();
+                    vec![
+                        record.timestamp,
+                        record.action,
+                        ids.join(","),
+                        record.profile,
+                    ]
+                })
+                .collect(),
+        ),
+    }
+}
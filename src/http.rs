@@ -0,0 +1,267 @@
+use std::{env, fs, io, path::Path, sync::OnceLock, time::Duration};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::{cache, error::DiceCliError, format_bytes};
+
+/// Client shared by every transfer, so connections and TLS sessions are reused between them
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Proxy every request goes through, resolved once from `--proxy` and the environment
+static PROXY: OnceLock<Option<String>> = OnceLock::new();
+
+/// Root certificates trusted on top of the system ones, from `--ca-cert`
+static CA_CERTIFICATES: OnceLock<Vec<reqwest::Certificate>> = OnceLock::new();
+
+/// Variables naming a proxy, in order of precedence after `--proxy`
+const PROXY_VARIABLES: [&str; 4] = ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"];
+
+/// Uploads allowed at the same time, set from `--max-parallel-uploads` or the config file
+static MAX_PARALLEL_UPLOADS: OnceLock<usize> = OnceLock::new();
+
+/// Limits the uploads running at the same time, so they do not saturate the upload bandwidth
+static UPLOADS: OnceLock<Semaphore> = OnceLock::new();
+
+/// Uploads allowed at the same time unless configured otherwise
+pub const DEFAULT_MAX_PARALLEL_UPLOADS: usize = 4;
+
+/// Largest file that may be uploaded, set from `--artifact-size-limit`
+static UPLOAD_SIZE_LIMIT: OnceLock<u64> = OnceLock::new();
+
+/// Files larger than this are uploaded with a warning
+const LARGE_UPLOAD: u64 = 100 * 1024 * 1024;
+
+/// Idle connections kept open per storage host, enough for many parallel artifact downloads
+const POOL_MAX_IDLE_PER_HOST: usize = 64;
+
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// Seconds a 429 or 503 response asks to wait before the next request; the HTTP date form
+/// is not used by the API or the storage and is ignored
+pub fn retry_after(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// A proxy URL with any password replaced, safe to print
+fn redact_proxy(proxy: &str) -> String {
+    match reqwest::Url::parse(proxy) {
+        Ok(mut url) if url.password().is_some() => {
+            let _ = url.set_password(Some("****"));
+            url.to_string()
+        }
+        _ => proxy.to_string(),
+    }
+}
+
+/// Checks `--proxy`, falling back to the first proxy variable that is set
+fn resolve_proxy(proxy: Option<String>) -> Result<Option<String>, DiceCliError> {
+    if let Some(proxy) = &proxy {
+        reqwest::Proxy::all(proxy.as_str()).map_err(|err| DiceCliError::InvalidArgument {
+            message: format!("invalid --proxy {}: {}", redact_proxy(proxy), err),
+        })?;
+    }
+
+    Ok(proxy.or_else(|| {
+        PROXY_VARIABLES
+            .iter()
+            .find_map(|variable| env::var(variable).ok().filter(|value| !value.is_empty()))
+    }))
+}
+
+/// Routes the requests of the shared client through `--proxy`, or else `HTTPS_PROXY` or
+/// `HTTP_PROXY`, except for the hosts in `NO_PROXY`. The SDK's API calls do not use this client.
+pub fn configure_proxy(proxy: Option<String>, verbose: bool) -> Result<(), DiceCliError> {
+    let proxy = resolve_proxy(proxy)?;
+
+    if verbose {
+        if let Some(proxy) = &proxy {
+            eprintln!("Using proxy {}", redact_proxy(proxy));
+        }
+    }
+    let _ = PROXY.set(proxy);
+    Ok(())
+}
+
+/// Trusts the certificates of the PEM file `path` as well as the system ones in the shared
+/// client, for deployments with a private CA. The file is checked here, before any request
+/// is made.
+pub fn configure_ca_cert(path: Option<&Path>) -> Result<(), DiceCliError> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let invalid = |message: String| DiceCliError::InvalidCaCert {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let pem = fs::read(path).map_err(|source| DiceCliError::Io {
+        action: "read",
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let certificates =
+        reqwest::Certificate::from_pem_bundle(&pem).map_err(|err| invalid(err.to_string()))?;
+    if certificates.is_empty() {
+        return Err(invalid("it contains no PEM certificate".to_string()));
+    }
+
+    let _ = CA_CERTIFICATES.set(certificates);
+    Ok(())
+}
+
+/// Returns the shared HTTP client. Clones share the same connection pool, and HTTPS
+/// connections use HTTP/2 when the server offers it.
+pub fn client() -> reqwest::Client {
+    CLIENT
+        .get_or_init(|| {
+            let mut builder = reqwest::Client::builder()
+                .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+                .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+                .tcp_keepalive(TCP_KEEPALIVE)
+                .http2_adaptive_window(true);
+            // A proxy from the environment that does not parse is left to reqwest to ignore
+            let proxy = PROXY.get().and_then(|proxy| {
+                reqwest::Proxy::all(proxy.as_deref()?)
+                    .ok()
+                    .map(|proxy| proxy.no_proxy(reqwest::NoProxy::from_env()))
+            });
+            if let Some(proxy) = proxy {
+                builder = builder.proxy(proxy);
+            }
+            for certificate in CA_CERTIFICATES.get().into_iter().flatten() {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+            builder.build().expect("Could not create HTTP client")
+        })
+        .clone()
+}
+
+/// Sets how many uploads may run at the same time; only the first call has an effect
+pub fn configure_uploads(max_parallel: usize, size_limit: u64) {
+    let _ = MAX_PARALLEL_UPLOADS.set(max_parallel.max(1));
+    let _ = UPLOAD_SIZE_LIMIT.set(size_limit);
+}
+
+/// Refuses to upload a file, or the files of a directory, larger than `--artifact-size-limit`
+/// and warns about unusually large ones, before any upload link is requested
+pub fn check_upload_size(path: &Path) -> Result<(), DiceCliError> {
+    let read_failed = |source: io::Error| DiceCliError::Io {
+        action: "read",
+        path: path.to_path_buf(),
+        source,
+    };
+    let metadata = fs::metadata(path).map_err(read_failed)?;
+    let bytes = if metadata.is_dir() {
+        cache::usage(path).map_err(read_failed)?.1
+    } else {
+        metadata.len()
+    };
+
+    let limit = UPLOAD_SIZE_LIMIT.get().copied().unwrap_or(u64::MAX);
+    if bytes > limit {
+        return Err(DiceCliError::UploadTooLarge {
+            path: path.to_path_buf(),
+            bytes,
+            limit,
+        });
+    }
+    if bytes > LARGE_UPLOAD {
+        eprintln!(
+            "Warning: {} is {}, an unusually large upload",
+            path.display(),
+            format_bytes(bytes)
+        );
+    }
+
+    Ok(())
+}
+
+pub fn max_parallel_uploads() -> usize {
+    MAX_PARALLEL_UPLOADS
+        .get()
+        .copied()
+        .unwrap_or(DEFAULT_MAX_PARALLEL_UPLOADS)
+}
+
+/// Waits until another upload may start; the upload may run while the permit is held
+pub async fn upload_permit() -> SemaphorePermit<'static> {
+    UPLOADS
+        .get_or_init(|| Semaphore::new(max_parallel_uploads()))
+        .acquire()
+        .await
+        .expect("The upload semaphore is never closed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Self-signed certificate of a test CA
+    const CA_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBhTCCASugAwIBAgIUZ6/UvOZW29+cCdjGbA2SVse4090wCgYIKoZIzj0EAwIw
+FzEVMBMGA1UEAwwMZGljZS10ZXN0LWNhMCAXDTI2MTAxNjA0MjQxN1oYDzIxMjYw
+OTIyMDQyNDE3WjAXMRUwEwYDVQQDDAxkaWNlLXRlc3QtY2EwWTATBgcqhkjOPQIB
+BggqhkjOPQMBBwNCAARPgmTsI+7RE8XD0VT0ujF6w0lOLTTogVwPW9EweZPKP2Uu
+qJ1eqg5ZkMyBjmNblrUJJIUNHpr3+yy9w5ELHrvoo1MwUTAdBgNVHQ4EFgQU65gY
+4780fuVx7kbRf6notQmvex4wHwYDVR0jBBgwFoAU65gY4780fuVx7kbRf6notQmv
+ex4wDwYDVR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNIADBFAiEAmfgXspFffLoW
+jxE95Y0jvncZDjK+OPV4OJQcM8lbHIcCIGAJMl8rsCvMkNKJnxhW2eBmhil8XLSp
+39DCZXKNc4Yy
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn trusts_a_ca_certificate_in_the_client_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        fs::write(&path, CA_CERTIFICATE).unwrap();
+        let ssl_cert_file = env::var_os("SSL_CERT_FILE");
+
+        configure_ca_cert(Some(&path)).unwrap();
+
+        assert_eq!(CA_CERTIFICATES.get().map(Vec::len), Some(1));
+        // The process environment is left alone
+        assert_eq!(env::var_os("SSL_CERT_FILE"), ssl_cert_file);
+    }
+
+    #[test]
+    fn prefers_the_proxy_flag_without_exporting_it() {
+        let variables: Vec<_> = PROXY_VARIABLES.iter().map(env::var_os).collect();
+
+        let proxy = resolve_proxy(Some("http://proxy.example.com:3128".to_string())).unwrap();
+
+        assert_eq!(proxy.as_deref(), Some("http://proxy.example.com:3128"));
+        assert_eq!(
+            PROXY_VARIABLES.iter().map(env::var_os).collect::<Vec<_>>(),
+            variables
+        );
+    }
+
+    #[test]
+    fn rejects_an_invalid_proxy() {
+        assert!(matches!(
+            resolve_proxy(Some("http://[::1".to_string())),
+            Err(DiceCliError::InvalidArgument { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_a_file_without_certificates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        fs::write(&path, "not a certificate").unwrap();
+
+        assert!(matches!(
+            configure_ca_cert(Some(&path)),
+            Err(DiceCliError::InvalidCaCert { .. })
+        ));
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::{TimeZone, Utc};
+use std::io::{self, Read};
+use tokio::sync::mpsc;
+
+use crate::{
+    archive, error::DiceCliError, format_bytes, get_for_download, http, output::outln, print_table,
+    retry, try_sdk, uri_host, OutputFormat,
+};
+
+/// Downloaded chunks buffered ahead of the archive reader
+const CHUNK_BUFFER: usize = 16;
+
+/// Blocking reader over chunks sent from an async download
+struct ChannelReader {
+    receiver: mpsc::Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    position: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.chunk.len() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.position = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let length = buf.len().min(self.chunk.len() - self.position);
+        buf[..length].copy_from_slice(&self.chunk[self.position..self.position + length]);
+        self.position += length;
+
+        Ok(length)
+    }
+}
+
+/// Lists the files inside an artifact by streaming its archive, without writing it to disk.
+/// The download stops as soon as the end of the archive has been read.
+pub async fn inspect_artifact(
+    artifact_id: String,
+    format: OutputFormat,
+) -> Result<(), DiceCliError> {
+    let download_artifact_response = try_sdk("get a download link", || {
+        rust_sdk::api::artifact::download(artifact_id.clone())
+    })
+    .await?;
+    let uri = download_artifact_response.uri;
+    let mut response =
+        retry::with_configured_retry(|| get_for_download(http::client(), &uri)).await?;
+
+    // The tar reader is blocking, so it runs on its own thread fed through a channel
+    let (sender, receiver) = mpsc::channel(CHUNK_BUFFER);
+    let listing = tokio::task::spawn_blocking(move || {
+        archive::list_entries(ChannelReader {
+            receiver,
+            chunk: vec![],
+            position: 0,
+        })
+    });
+
+    while let Some(chunk) =
+        response
+            .chunk()
+            .await
+            .map_err(|source| DiceCliError::ConnectionFailed {
+                uri_host: uri_host(&uri),
+                source,
+            })?
+    {
+        if sender.send(chunk.to_vec()).await.is_err() {
+            break;
+        }
+    }
+    drop(sender);
+
+    let entries = listing
+        .await
+        .map_err(|err| DiceCliError::Internal {
+            message: format!("could not list the archive: {}", err),
+        })?
+        .map_err(|err| DiceCliError::UnreadableArchive {
+            artifact_id: artifact_id.clone(),
+            message: err.to_string(),
+        })?;
+
+    match format {
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&entries).unwrap()),
+        OutputFormat::Table | OutputFormat::Raw => {
+            print_table(
+                &["NAME", "SIZE", "MODIFIED"],
+                entries
+                    .iter()
+                    .map(|entry| {
+                        vec![
+                            if entry.is_dir {
+                                format!("{}/", entry.path.trim_end_matches('/'))
+                            } else {
+                                entry.path.clone()
+                            },
+                            format_bytes(entry.size),
+                            Utc.timestamp_opt(entry.modified as i64, 0)
+                                .single()
+                                .map(|date| date.to_rfc3339())
+                                .unwrap_or_default(),
+                        ]
+                    })
+                    .collect(),
+            );
+            let total: u64 = entries.iter().map(|entry| entry.size).sum();
+            outln!("{} entries, {} total", entries.len(), format_bytes(total));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use keyring::Entry;
+
+use crate::error::DiceCliError;
+
+/// Service the API keys are stored under in the system keychain, one entry per profile
+const SERVICE: &str = "dice-cli";
+
+fn entry(profile: &str) -> Result<Entry, DiceCliError> {
+    Entry::new(SERVICE, profile).map_err(|source| DiceCliError::Keychain {
+        action: "open",
+        source,
+    })
+}
+
+/// API key stored for a profile by `--login`. A missing or unavailable keychain is not an
+/// error, the config file is used instead.
+pub fn api_key(profile: &str) -> Option<String> {
+    entry(profile).ok()?.get_password().ok()
+}
+
+pub fn store_api_key(profile: &str, api_key: &str) -> Result<(), DiceCliError> {
+    entry(profile)?
+        .set_password(api_key)
+        .map_err(|source| DiceCliError::Keychain {
+            action: "store the API key in",
+            source,
+        })
+}
+
+/// Removes the API key of a profile, returning whether one was stored
+pub fn delete_api_key(profile: &str) -> Result<bool, DiceCliError> {
+    match entry(profile)?.delete_password() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(source) => Err(DiceCliError::Keychain {
+            action: "remove the API key from",
+            source,
+        }),
+    }
+}
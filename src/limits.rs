@@ -0,0 +1,140 @@
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::OnceLock,
+};
+
+use crate::{error::DiceCliError, NAME_TAG};
+
+/// Limits of this invocation, set once from the config file
+static LIMITS: OnceLock<Limits> = OnceLock::new();
+
+/// Largest values the CLI sends to the API, so a mistake like a shell expansion gone wrong is
+/// rejected before anything is created. Deployments with other limits set them in a
+/// `[default.limits]` or `[profiles.<name>.limits]` table of the config file.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct Limits {
+    pub max_name_length: usize,
+    pub max_description_length: usize,
+    pub max_tag_key_length: usize,
+    pub max_tag_value_length: usize,
+    pub max_tags: usize,
+    pub max_input_artifacts: usize,
+    /// Length of the parameters of a job or job execution serialized as a JSON object
+    pub max_parameters_length: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_name_length: 1024,
+            max_description_length: 1024,
+            max_tag_key_length: 128,
+            max_tag_value_length: 512,
+            max_tags: 256,
+            max_input_artifacts: 1000,
+            max_parameters_length: 8192,
+        }
+    }
+}
+
+pub fn configure(limits: Limits) {
+    let _ = LIMITS.set(limits);
+}
+
+fn limits() -> Limits {
+    LIMITS.get().copied().unwrap_or_default()
+}
+
+fn check_length(
+    what: impl FnOnce() -> String,
+    value: &str,
+    limit: usize,
+    setting: &'static str,
+) -> Result<(), DiceCliError> {
+    let length = value.chars().count();
+    if length > limit {
+        return Err(DiceCliError::TooLong {
+            what: what(),
+            length,
+            limit,
+            setting,
+        });
+    }
+    Ok(())
+}
+
+pub fn check_name(name: &str) -> Result<(), DiceCliError> {
+    check_length(
+        || "the name".to_string(),
+        name,
+        limits().max_name_length,
+        "max_name_length",
+    )
+}
+
+pub fn check_description(description: &str) -> Result<(), DiceCliError> {
+    check_length(
+        || "the description".to_string(),
+        description,
+        limits().max_description_length,
+        "max_description_length",
+    )
+}
+
+/// Checks the number of tags and the length of each; the name tag is held to the name limit
+pub fn check_tags(tags: &HashMap<String, String>) -> Result<(), DiceCliError> {
+    let limits = limits();
+    if tags.len() > limits.max_tags {
+        return Err(DiceCliError::TooMany {
+            what: "tags",
+            count: tags.len(),
+            limit: limits.max_tags,
+            setting: "max_tags",
+        });
+    }
+
+    for (key, value) in tags {
+        check_length(
+            || format!("the tag key starting with {:.32}", key),
+            key,
+            limits.max_tag_key_length,
+            "max_tag_key_length",
+        )?;
+        if key == NAME_TAG {
+            check_name(value)?;
+        } else {
+            check_length(
+                || format!("the value of tag {}", key),
+                value,
+                limits.max_tag_value_length,
+                "max_tag_value_length",
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn check_input_artifacts(input_artifact_ids: &[String]) -> Result<(), DiceCliError> {
+    let limit = limits().max_input_artifacts;
+    if input_artifact_ids.len() > limit {
+        return Err(DiceCliError::TooMany {
+            what: "input artifacts",
+            count: input_artifact_ids.len(),
+            limit,
+            setting: "max_input_artifacts",
+        });
+    }
+    Ok(())
+}
+
+pub fn check_parameters(parameters: &BTreeMap<String, String>) -> Result<(), DiceCliError> {
+    check_length(
+        || "the JSON of the parameters".to_string(),
+        &serde_json::to_string(parameters).unwrap(),
+        limits().max_parameters_length,
+        "max_parameters_length",
+    )
+}
@@ -0,0 +1,79 @@
+use rust_sdk::model::job_execution::{JobExecutionLog, Status as JobExecutionStatus};
+use serde_json::json;
+
+use crate::{
+    color, error::DiceCliError, output::outln, try_sdk, OutputFormat, JOB_EXECUTION_POLL_INTERVAL,
+};
+
+fn print_log(log: &JobExecutionLog, format: OutputFormat) {
+    let timestamp = log
+        .timestamp
+        .try_to_rfc3339_string()
+        .unwrap_or_else(|_| log.timestamp.to_string());
+    match format {
+        // One object per line, so followed logs can be read as they arrive
+        OutputFormat::Json => outln!(
+            "{}",
+            json!({ "timestamp": timestamp, "message": log.message })
+        ),
+        OutputFormat::Table | OutputFormat::Raw => outln!("{} {}", timestamp, log.message),
+    }
+}
+
+async fn fetch_logs(job_execution_id: &str) -> Result<Vec<JobExecutionLog>, DiceCliError> {
+    try_sdk("fetch job execution logs", || {
+        rust_sdk::api::job_execution::get_logs(job_execution_id.to_string())
+    })
+    .await
+}
+
+/// Prints the last `tail` log lines of a job execution (all of them when `tail` is 0), then
+/// with `follow` keeps printing new lines until the execution is no longer pending or running
+pub async fn job_execution_logs(
+    job_execution_id: String,
+    tail: usize,
+    follow: bool,
+    format: OutputFormat,
+) -> Result<(), DiceCliError> {
+    let logs = fetch_logs(&job_execution_id).await?;
+    let skipped = match tail {
+        0 => 0,
+        tail => logs.len().saturating_sub(tail),
+    };
+    for log in &logs[skipped..] {
+        print_log(log, format);
+    }
+
+    if !follow {
+        return Ok(());
+    }
+
+    let mut printed = logs.len();
+    loop {
+        let job_execution = try_sdk("fetch job execution", || {
+            rust_sdk::api::job_execution::get(job_execution_id.clone())
+        })
+        .await?;
+        let finished = !matches!(
+            job_execution.status,
+            JobExecutionStatus::Pending | JobExecutionStatus::Running
+        );
+
+        // Lines are only ever appended, so everything past those already printed is new
+        let logs = fetch_logs(&job_execution_id).await?;
+        for log in logs.iter().skip(printed) {
+            print_log(log, format);
+        }
+        printed = printed.max(logs.len());
+
+        if finished {
+            eprintln!(
+                "Job execution {} is {}",
+                job_execution_id,
+                color::job_execution_status(&job_execution.status).for_stderr()
+            );
+            return Ok(());
+        }
+        tokio::time::sleep(JOB_EXECUTION_POLL_INTERVAL).await;
+    }
+}
@@ -1,385 +1,5389 @@
-use mongodb::bson::doc;
+mod archive;
+mod buildlog;
+mod cache;
+mod cleanup;
+mod color;
+mod completions;
+mod config;
+mod dedupe;
+mod delete;
+mod diff;
+mod doctor;
+mod error;
+mod export;
+mod filter;
+mod history;
+mod http;
+mod inspect;
+mod keychain;
+mod limits;
+mod logs;
+mod manifest;
+mod output;
+mod paths;
+mod progress;
+mod queue;
+mod receipt;
+mod retry;
+mod scaffold;
+mod search;
+mod smoke;
+mod stats;
+#[cfg(test)]
+mod test_server;
+mod top;
+mod update;
+mod validate;
+mod version;
+
+use mongodb::bson::{doc, oid::ObjectId, Document};
 use rust_sdk::model::{
-    artifact::{ArtifactType, CreateArtifactDTO, Status as ArtifactStatus, UpdateArtifactDTO},
+    artifact::{
+        Artifact, ArtifactType, CreateArtifactDTO, Status as ArtifactStatus, UpdateArtifactDTO,
+    },
     entity::EntityType,
-    job::CreateJobDTO,
-    job_execution::{CreateJobExecutionDTO, Status as JobExecutionStatus},
-    project::CreateProjectDTO,
+    job::{CreateJobDTO, Job},
+    job_execution::{CreateJobExecutionDTO, JobExecution, Status as JobExecutionStatus},
+    project::{CreateProjectDTO, UpdateProjectDTO},
     runtime::{CreateRuntimeDTO, Status as RuntimeStatus, UpdateRuntimeDTO},
 };
+use serde_derive::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     env,
     ffi::OsStr,
     fs::{self, File},
-    io::{self, Cursor, Read},
-    path::PathBuf,
-    process::Command,
+    future::Future,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::{self, Command},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
+use tokio::{sync::Semaphore, task::JoinError};
+use tokio_util::io::ReaderStream;
+
+use archive::CompressionFormat;
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use console::style;
+use error::{required, uri_host, DiceCliError, ExitCode};
+use filter::{Field, Filter, Op};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use output::outln;
+use progress::ProgressMode;
+use receipt::{ArtifactReceipt, DownloadReport, FailedStep, Receipt};
+use sha2::{Digest, Sha256};
+
+/// Output format for command results
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    /// Human readable output
+    Table,
+    /// Machine readable JSON output
+    Json,
+    /// Debug dump of the DICE response for --get-job-execution, human readable output elsewhere
+    Raw,
+}
+
+/// Type of artifact to create
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ArtifactKind {
+    Input,
+    Output,
+}
+
+impl From<ArtifactKind> for ArtifactType {
+    fn from(kind: ArtifactKind) -> Self {
+        match kind {
+            ArtifactKind::Input => ArtifactType::Input,
+            ArtifactKind::Output => ArtifactType::Output,
+        }
+    }
+}
+
+/// Type of entity an artifact is attached to
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum EntityKind {
+    Project,
+    Job,
+    JobExecution,
+}
+
+impl From<EntityKind> for EntityType {
+    fn from(kind: EntityKind) -> Self {
+        match kind {
+            EntityKind::Project => EntityType::Project,
+            EntityKind::Job => EntityType::Job,
+            EntityKind::JobExecution => EntityType::JobExecution,
+        }
+    }
+}
 
-use clap::Parser;
+/// Build system of a new runtime project
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum BuildSystem {
+    /// A Makefile wrapping cargo, built with `make clean` and `make build`
+    Make,
+    /// Plain cargo, the binary is packed into a tarball on upload
+    Cargo,
+}
+
+/// What a batch command does when one of its parallel tasks fails
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OnErrorStrategy {
+    /// Cancel the tasks that have not started yet and fail
+    Stop,
+    /// Run the remaining tasks, then report all failures
+    Continue,
+    /// Retry each task failing on a transient error up to --retries times, then continue
+    Retry,
+}
+
+/// Field used to sort the results of list commands
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum SortBy {
+    Id,
+    Created,
+    Status,
+}
 
 /// DICE Command Line Interface
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    disable_version_flag = true,
+    after_help = "Failures exit with a code per kind of failure, run `dice --help-exit-codes` to list them"
+)]
 struct Arguments {
-    /// Action to create and upload a runtime to DICE
+    /// Action to create and upload a runtime to DICE, built from the current directory or
+    /// read from a prebuilt archive given with --file
     #[arg(short, long)]
     create_runtime: bool,
 
+    /// Action to build the runtime in the current directory and check it as --create-runtime
+    /// would, without contacting DICE, e.g. to gate uploads in CI
+    #[arg(long)]
+    verify_runtime: bool,
+
+    /// Action to upload a new binary for an existing runtime
+    #[arg(long)]
+    upload_runtime: bool,
+
+    /// Action to update the status of an existing runtime
+    #[arg(long)]
+    update_runtime: bool,
+
+    /// Action to set the status of --artifact-id or each of --ids to Active or Inactive.
+    /// Tags and the Archived and Deprecated statuses are not supported yet: the SDK artifact
+    /// update only carries one of these two statuses
+    #[arg(long)]
+    update_artifact: bool,
+
     /// Action to create and upload an input artifact
-    #[arg(short, long)]
+    #[arg(long)]
     create_input_artifact: bool,
 
+    /// Action to create and upload an artifact of any type
+    #[arg(long)]
+    create_artifact: bool,
+
+    /// Action to create one input artifact for each file in --directory
+    #[arg(long)]
+    create_input_artifacts_from_directory: bool,
+
+    /// Action to create an input artifact from the contents of a remote URL
+    #[arg(long)]
+    upload_artifact_from_url: bool,
+
+    /// Action to create an input artifact from data piped through stdin
+    #[arg(long)]
+    create_artifact_from_stdin: bool,
+
+    /// Action to copy an artifact, or the output artifacts of a job execution, into a project
+    /// as input artifacts without storing them locally
+    #[arg(long)]
+    copy_artifact: bool,
+
     /// Action to create a new project
-    #[arg(short, long)]
+    #[arg(long)]
     create_project: bool,
 
+    /// Action to create .dice/project.toml in the current directory, so commands run below it
+    /// default to --project-id, --runtime-id and the given --tag values
+    #[arg(long)]
+    init_project: bool,
+
+    /// Action to update the description and tags of a project
+    #[arg(long)]
+    update_project: bool,
+
     /// Action to create a new job
-    #[arg(short, long)]
+    #[arg(long)]
     create_job: bool,
 
+    /// Action to create a copy of the job given with --job-id, with the inputs and runtime
+    /// changed by --replace-input, --add-input, --remove-input and --runtime-id
+    #[arg(long)]
+    clone_job: bool,
+
+    /// Action to create a new runtime project with a .dice/runtime.toml manifest in the current
+    /// directory
+    #[arg(long)]
+    init_runtime: bool,
+
+    /// Action to create a job definition file skeleton in the current directory
+    #[arg(long)]
+    init_job_file: bool,
+
     /// Action to create a new job execution
-    #[arg(short, long)]
+    #[arg(long)]
     create_job_execution: bool,
 
+    /// Action to create --count job executions of a job in parallel, tagged with their batch_index
+    #[arg(long)]
+    batch_create_job_executions: bool,
+
     /// Action to get an existing job execution
     #[arg(short, long)]
     get_job_execution: bool,
 
+    /// Show a job: its runtime, input artifacts, parameters and tags
+    #[arg(long)]
+    get_job: bool,
+
     /// List pending notifications
     #[arg(short, long)]
     list_notifications: bool,
 
-    /// Download output artifacts for a job execution into the current directory
+    /// Summarize the status and duration of the job executions of a job; a finished execution
+    /// is timed from its creation to its last output artifact, as the API records no end time
+    #[arg(long)]
+    job_execution_stats: bool,
+
+    /// Count the runtimes, jobs, job executions and artifacts of a project
+    #[arg(long)]
+    project_summary: bool,
+
+    /// Count the jobs and job executions that use a runtime
+    #[arg(long)]
+    runtime_stats: bool,
+
+    /// Print the logs of a job execution
+    #[arg(long)]
+    get_job_execution_logs: bool,
+
+    /// Show a view of the executions of a job, refreshed every --interval until all of them
+    /// have finished
+    #[arg(long)]
+    job_execution_top: bool,
+
+    /// Show a table of every execution of a job, refreshed every --interval until all of them
+    /// have finished
+    #[arg(long)]
+    watch_job: bool,
+
+    /// Download output artifacts for a job execution into the current directory. Each artifact
+    /// is extracted into <job execution id>/<artifact name or id>; use --flatten for the
+    /// previous layout with all artifacts in <job execution id>
     #[arg(short, long)]
     download_output_artifacts: bool,
 
+    /// Download output artifacts for every completed job execution of a job
+    #[arg(long)]
+    batch_download_artifacts_by_job: bool,
+
+    /// List the files inside an artifact without downloading it to disk
+    #[arg(long)]
+    inspect_artifact: bool,
+
+    /// List the input artifacts of a project
+    #[arg(long)]
+    list_input_artifacts: bool,
+
+    /// Find projects, runtimes, jobs, job executions and artifacts whose name contains this
+    /// text, ignoring case; projects also match on their description. Like the other actions it
+    /// has no DICE_ variable, so the environment never picks the command to run
+    #[arg(long, value_name = "QUERY")]
+    search: Option<String>,
+
+    /// Only search resources of this type
+    #[arg(long, value_enum, env = "DICE_SEARCH_TYPE")]
+    search_type: Option<search::SearchType>,
+
+    /// Compare the output artifacts of two job executions file by file; --verbose also prints
+    /// the line diff of each changed file
+    #[arg(long)]
+    diff_job_executions: bool,
+
+    /// Report input artifacts of a project with identical contents
+    #[arg(long)]
+    deduplicate_artifacts: bool,
+
+    /// Delete all but the newest artifact of each group of duplicates
+    #[arg(long)]
+    delete_duplicates: bool,
+
+    /// Action to delete a job, listing its dependent resources first
+    #[arg(long)]
+    delete_job: bool,
+
+    /// Action to delete a runtime, listing the jobs that use it first
+    #[arg(long)]
+    delete_runtime: bool,
+
+    /// Action to delete a project, listing its dependent resources first
+    #[arg(long)]
+    delete_project: bool,
+
+    /// Export the metadata of a project to the JSON file given with --export-file
+    #[arg(long)]
+    export_project: bool,
+
+    /// Create a project from a file written by --export-project
+    #[arg(long)]
+    import_project: bool,
+
+    /// With --import-project, also copy the input artifacts of the project
+    #[arg(long)]
+    include_artifacts: bool,
+
+    /// Profile of the deployment a project was exported from, to copy its artifacts with
+    /// --include-artifacts (defaults to the current profile)
+    #[arg(long, env = "DICE_SOURCE_PROFILE")]
+    source_profile: Option<String>,
+
+    /// Show previously performed operations from the local history
+    #[arg(long)]
+    history: bool,
+
+    /// Show the operations saved by --queue that have not been replayed yet
+    #[arg(long)]
+    list_queue: bool,
+
+    /// Replay the operations saved by --queue in order, removing each one that succeeds
+    #[arg(long)]
+    flush_queue: bool,
+
+    /// Interactively create the ~/.dice/config.toml configuration file
+    #[arg(long)]
+    init_config: bool,
+
+    /// Check an API key against the API and store it in the system keychain for the profile,
+    /// prompting for it unless --api-key is given
+    #[arg(long)]
+    login: bool,
+
+    /// Remove the API key of the profile from the system keychain
+    #[arg(long)]
+    logout: bool,
+
+    /// Print the versions of the CLI and of the rust-sdk it was built with
+    #[arg(short = 'V', long)]
+    version: bool,
+
+    /// With --version, also fetch the version of the DICE API and compare it with the CLI's. With
+    /// --self-update, only print whether a newer release is available
+    #[arg(long)]
+    check: bool,
+
+    /// Replace the CLI with its latest release, after verifying the release's checksum
+    #[arg(long)]
+    self_update: bool,
+
+    /// Release --self-update installs instead of the latest, e.g. 0.2.0, also to roll back
+    #[arg(long, env = "DICE_RELEASE")]
+    release: Option<String>,
+
+    /// Print the versions of the CLI, of its rust-sdk and of the DICE API, warning when the
+    /// API is incompatible with the CLI; short for --version --check
+    #[arg(long)]
+    version_info: bool,
+
+    /// Do not warn when the DICE API version does not match the CLI
+    #[arg(long, env = "DICE_NO_VERSION_CHECK")]
+    no_version_check: bool,
+
+    /// Print the resolved configuration and where each value comes from
+    #[arg(long)]
+    show_config: bool,
+
+    /// Print a completion script for the shell, e.g. `source <(dice --completions bash)`. An
+    /// action, so it has no DICE_ variable
+    #[arg(long, value_enum)]
+    completions: Option<clap_complete::Shell>,
+
+    /// List the exit codes of the CLI and what each means
+    #[arg(long)]
+    help_exit_codes: bool,
+
+    /// Print the ids of a resource for shell completion, used by the completion scripts
+    #[arg(long, value_enum, hide = true)]
+    complete_ids: Option<completions::IdResource>,
+
+    /// List the profiles in the configuration file
+    #[arg(long)]
+    list_profiles: bool,
+
+    /// Add or replace the profile --name using --api-url, --api-key and --project-id
+    #[arg(long)]
+    add_profile: bool,
+
+    /// Delete the profile --name from the configuration file
+    #[arg(long)]
+    delete_profile: bool,
+
+    /// Check the environment and connectivity to DICE
+    #[arg(long)]
+    doctor: bool,
+
+    /// Run a job end to end in a temporary project, with the test runtime of --runtime-id or
+    /// smoke_test_runtime_id in the config file, then delete everything it created
+    #[arg(long)]
+    smoke_test: bool,
+
+    /// Keep the project and files --smoke-test created, or the outputs --diff-job-executions
+    /// downloaded, to inspect them
+    #[arg(long)]
+    keep: bool,
+
+    /// Show the location and disk usage of the cache directory
+    #[arg(long)]
+    cache_info: bool,
+
+    /// Remove all intermediate archives from the cache directory
+    #[arg(long)]
+    clean_cache: bool,
+
     /// Name (optional for some commands, required for others)
-    #[arg(short, long)]
+    #[arg(short, long, env = "DICE_NAME")]
     name: Option<String>,
 
     /// Description (optional for some commands, required for others)
-    #[arg(short, long)]
+    #[arg(long, env = "DICE_DESCRIPTION")]
     description: Option<String>,
 
     /// Project ID (optional for some commands, required for others)
-    #[arg(short, long)]
+    #[arg(short, long, env = "DICE_PROJECT_ID")]
     project_id: Option<String>,
 
     /// Job ID (optional for some commands, required for others)
-    #[arg(short, long)]
+    #[arg(short, long, env = "DICE_JOB_ID")]
     job_id: Option<String>,
 
     /// Job execution ID (optional for some commands, required for others)
-    #[arg(short, long)]
+    #[arg(long, env = "DICE_JOB_EXECUTION_ID")]
     job_execution_id: Option<String>,
 
+    /// First job execution compared by --diff-job-executions
+    #[arg(long, env = "DICE_JOB_EXECUTION_ID_A")]
+    job_execution_id_a: Option<String>,
+
+    /// Second job execution compared by --diff-job-executions
+    #[arg(long, env = "DICE_JOB_EXECUTION_ID_B")]
+    job_execution_id_b: Option<String>,
+
+    /// Artifact ID (optional for some commands, required for others)
+    #[arg(long, env = "DICE_ARTIFACT_ID")]
+    artifact_id: Option<String>,
+
+    /// Comma separated IDs --update-artifact applies the same update to
+    #[arg(long, value_delimiter = ',', env = "DICE_IDS")]
+    ids: Vec<String>,
+
     /// Runtime ID (optional for some commands, required for others)
-    #[arg(short, long)]
+    #[arg(short, long, env = "DICE_RUNTIME_ID")]
     runtime_id: Option<String>,
 
     /// File (optional for some commands, required for others)
-    #[arg(short, long, use_value_delimiter = true, value_delimiter = ',')]
+    #[arg(
+        short,
+        long,
+        use_value_delimiter = true,
+        value_delimiter = ',',
+        env = "DICE_INPUT_ARTIFACT_IDS"
+    )]
     input_artifact_ids: Option<Vec<String>>,
 
-    /// File (optional for some commands, required for others)
-    #[arg(short, long)]
+    /// File containing input artifact IDs, one per line or as a JSON array
+    #[arg(long, env = "DICE_INPUT_ARTIFACT_IDS_FILE", value_parser = paths::expand)]
+    input_artifact_ids_file: Option<String>,
+
+    /// Job definition file (TOML, or JSON when ending in .json) used instead of individual flags
+    #[arg(long, env = "DICE_JOB_FILE", value_parser = paths::expand)]
+    job_file: Option<String>,
+
+    /// File (optional for some commands, required for others); `-` reads the artifact of
+    /// --create-input-artifact and --create-artifact from stdin
+    #[arg(short, long, env = "DICE_FILE", value_parser = paths::expand)]
     file: Option<String>,
-}
 
-fn list_files_in_dir(root: &str) -> io::Result<Vec<PathBuf>> {
-    let mut result = vec![];
+    /// Name the data is archived under when --file - reads an artifact from stdin
+    #[arg(long, default_value = "stdin", env = "DICE_STDIN_NAME")]
+    stdin_name: String,
 
-    for path in fs::read_dir(root)? {
-        result.push(path?.path().to_owned());
-    }
+    /// Directory whose files are uploaded by --create-input-artifacts-from-directory
+    #[arg(long, env = "DICE_DIRECTORY", value_parser = paths::expand_path)]
+    directory: Option<PathBuf>,
 
-    Ok(result)
-}
+    /// Also include the files of subdirectories
+    #[arg(long)]
+    recursive: bool,
 
-fn is_directory_dice_runtime(root: &str) -> bool {
-    let mut result = false;
+    /// Status (optional for some commands, required for others)
+    #[arg(long, env = "DICE_STATUS")]
+    status: Option<String>,
 
-    match list_files_in_dir(root) {
-        Ok(files) => files.into_iter().for_each(|path| {
-            if path.to_str().unwrap().contains(".dice") {
-                result = true;
-            };
-        }),
-        Err(_) => (),
-    };
+    /// How artifacts, and runtimes packed from a bare .wasm binary, are compressed before
+    /// they are uploaded; none suits contents that are already compressed
+    #[arg(long, value_enum, default_value_t = CompressionFormat::Gzip, env = "DICE_COMPRESSION")]
+    compression: CompressionFormat,
 
-    result
-}
+    /// Compression level, 0 to 9 for gzip (default 6) and 1 to 22 for zstd (default 3)
+    #[arg(long, env = "DICE_COMPRESSION_LEVEL")]
+    compression_level: Option<i32>,
 
-fn get_current_dir() -> String {
-    let cwd: PathBuf = env::current_dir().unwrap();
-    let name: &OsStr = cwd.file_name().unwrap();
+    /// Command used to build a runtime instead of `make clean` and `make build`
+    #[arg(long, env = "DICE_BUILD_COMMAND")]
+    build_command: Option<String>,
 
-    name.to_string_lossy().into_owned()
+    /// Build system of a new runtime project
+    #[arg(long, value_enum, default_value_t = BuildSystem::Make, env = "DICE_BUILD_SYSTEM")]
+    build_system: BuildSystem,
+
+    /// Upload a runtime without checking that it is a non-empty archive of a wasm32-wasi binary
+    #[arg(long)]
+    skip_validation: bool,
+
+    /// Use the output of an earlier runtime build instead of building again, e.g. when CI
+    /// builds and deploys in separate steps
+    #[arg(long)]
+    skip_build: bool,
+
+    /// Do not print the output of the runtime build, which is still written to .dice-build.log
+    #[arg(long)]
+    quiet_build: bool,
+
+    /// Runtime repository to build, verify and upload, instead of the current directory. The
+    /// build runs there and the manifest's output path is relative to it; the runtime is named
+    /// after the directory unless the manifest or --name gives a name
+    #[arg(
+        long,
+        visible_alias = "path",
+        env = "DICE_RUNTIME_DIR",
+        value_parser = paths::expand_path
+    )]
+    runtime_dir: Option<PathBuf>,
+
+    /// With --create-runtime, create every runtime listed in the .dice-workspace manifest of
+    /// the current directory, or of --runtime-dir. With --list-input-artifacts, also list the
+    /// Inactive artifacts, which are hidden otherwise
+    #[arg(long)]
+    all: bool,
+
+    /// With --create-runtime --all, stop at the first runtime that fails
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Proceed despite conflicts: replace a runtime that running job executions use, download
+    /// output artifacts again that were already extracted, or import a project that exists
+    #[arg(long)]
+    force: bool,
+
+    /// Upload input artifacts even when the project has an active one with the same contents
+    #[arg(long)]
+    force_upload: bool,
+
+    /// Look for input artifacts with the same contents in every project, not only the target
+    #[arg(long)]
+    global_dedup: bool,
+
+    /// Skip hashing files to look for input artifacts with the same contents before uploading
+    #[arg(long)]
+    no_dedup: bool,
+
+    /// Confirm a deletion that has no dependent resources
+    #[arg(long)]
+    yes: bool,
+
+    /// Also delete all dependent resources
+    #[arg(long)]
+    cascade: bool,
+
+    /// Only print what would be done
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Save --create-input-artifact, --create-job and --create-job-execution to a local queue
+    /// when the API is unreachable, to be replayed by --flush-queue
+    #[arg(long, env = "DICE_QUEUE")]
+    queue: bool,
+
+    /// With --create-project, --create-runtime or --create-job, print the ID of a resource
+    /// with the same name (in the same project for runtimes and jobs) instead of creating
+    /// another one
+    #[arg(long)]
+    if_not_exists: bool,
+
+    /// With --create-job, skip checking that the project, runtime and input artifacts exist
+    /// and are active before the job is created
+    #[arg(long)]
+    no_precheck: bool,
+
+    /// Write everything the command prints to this file instead of stdout, created or
+    /// truncated; errors and progress still go to stderr
+    #[arg(long, env = "DICE_OUTPUT_FILE", value_parser = paths::expand_path)]
+    output_file: Option<PathBuf>,
+
+    /// JSON file --export-project writes the project to (--file is accepted too)
+    #[arg(long, env = "DICE_EXPORT_FILE", value_parser = paths::expand_path)]
+    export_file: Option<PathBuf>,
+
+    /// URL of a remote resource (optional for some commands, required for others)
+    #[arg(long, env = "DICE_URL")]
+    url: Option<String>,
+
+    /// Content type to upload artifacts with (defaults to the type of --compression,
+    /// application/gzip, and to application/octet-stream for contents uploaded as they are)
+    #[arg(long, env = "DICE_CONTENT_TYPE")]
+    content_type: Option<String>,
+
+    /// Directory that downloaded output artifacts are saved under
+    #[arg(long, default_value = ".", env = "DICE_OUTPUT_DIR", value_parser = paths::expand_path)]
+    output_dir: PathBuf,
+
+    /// Number of log lines to print from the end of the logs, 0 for all of them
+    #[arg(long, default_value_t = 100, env = "DICE_TAIL")]
+    tail: usize,
+
+    /// Keep printing new log lines until the job execution finishes
+    #[arg(long, env = "DICE_FOLLOW")]
+    follow: bool,
+
+    /// Seconds between refreshes of --job-execution-top and --watch-job
+    #[arg(long, default_value_t = 2, env = "DICE_INTERVAL")]
+    interval: u64,
+
+    /// Keep refreshing --job-execution-top after all executions have finished, until Ctrl-C
+    #[arg(long)]
+    forever: bool,
+
+    /// Write a JSON receipt of the downloaded output artifacts to this file, relative to
+    /// --output-dir
+    #[arg(long, env = "DICE_RECEIPT", value_parser = paths::expand_path)]
+    receipt: Option<PathBuf>,
+
+    /// Directory for intermediate archives (defaults to the user cache directory)
+    #[arg(long, env = "DICE_TEMP_DIR", value_parser = paths::expand_path)]
+    temp_dir: Option<PathBuf>,
+
+    /// Download even if there does not appear to be enough free disk space
+    #[arg(long)]
+    ignore_space_check: bool,
+
+    /// Also download the output artifacts of job executions in these statuses, e.g. failed,running
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = parse_job_execution_status,
+        env = "DICE_ALLOW_STATUS"
+    )]
+    allow_status: Vec<String>,
+
+    /// Wait for a pending or running job execution to finish before downloading
+    #[arg(long)]
+    wait: bool,
+
+    /// Maximum time in seconds to wait for a job execution with --wait or --smoke-test
+    #[arg(long, default_value_t = 3600, env = "DICE_WAIT_TIMEOUT")]
+    wait_timeout: u64,
+
+    /// What to do when a task of a batch fails (defaults to continue when creating job
+    /// executions and stop when downloading artifacts)
+    #[arg(long, value_enum, env = "DICE_ON_ERROR")]
+    on_error: Option<OnErrorStrategy>,
+
+    /// Extract the output artifacts of a job execution side by side into its directory, the
+    /// layout before each artifact got a directory of its own. Two artifacts containing the
+    /// same file or directory then fail the download instead of overwriting each other.
+    #[arg(long)]
+    flatten: bool,
+
+    /// No longer needed: artifacts are extracted into directories named after them unless
+    /// --flatten is given
+    #[arg(long, hide = true)]
+    use_names: bool,
+
+    /// Output format for command results. With json, errors are written to stderr as an
+    /// object with an `error` key holding their code, message, hint and the ids involved
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table, env = "DICE_OUTPUT_FORMAT")]
+    format: OutputFormat,
+
+    /// Print human readable output without colors, which are otherwise used when writing to
+    /// a terminal and NO_COLOR is not set
+    #[arg(long, env = "DICE_NO_COLOR")]
+    no_color: bool,
+
+    /// How progress is reported. With json, progress bars are replaced by one JSON object per
+    /// line on stderr: {"event":"start"|"finish","phase":"build"|"create"|"upload"|"poll"|
+    /// "download"|"extract","id":...} around each step, {"event":"error","phase":...,"id":...,
+    /// "code":...,"message":...} when a step fails, and {"event":"upload_progress"|
+    /// "download_progress","id":...,"bytes":...,"total":...} during transfers. The id is
+    /// omitted when a step has none yet. Other lines on stderr are not JSON.
+    #[arg(long, value_enum, default_value_t = ProgressMode::Bar, env = "DICE_PROGRESS")]
+    progress: ProgressMode,
+
+    /// Number of job executions to create for each job (defaults to 1)
+    #[arg(long, env = "DICE_COUNT")]
+    count: Option<u32>,
+
+    /// Tag key used to label each created job execution with its index
+    #[arg(long, env = "DICE_TAG_INDEX")]
+    tag_index: Option<String>,
+
+    /// File containing one job ID per line to create job executions for
+    #[arg(long, env = "DICE_JOBS_FILE", value_parser = paths::expand)]
+    jobs_file: Option<String>,
+
+    /// Maximum number of API requests to run in parallel for bulk commands
+    #[arg(long, default_value_t = 8, env = "DICE_CONCURRENCY")]
+    concurrency: usize,
+
+    /// Maximum number of output artifacts to download at the same time
+    #[arg(long, default_value_t = 8, env = "DICE_MAX_PARALLEL_DOWNLOADS")]
+    max_parallel_downloads: usize,
+
+    /// Largest file, in bytes, that is uploaded as an artifact or runtime (1 GB by default)
+    #[arg(long, default_value_t = 1024 * 1024 * 1024, env = "DICE_ARTIFACT_SIZE_LIMIT")]
+    artifact_size_limit: u64,
+
+    /// Maximum number of artifacts to upload at the same time (defaults to 4, or
+    /// max_parallel_uploads in the config file)
+    #[arg(long, env = "DICE_MAX_PARALLEL_UPLOADS")]
+    max_parallel_uploads: Option<usize>,
+
+    /// Print more detail about progress, such as the number of downloads in flight and each
+    /// retry with its backoff
+    #[arg(long, env = "DICE_VERBOSE")]
+    verbose: bool,
+
+    /// HTTP proxy for uploads and downloads only, e.g. http://proxy.example.com:3128; defaults
+    /// to HTTPS_PROXY or HTTP_PROXY, and hosts listed in NO_PROXY bypass it. API calls are not
+    /// covered: the SDK makes them, so behind a mandatory proxy set HTTPS_PROXY instead
+    #[arg(long, value_name = "URL", env = "DICE_PROXY", hide_env_values = true)]
+    proxy: Option<String>,
+
+    /// PEM file with the CA certificate of a self-hosted DICE deployment with a private TLS
+    /// certificate, trusted in addition to the system certificates for uploads and downloads
+    /// only. API calls are not covered: the SDK makes them and only trusts the system
+    /// certificates
+    #[arg(long, env = "DICE_CA_CERT_PATH", value_parser = paths::expand_path)]
+    ca_cert: Option<PathBuf>,
+
+    /// Maximum number of results per page for list commands
+    #[arg(long, env = "DICE_LIMIT")]
+    limit: Option<usize>,
+
+    /// Page of results to show for list commands (starting at 1)
+    #[arg(long, default_value_t = 1, env = "DICE_PAGE")]
+    page: usize,
+
+    /// Only list results created at or after this RFC 3339 date, e.g. 2024-01-31T00:00:00Z
+    #[arg(long, alias = "from", value_parser = parse_date, env = "DICE_FILTER_BY_DATE_FROM")]
+    filter_by_date_from: Option<DateTime<FixedOffset>>,
+
+    /// Only list results created at or before this RFC 3339 date
+    #[arg(long, alias = "to", value_parser = parse_date, env = "DICE_FILTER_BY_DATE_TO")]
+    filter_by_date_to: Option<DateTime<FixedOffset>>,
+
+    /// Only download output artifacts with this tag, in the form key=value (can be repeated,
+    /// all of them must match, or comma separated)
+    #[arg(
+        long = "filter-tag",
+        value_delimiter = ',',
+        value_parser = parse_key_value,
+        env = "DICE_FILTER_TAGS"
+    )]
+    filter_tags: Vec<(String, String)>,
+
+    /// Only download output artifacts whose name matches this glob pattern, e.g. 'frame-*.png'
+    #[arg(long, env = "DICE_FILTER_NAME")]
+    filter_name: Option<String>,
+
+    /// Succeed when --filter-tag and --filter-name match no output artifacts
+    #[arg(long)]
+    allow_empty: bool,
+
+    /// Only list results whose name contains this text, ignoring case
+    #[arg(long, env = "DICE_NAME_FILTER")]
+    name_filter: Option<String>,
+
+    /// Only list results matching this expression, e.g.
+    /// 'status=active and tags.team=genomics and created>2024-01-01'. The fields are status,
+    /// name, created and tags.<key>, compared with =, !=, ~ (contains), <, <=, > or >=;
+    /// quote values containing spaces. --tag, --filter-by-date-from,
+    /// --filter-by-date-to and --name-filter add to it
+    #[arg(long, value_name = "EXPRESSION", env = "DICE_FILTER")]
+    filter: Option<String>,
+
+    /// Field to sort the results of list commands by
+    #[arg(long, value_enum, default_value_t = SortBy::Created, env = "DICE_SORT_BY")]
+    sort_by: SortBy,
+
+    /// Type of artifact to create
+    #[arg(long = "type", value_enum, env = "DICE_ARTIFACT_TYPE")]
+    artifact_type: Option<ArtifactKind>,
+
+    /// Type of entity the artifact is attached to
+    #[arg(long, value_enum, env = "DICE_ENTITY_TYPE")]
+    entity_type: Option<EntityKind>,
+
+    /// ID of the entity the artifact is attached to
+    #[arg(long, env = "DICE_ENTITY_ID")]
+    entity_id: Option<String>,
+
+    /// Only include results from this recent period, e.g. 30m, 24h or 7d
+    #[arg(long, env = "DICE_SINCE")]
+    since: Option<String>,
+
+    /// Only show the last N entries of the history
+    #[arg(long, env = "DICE_LAST")]
+    last: Option<usize>,
+
+    /// Only show history entries for this action, e.g. create-job
+    #[arg(long, env = "DICE_ACTION")]
+    action: Option<String>,
+
+    /// Configuration profile to use
+    #[arg(long, env = "DICE_PROFILE")]
+    profile: Option<String>,
+
+    /// DICE API endpoint, overriding API_URL and the config file
+    #[arg(long, env = "DICE_API_URL")]
+    api_url: Option<String>,
+
+    /// DICE API key, overriding API_KEY and the config file
+    #[arg(long, env = "DICE_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    /// Number of times a failed API request or transfer is retried; creates are only retried
+    /// when the API could not be reached, so a timed out create is not made twice
+    #[arg(long, default_value_t = 3, env = "DICE_RETRIES")]
+    retries: u32,
+
+    /// Delay between retries in milliseconds
+    #[arg(long, default_value_t = 1000, env = "DICE_RETRY_DELAY")]
+    retry_delay: u64,
+
+    /// Longest wait in seconds before retrying a rate limited request; without a Retry-After
+    /// header the wait doubles from --retry-delay on each attempt, with jitter
+    #[arg(long, default_value_t = 60, env = "DICE_MAX_BACKOFF")]
+    max_backoff: u64,
+
+    /// Requests per second batch commands send at most, to stay below the API's rate limit
+    #[arg(long, env = "DICE_RPS")]
+    rps: Option<f64>,
+
+    /// Tag in the form key=value (can be repeated or comma separated)
+    #[arg(
+        long = "tag",
+        value_delimiter = ',',
+        value_parser = parse_key_value,
+        env = "DICE_TAGS"
+    )]
+    tags: Vec<(String, String)>,
+
+    /// Input artifact of the cloned job to swap for another, in the form old_id=new_id (can be
+    /// repeated or comma separated)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = parse_key_value,
+        env = "DICE_REPLACE_INPUT"
+    )]
+    replace_input: Vec<(String, String)>,
+
+    /// Input artifact to add to the cloned job (can be repeated or comma separated)
+    #[arg(long, value_delimiter = ',', env = "DICE_ADD_INPUT")]
+    add_input: Vec<String>,
+
+    /// Input artifact to remove from the cloned job (can be repeated or comma separated)
+    #[arg(long, value_delimiter = ',', env = "DICE_REMOVE_INPUT")]
+    remove_input: Vec<String>,
+
+    /// Create a job execution of the cloned job right away
+    #[arg(long)]
+    and_execute: bool,
+
+    /// Parameter in the form key=value passed to the runtime's WASM entry point, set on new
+    /// jobs and job executions (can be repeated or comma separated); with --clone-job it
+    /// overrides a parameter of the source job
+    #[arg(
+        long = "param",
+        visible_alias = "set-param",
+        value_delimiter = ',',
+        value_parser = parse_key_value,
+        env = "DICE_PARAMS"
+    )]
+    params: Vec<(String, String)>,
+
+    /// JSON file with an object of parameters, whose values are strings, numbers or booleans;
+    /// --param wins on the same key
+    #[arg(long, env = "DICE_PARAMS_FILE", value_parser = paths::expand_path)]
+    params_file: Option<PathBuf>,
+
+    /// Tag key to remove (can be repeated or comma separated); with --update-project it can be
+    /// combined with --tag, both being applied in a single update
+    #[arg(long = "remove-tag", value_delimiter = ',', env = "DICE_REMOVE_TAGS")]
+    remove_tags: Vec<String>,
+
+    /// Do not add the default tags of the config file and project settings to created resources
+    #[arg(long)]
+    no_default_tags: bool,
+
+    /// Tags from the config file and project settings, added to created resources
+    #[arg(skip)]
+    default_tags: HashMap<String, String>,
 }
 
-async fn create_runtime(name: String, project_id: String) {
-    // Validate that I am in a DICE runtime repository
-    if is_directory_dice_runtime(".") {
-        println!("Validated located in DICE runtime");
-
-        // Build the runtime
-        Command::new("make")
-            .arg("clean")
-            .status()
-            .expect("Could not clean runtime");
-
-        Command::new("make")
-            .arg("build")
-            .status()
-            .expect("Could not build runtime");
-        println!("Runtime build completed");
-
-        // Utilizing the rust-sdk, get an upload link
-        let create_runtime_response = rust_sdk::api::runtime::create(CreateRuntimeDTO {
-            name: name,
-            project_id: project_id,
-            tags: HashMap::new(),
-        })
-        .await;
+/// Job definition read with `--job-file`, in TOML or JSON (when the file ends in `.json`):
+///
+/// ```toml
+/// project_id = "<project id>"
+/// runtime_id = "<runtime id>"
+/// input_artifact_ids = ["<artifact id>", "<artifact id>"]
+///
+/// [tags]
+/// key = "value"
+/// ```
+#[derive(Deserialize, Debug)]
+struct JobDefinitionFile {
+    project_id: String,
+    runtime_id: String,
+    input_artifact_ids: Vec<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
 
-        // Load runtime file
-        let mut file = File::open(format!(
-            "target/wasm32-wasi/release/{}.tar",
-            get_current_dir()
-        ))
-        .expect("Could not open runtime file");
-
-        // Read the file contents into a buffer
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)
-            .expect("Could not read runtime file");
-
-        // Upload the runtime to DICE
-        let upload_response = reqwest::Client::new()
-            .put(create_runtime_response.uri)
-            .body(buffer)
-            .send()
-            .await;
-        match upload_response {
-            Ok(_) => {
-                println!("Successfully uploaded runtime");
-
-                // Set runtime status to active
-                rust_sdk::api::runtime::update(
-                    create_runtime_response.id.clone(),
-                    UpdateRuntimeDTO {
-                        status: RuntimeStatus::Active,
-                    },
-                )
-                .await;
+/// Skeleton written by `--init-job-file`
+const JOB_FILE_TEMPLATE: &str = r#"# DICE job definition, create the job with `dice --create-job --job-file dice-job.toml`
+project_id = ""
+runtime_id = ""
+input_artifact_ids = []
 
-                println!("Created runtime: {}", create_runtime_response.id);
-            }
-            Err(err) => println!("Could not upload runtime: {}", err),
-        };
-    } else {
-        println!("NOT IN A DICE RUNTIME");
+[tags]
+"#;
+
+/// Default name of the job definition file created by `--init-job-file`
+const JOB_FILE_NAME: &str = "dice-job.toml";
+
+/// Result of creating a single job execution as part of a bulk request
+#[derive(Serialize, Debug)]
+struct CreatedJobExecution {
+    job_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    execution_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    parameters: BTreeMap<String, String>,
+}
+
+fn parse_key_value(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected key=value, got \"{}\"", value)),
     }
 }
 
-async fn create_input_artifact(project_id: String, file_name: String) {
-    let tar_file_name = format!("{}.tar", file_name);
+/// Validates a job execution status, returning its canonical name
+fn parse_job_execution_status(value: &str) -> Result<String, String> {
+    enum_from_string::<JobExecutionStatus>(value).map(|status| enum_to_string(&status))
+}
 
-    // Compress the file
-    Command::new("tar")
-        .arg("-czf")
-        .arg(tar_file_name.clone())
-        .arg(file_name)
-        .status()
-        .expect("Could not tar the input artifact");
+fn parse_date(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(value).map_err(|err| {
+        format!(
+            "expected an RFC 3339 date such as 2024-01-31T12:00:00Z, got \"{}\": {}",
+            value, err
+        )
+    })
+}
 
-    // Utilizing the rust SDK, get an upload link
-    let create_artifact_response = rust_sdk::api::artifact::create(CreateArtifactDTO {
-        entity_id: project_id,
-        entity_type: EntityType::Project,
-        artifact_type: ArtifactType::Input,
-        tags: HashMap::new(),
-    })
-    .await;
-
-    // Load runtime file
-    let mut file = File::open(tar_file_name.clone()).expect("Could not open tar file");
-
-    // Read the file contents into a buffer
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)
-        .expect("Could not read tar file");
-
-    // Upload the compressed file
-    let upload_response = reqwest::Client::new()
-        .put(create_artifact_response.uri)
-        .body(buffer)
-        .send()
-        .await;
-    match upload_response {
-        Ok(_) => {
-            println!("Successfully uploaded input artifact");
-
-            //  Delete tar file
-            Command::new("rm")
-                .arg(tar_file_name)
-                .status()
-                .expect("Could not delete tar file");
-
-            // Set input artifact status to active
-            rust_sdk::api::artifact::update(
-                create_artifact_response.id.clone(),
-                UpdateArtifactDTO {
-                    status: ArtifactStatus::Active,
-                },
-            )
-            .await;
+fn enum_to_string<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap().replace('"', "")
+}
 
-            println!("Created input artifact: {}", create_artifact_response.id);
-        }
-        Err(err) => println!("Could not upload input artifact: {}", err),
+/// Parses a case-insensitive name such as `active` into an SDK enum
+fn enum_from_string<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, String> {
+    let mut chars = value.chars();
+    let name: String = match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
     };
+
+    serde_json::from_value(serde_json::Value::String(name))
+        .map_err(|_| format!("unknown value \"{}\"", value))
 }
 
-async fn create_project(description: String) {
-    // Utilizing the rust SDK, create a project
-    let project_id = rust_sdk::api::project::create(CreateProjectDTO {
-        description: description,
-        tags: HashMap::new(),
-    })
-    .await;
+fn format_tags(tags: &HashMap<String, String>) -> String {
+    let mut tags: Vec<String> = tags
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    tags.sort();
 
-    println!("Created project: {}", project_id);
+    tags.join(",")
 }
 
-async fn create_job(project_id: String, runtime_id: String, input_artifact_ids: Vec<String>) {
-    // Utilizing the rust SDK, create a job
-    let create_job_response = rust_sdk::api::job::create(CreateJobDTO {
-        project_id: project_id,
-        runtime_id: runtime_id,
-        input_artifact_ids: input_artifact_ids,
-        tags: HashMap::new(),
-    })
-    .await;
+/// Tag holding the name of resources whose DTOs have no name field
+const NAME_TAG: &str = "name";
 
-    println!("Created job: {}", create_job_response.id);
-}
+/// Tag recording the job a cloned job was made from
+const CLONED_FROM_TAG: &str = "cloned_from";
 
-async fn create_job_execution(job_id: String) {
-    // Utilizing the rust SDK, create a job execution
-    let create_job_execution_response =
-        rust_sdk::api::job_execution::create(CreateJobExecutionDTO {
-            job_id: job_id,
-            tags: HashMap::new(),
-        })
-        .await;
+/// Prefix of the tags carrying the parameters of a job execution, as the SDK has no
+/// dedicated field for them
+const PARAM_TAG_PREFIX: &str = "param:";
 
-    println!(
-        "Created job execution: {}",
-        create_job_execution_response.id
+/// Adds the `--params-file` and `--param` values to the tags of a new job or job execution
+fn param_tags(
+    mut tags: HashMap<String, String>,
+    params_file: Option<&Path>,
+    params: Vec<(String, String)>,
+) -> Result<HashMap<String, String>, DiceCliError> {
+    let file_params = match params_file {
+        Some(path) => read_params_file(path)?,
+        None => vec![],
+    };
+    tags.extend(
+        file_params
+            .into_iter()
+            .chain(params)
+            .map(|(key, value)| (format!("{}{}", PARAM_TAG_PREFIX, key), value)),
     );
+    // Checked before a batch starts, not once per job execution
+    limits::check_parameters(&tag_parameters(&tags))?;
+    Ok(tags)
 }
 
-async fn get_job_execution(job_execution_id: String) {
-    // Utilizing the rust SDK, get an existing job execution
-    let job_execution = rust_sdk::api::job_execution::get(job_execution_id).await;
+/// Reads a flat JSON object of parameters, turning numbers and booleans into their text
+fn read_params_file(path: &Path) -> Result<Vec<(String, String)>, DiceCliError> {
+    let contents = read_to_string(path)?;
+    let params: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&contents).map_err(|err| parse_error(path, err))?;
 
-    println!("Job execution: {:?}", job_execution);
+    params
+        .into_iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(value) => Ok((key, value)),
+            serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {
+                Ok((key, value.to_string()))
+            }
+            _ => Err(DiceCliError::InvalidArgument {
+                message: format!(
+                    "parameter {} in {} must be a string, number or boolean",
+                    key,
+                    path.display()
+                ),
+            }),
+        })
+        .collect()
 }
 
-async fn download_output_artifacts(job_execution_id: String) {
-    // Utilizing the rust SDK, get an existing job execution
-    let job_execution = rust_sdk::api::job_execution::get(job_execution_id).await;
+/// Parameters carried by the tags of a job or job execution
+fn tag_parameters(tags: &HashMap<String, String>) -> BTreeMap<String, String> {
+    tags.iter()
+        .filter_map(|(key, value)| {
+            let key = key.strip_prefix(PARAM_TAG_PREFIX)?;
+            Some((key.to_string(), value.clone()))
+        })
+        .collect()
+}
 
-    if job_execution.status != JobExecutionStatus::Completed {
-        panic!("Cannot download artifacts for a job that has not yet been completed");
+/// Tags of a new resource: the project defaults, overridden by the given tags and `--name`
+fn creation_tags(
+    defaults: &HashMap<String, String>,
+    tags: impl IntoIterator<Item = (String, String)>,
+    name: Option<String>,
+) -> HashMap<String, String> {
+    let mut result = defaults.clone();
+    result.extend(tags);
+    if let Some(name) = name {
+        result.insert(NAME_TAG.to_string(), name);
     }
+    result
+}
 
-    // Create directory for job
-    let job_root_path = job_execution.id.to_string();
-    fs::create_dir_all(job_root_path.clone()).expect("Could not create job output directory");
+/// Condition matching a field that contains `text`, ignoring case
+fn contains_text(text: &str) -> Document {
+    let pattern: String = text
+        .chars()
+        .flat_map(|c| {
+            let escape = "\\^$.|?*+()[]{}".contains(c).then_some('\\');
+            escape.into_iter().chain([c])
+        })
+        .collect();
+    doc! { "$regex": pattern, "$options": "i" }
+}
 
-    // Change directory into directory
-    env::set_current_dir(job_root_path).expect("Could not change directories");
+/// Filter of a list command: `--filter` and the conditions of the dedicated filter flags
+fn list_filter(
+    expression: Option<&str>,
+    tags: &[(String, String)],
+    created: DateRange,
+    name: Option<&str>,
+) -> Result<Filter, DiceCliError> {
+    let mut filter = match expression {
+        Some(expression) => Filter::parse(expression)?,
+        None => Filter::default(),
+    };
+    for (key, value) in tags {
+        filter = filter.and(Field::Tag(key.clone()), Op::Eq, value.clone());
+    }
+    if let Some(from) = created.from {
+        filter = filter.and(Field::Created, Op::Ge, from.to_rfc3339());
+    }
+    if let Some(to) = created.to {
+        filter = filter.and(Field::Created, Op::Le, to.to_rfc3339());
+    }
+    if let Some(name) = name {
+        filter = filter.and(Field::Name, Op::Contains, name);
+    }
+    Ok(filter)
+}
 
-    // Get list of output artifacts for job execution
-    let artifacts = rust_sdk::api::artifact::list(doc! {
-        "artifact_type": serde_json::to_string(&ArtifactType::Output).unwrap().replace("\"", ""),
-        "entity_id": job_execution.id,
-        "status": serde_json::to_string(&ArtifactStatus::Active).unwrap().replace("\"", "")
-    })
-    .await;
-
-    // For each artifact in job execution, download it, untar it, and then remove the tar file
-    let task_handles = artifacts.into_iter().map(|artifact| {
-        tokio::spawn(async move {
-            //  Download artifact
-            let tar_file_path = format!("{}.tar", artifact.id.to_string());
-
-            let download_artifact_response =
-                rust_sdk::api::artifact::download(artifact.id.to_string()).await;
-            let response = reqwest::get(download_artifact_response.uri).await.unwrap();
-
-            let mut artifact_file = File::create(&tar_file_path).unwrap();
-            let mut content = Cursor::new(response.bytes().await.unwrap());
-            std::io::copy(&mut content, &mut artifact_file)
-                .expect("Could not copy artifact to file");
-
-            //  Untar the artifact
-            Command::new("tar")
-                .arg("-xvf")
-                .arg(tar_file_path.clone())
-                .status()
-                .expect("Could not untar the output artifact");
-
-            //  Delete tar file
-            Command::new("rm")
-                .arg(tar_file_path)
-                .status()
-                .expect("Could not delete tar file");
-        })
-    });
+/// Whether `name` matches a glob `pattern`, where `*` matches any text and `?` any character
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
 
-    for handler in task_handles {
-        handler.await.expect("Could not upload ouput artifact");
+    // Backtrack to the last `*` on a mismatch, letting it match one more character
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
     }
+
+    pattern[p..].iter().all(|&c| c == '*')
 }
 
-#[tokio::main]
-async fn main() {
-    let args = Arguments::parse();
+/// Creation dates to filter list results on, either bound can be left open
+#[derive(Clone, Copy, Debug)]
+struct DateRange {
+    from: Option<DateTime<FixedOffset>>,
+    to: Option<DateTime<FixedOffset>>,
+}
 
-    if args.create_runtime {
-        create_runtime(
-            args.name.expect("--name required"),
-            args.project_id.expect("--project-id required"),
-        )
-        .await;
+/// ID bounding those created in the second of `date`: the lowest with `fill` 0x00, the
+/// highest with 0xff
+fn creation_bound(date: DateTime<FixedOffset>, fill: u8) -> ObjectId {
+    // The first 4 bytes of an ObjectId are its creation time in seconds
+    let mut bytes = [fill; 12];
+    let seconds = date.timestamp().clamp(0, u32::MAX as i64) as u32;
+    bytes[..4].copy_from_slice(&seconds.to_be_bytes());
+    ObjectId::from_bytes(bytes)
+}
+
+/// Limits results to those created within the range, using the timestamp in their ID
+fn date_filter(filter: &mut Document, range: DateRange) {
+    let mut bounds = Document::new();
+    if let Some(from) = range.from {
+        bounds.insert("$gte", creation_bound(from, 0x00));
+    }
+    if let Some(to) = range.to {
+        bounds.insert("$lte", creation_bound(to, 0xff));
+    }
+    if !bounds.is_empty() {
+        filter.insert("_id", bounds);
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn paginate<T>(items: Vec<T>, limit: Option<usize>, page: usize) -> Vec<T> {
+    match limit {
+        Some(limit) => items
+            .into_iter()
+            .skip(page.saturating_sub(1) * limit)
+            .take(limit)
+            .collect(),
+        None => items,
+    }
+}
+
+fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |cells: Vec<String>| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        outln!("{}", line.join("  ").trim_end());
+    };
+
+    print_row(headers.iter().map(|header| header.to_string()).collect());
+    for row in rows {
+        print_row(row);
+    }
+}
+
+fn read_to_string(path: &Path) -> Result<String, DiceCliError> {
+    fs::read_to_string(path).map_err(|source| DiceCliError::Io {
+        action: "read",
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn parse_error(path: &Path, err: impl std::fmt::Display) -> DiceCliError {
+    DiceCliError::Parse {
+        path: path.to_path_buf(),
+        message: err.to_string(),
+    }
+}
+
+fn read_ids_file(path: &str) -> Result<Vec<String>, DiceCliError> {
+    let path = Path::new(path);
+    let contents = read_to_string(path)?;
+
+    // Accept either a JSON array of IDs or one ID per line
+    if contents.trim_start().starts_with('[') {
+        return serde_json::from_str(&contents).map_err(|err| parse_error(path, err));
+    }
+
+    Ok(contents
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+fn read_job_definition_file(path: &str) -> Result<JobDefinitionFile, DiceCliError> {
+    let path = Path::new(path);
+    let contents = read_to_string(path)?;
+
+    if path.extension() == Some(OsStr::new("json")) {
+        serde_json::from_str(&contents).map_err(|err| parse_error(path, err))
+    } else {
+        toml::from_str(&contents).map_err(|err| parse_error(path, err))
+    }
+}
+
+/// Writes a file through a temporary sibling so readers never see partial contents
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, process::id()));
+
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })
+}
+
+tokio::task_local! {
+    /// Set on the task of an SDK call, whose panics are reported as errors
+    static SDK_CALL: ();
+}
+
+/// Keeps the panics of SDK calls off stderr, where they would precede the error they are
+/// reported as; any other panic is a crash and still reported by the default hook
+fn silence_sdk_panics() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if SDK_CALL.try_with(|_| ()).is_err() {
+            default_hook(info);
+        }
+    }));
+}
+
+/// Runs an SDK call on its own task so a failing call is reported instead of aborting,
+/// calling `call` again for each retry
+async fn try_sdk<F, Fut, T>(action: &str, call: F) -> Result<T, DiceCliError>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    retry::with_configured_retry(|| spawn_sdk(action, &call)).await
+}
+
+/// Like [`try_sdk`] for calls that create a resource, retried only when the request cannot
+/// have reached the API, so a timeout does not create it twice
+async fn try_sdk_create<F, Fut, T>(action: &str, call: F) -> Result<T, DiceCliError>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    retry::with_configured_create_retry(|| spawn_sdk(action, &call)).await
+}
+
+fn spawn_sdk<F, Fut, T>(
+    action: &str,
+    call: &F,
+) -> impl std::future::Future<Output = Result<T, DiceCliError>>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let call = call();
+    let action = action.to_string();
+    async move {
+        // Held until the call ends, even if its caller gives up on it, so a profile switch
+        // never changes the API variables while the SDK may read them
+        let settings = config::API_SETTINGS.read().await;
+        let handle = tokio::spawn(SDK_CALL.scope((), async move {
+            let result = call.await;
+            drop(settings);
+            result
+        }));
+        handle.await.map_err(|err| DiceCliError::Api {
+            action,
+            message: panic_message(err),
+        })
+    }
+}
+
+fn panic_message(err: JoinError) -> String {
+    match err.try_into_panic() {
+        Ok(payload) => match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "unknown error".to_string(),
+            },
+        },
+        Err(err) => err.to_string(),
+    }
+}
+
+fn is_directory_dice_runtime(root: &str) -> bool {
+    manifest::manifest_path(Path::new(root)).is_some()
+}
+
+fn get_current_dir() -> String {
+    let cwd: PathBuf = env::current_dir().unwrap();
+    let name: &OsStr = cwd.file_name().unwrap();
+
+    name.to_string_lossy().into_owned()
+}
+
+/// Name of the directory `dir` points to, so `.` and `..` are named too
+fn dir_name(dir: &Path) -> Option<String> {
+    let dir = dir.canonicalize().ok()?;
+    dir.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+fn is_on_path(program: &str) -> bool {
+    env::var_os("PATH")
+        .map(|paths| {
+            env::split_paths(&paths).any(|dir| {
+                dir.join(program).is_file() || dir.join(format!("{}.exe", program)).is_file()
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Name of the make program, which is `mingw32-make` on some Windows toolchains
+fn make_program() -> Option<&'static str> {
+    ["make", "mingw32-make"]
+        .into_iter()
+        .find(|program| is_on_path(program))
+}
+
+/// Builds the runtime in `root`, either with `make clean build` or a whitespace separated
+/// `--build-command`, saving the output to `.dice-build.log` and echoing it live unless `quiet`
+fn build_runtime(
+    root: &Path,
+    build_command: Option<&str>,
+    quiet: bool,
+) -> Result<(), DiceCliError> {
+    // Each step with whether the build goes on when it fails: a Makefile without a clean
+    // target can still build
+    let steps: Vec<(Vec<String>, bool)> = match build_command {
+        Some(build_command) => vec![(
+            build_command
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            false,
+        )],
+        None => {
+            let make = make_program().unwrap_or("make");
+            vec![
+                (vec![make.to_string(), "clean".to_string()], true),
+                (vec![make.to_string(), "build".to_string()], false),
+            ]
+        }
+    };
+
+    let log = buildlog::BuildLog::create(&root.join(buildlog::BUILD_LOG), quiet)?;
+    for (command, optional) in steps {
+        let (program, arguments) = command.split_first().ok_or(DiceCliError::InvalidArgument {
+            message: "--build-command is empty".to_string(),
+        })?;
+        let status = log.run(Command::new(program).args(arguments).current_dir(root))?;
+        if status.success() {
+            continue;
+        }
+
+        let command = command.join(" ");
+        let exit_code = status.code().unwrap_or(-1);
+        if optional {
+            eprintln!(
+                "Warning: `{}` failed with exit code {}, building anyway",
+                command, exit_code
+            );
+            continue;
+        }
+        log.print_tail();
+        return Err(DiceCliError::BuildFailed {
+            command,
+            exit_code,
+            log: log.path().to_path_buf(),
+        });
+    }
+    outln!(
+        "Runtime build completed, its output is in {}",
+        log.path().display()
+    );
+
+    Ok(())
+}
+
+/// How a runtime is built and checked before it is uploaded
+#[derive(Clone, Debug)]
+struct RuntimeBuild {
+    /// Runtime repository, the current directory unless `--runtime-dir` is given
+    dir: PathBuf,
+    /// Overrides the build command of the manifest
+    command: Option<String>,
+    /// Use the output of an earlier build
+    skip_build: bool,
+    skip_validation: bool,
+    /// Only write the build output to the log
+    quiet: bool,
+}
+
+/// Builds the runtime described by `manifest`, or with `skip_build` only checks that an
+/// earlier build left its output in place, and returns the path of the output
+fn build_runtime_output(
+    manifest: &manifest::DiceRuntimeManifest,
+    build: &RuntimeBuild,
+) -> Result<PathBuf, DiceCliError> {
+    let output_path = manifest.output_path(&build.dir);
+    if build.skip_build {
+        if !output_path.is_file() {
+            return Err(DiceCliError::RuntimeNotBuilt { path: output_path });
+        }
+        outln!("Skipped the build, using {}", output_path.display());
+    } else {
+        let command = build.command.as_ref().or(manifest.build_command.as_ref());
+        // Build output on stderr would be mixed with the progress events
+        let quiet = build.quiet || progress::is_json();
+        progress::phase_blocking(progress::Phase::Build, None, || {
+            build_runtime(&build.dir, command.map(String::as_str), quiet)
+        })?;
+    }
+
+    Ok(output_path)
+}
+
+async fn running_execution_count(runtime_id: String) -> Result<usize, DiceCliError> {
+    let job_ids: Vec<String> = try_sdk("list jobs", || {
+        rust_sdk::api::job::list(doc! { "runtime_id": runtime_id.clone() })
+    })
+    .await?
+    .into_iter()
+    .map(|job| job.id.to_string())
+    .collect();
+    if job_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let executions = try_sdk("list job executions", || {
+        rust_sdk::api::job_execution::list(doc! {
+            "job_id": { "$in": job_ids.clone() },
+            "status": "Running",
+        })
+    })
+    .await?;
+
+    Ok(executions.len())
+}
+
+/// Content type of bodies uploaded as they come, from stdin, a URL or another artifact,
+/// unless overridden
+const RAW_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Content type of a runtime archive, built by the CLI or by the runtime's own build
+fn runtime_content_type(buffer: &[u8]) -> &'static str {
+    archive::CompressionFormat::detect(buffer).content_type()
+}
+
+/// Uploads a body to a presigned link, including the storage response body when it is rejected
+async fn put_presigned(
+    uri: String,
+    body: reqwest::Body,
+    content_type: &str,
+    content_length: Option<u64>,
+) -> Result<(), DiceCliError> {
+    let _permit = http::upload_permit().await;
+    let uri_host = uri_host(&uri);
+    let mut request = http::client()
+        .put(uri)
+        .header(reqwest::header::CONTENT_TYPE, content_type)
+        .body(body);
+    if let Some(content_length) = content_length {
+        request = request.header(reqwest::header::CONTENT_LENGTH, content_length);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|source| DiceCliError::ConnectionFailed {
+            uri_host: uri_host.clone(),
+            source,
+        })?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(DiceCliError::RateLimited {
+            retry_after: http::retry_after(&response),
+            uri_host,
+        });
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(DiceCliError::UploadFailed {
+        uri_host,
+        status: status.as_u16(),
+        body: body.trim().to_string(),
+    })
+}
+
+/// Uploads an archive to a presigned link with the configured retries, as the upload phase of
+/// the runtime or artifact `id`
+async fn upload_archive(
+    id: &str,
+    uri: &str,
+    buffer: &[u8],
+    content_type: &str,
+) -> Result<(), DiceCliError> {
+    let content_length = buffer.len() as u64;
+    progress::phase(
+        progress::Phase::Upload,
+        Some(id),
+        retry::with_configured_retry(|| {
+            put_presigned(
+                uri.to_string(),
+                progress::upload_body(id, buffer.to_vec()),
+                content_type,
+                Some(content_length),
+            )
+        }),
+    )
+    .await
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, DiceCliError> {
+    fs::read(path).map_err(|source| DiceCliError::Io {
+        action: "read",
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Reads the archive to upload as a runtime, packing a bare `.wasm` binary into a tarball
+/// first and validating the result unless `skip_validation` is set
+fn load_runtime_archive(
+    path: &Path,
+    target: Option<&str>,
+    skip_validation: bool,
+    temp_dir: &Path,
+) -> Result<Vec<u8>, DiceCliError> {
+    http::check_upload_size(path)?;
+    if path.extension() != Some(OsStr::new("wasm")) {
+        if !skip_validation {
+            validate::validate_runtime(path, target)?;
+        }
+        return read_file(path);
+    }
+
+    let tar_file_name = cache::temp_file_path(
+        temp_dir,
+        &format!("runtime.{}", archive::compression().extension()),
+    )?;
+    cleanup::register_temp_file(&tar_file_name);
+    archive::create_archive(path, &tar_file_name)?;
+    if !skip_validation {
+        validate::validate_runtime(&tar_file_name, target)?;
+    }
+    let buffer = read_file(&tar_file_name)?;
+
+    fs::remove_file(&tar_file_name).map_err(|source| DiceCliError::Io {
+        action: "delete",
+        path: tar_file_name.clone(),
+        source,
+    })?;
+    cleanup::unregister_temp_file(&tar_file_name);
+
+    Ok(buffer)
+}
+
+async fn upload_runtime(
+    runtime_id: String,
+    file_name: Option<String>,
+    force: bool,
+    build: &RuntimeBuild,
+    temp_dir: PathBuf,
+) -> Result<(), DiceCliError> {
+    let runtime = try_sdk("fetch runtime", || {
+        rust_sdk::api::runtime::get(runtime_id.clone())
+    })
+    .await?;
+
+    // Executions that are running may be using the current binary
+    let running = running_execution_count(runtime_id.clone()).await?;
+    if running > 0 && !force {
+        return Err(DiceCliError::RuntimeInUse {
+            runtime_id,
+            running,
+        });
+    }
+
+    // Either upload a prebuilt archive or rebuild the runtime in place
+    let (file_name, target) = match file_name {
+        Some(file_name) => {
+            let path = PathBuf::from(file_name);
+            check_runtime_file(&path)?;
+            (path, None)
+        }
+        None if manifest::manifest_path(&build.dir).is_some() => {
+            let manifest = manifest::load(&build.dir)?;
+            let output_path = build_runtime_output(&manifest, build)?;
+            (output_path, Some(manifest.target().to_string()))
+        }
+        None => return Err(DiceCliError::MissingArgument { flag: "--file" }),
+    };
+
+    // The current binary stays in place if the new one is rejected
+    let buffer = load_runtime_archive(
+        &file_name,
+        target.as_deref(),
+        build.skip_validation,
+        &temp_dir,
+    )?;
+
+    // Utilizing the rust SDK, get a fresh upload link for the existing runtime
+    let upload_runtime_response = try_sdk("get an upload link", || {
+        rust_sdk::api::runtime::upload(runtime_id.clone())
+    })
+    .await?;
+
+    upload_archive(
+        &runtime_id,
+        &upload_runtime_response.uri,
+        &buffer,
+        runtime_content_type(&buffer),
+    )
+    .await?;
+    outln!("Successfully uploaded runtime");
+
+    try_sdk("activate runtime", || {
+        rust_sdk::api::runtime::update(
+            runtime_id.clone(),
+            UpdateRuntimeDTO {
+                status: RuntimeStatus::Active,
+            },
+        )
+    })
+    .await?;
+
+    outln!(
+        "Runtime {} status: {} -> {}",
+        runtime_id,
+        color::runtime_status(&runtime.status),
+        color::runtime_status(&RuntimeStatus::Active)
+    );
+    history::record("upload-runtime", &[("runtime", &runtime_id)]);
+
+    Ok(())
+}
+
+async fn update_runtime(
+    runtime_id: String,
+    status: String,
+    tags: Vec<(String, String)>,
+) -> Result<(), DiceCliError> {
+    if !tags.is_empty() {
+        return Err(DiceCliError::InvalidArgument {
+            message: "runtime tags cannot be updated, only --status is supported".to_string(),
+        });
+    }
+
+    let status: RuntimeStatus =
+        enum_from_string(&status).map_err(|err| DiceCliError::InvalidArgument {
+            message: format!("invalid runtime status: {}", err),
+        })?;
+
+    let new_status = color::runtime_status(&status);
+    let status_name = enum_to_string(&status);
+
+    let runtime = try_sdk("fetch runtime", || {
+        rust_sdk::api::runtime::get(runtime_id.clone())
+    })
+    .await?;
+
+    try_sdk("update runtime", || {
+        // SDK statuses cannot be cloned, so every attempt parses the validated name again
+        let status = enum_from_string(&status_name).expect("Runtime status was validated");
+        rust_sdk::api::runtime::update(runtime_id.clone(), UpdateRuntimeDTO { status })
+    })
+    .await?;
+
+    outln!(
+        "Runtime {} status: {} -> {}",
+        runtime_id,
+        color::runtime_status(&runtime.status),
+        new_status
+    );
+    history::record("update-runtime", &[("runtime", &runtime_id)]);
+
+    Ok(())
+}
+
+/// Sets the status of an artifact, returning the status it had
+async fn update_artifact_status(
+    artifact_id: &str,
+    status_name: &str,
+) -> Result<ArtifactStatus, DiceCliError> {
+    let artifact = try_sdk("fetch artifact", || {
+        rust_sdk::api::artifact::get(artifact_id.to_string())
+    })
+    .await?;
+    if artifact.status == ArtifactStatus::Pending && status_name == "Active" {
+        return Err(DiceCliError::ArtifactUnavailable {
+            artifact_id: artifact_id.to_string(),
+            reason: "was never uploaded, so it cannot be made Active".to_string(),
+        });
+    }
+
+    try_sdk("update artifact", || {
+        // SDK statuses cannot be cloned, so every attempt parses the validated name again
+        let status = enum_from_string(status_name).expect("Artifact status was validated");
+        rust_sdk::api::artifact::update(artifact_id.to_string(), UpdateArtifactDTO { status })
+    })
+    .await?;
+
+    Ok(artifact.status)
+}
+
+/// Sets the status of each artifact, reporting the result per artifact
+async fn update_artifacts(artifact_ids: Vec<String>, status: String) -> Result<(), DiceCliError> {
+    let status: ArtifactStatus =
+        enum_from_string(&status).map_err(|err| DiceCliError::InvalidArgument {
+            message: format!(
+                "invalid artifact status: {}, artifacts can only be set Active or Inactive",
+                err
+            ),
+        })?;
+    if status == ArtifactStatus::Pending {
+        return Err(DiceCliError::InvalidArgument {
+            message: "artifacts can only be set Active or Inactive".to_string(),
+        });
+    }
+    let new_status = color::artifact_status(&status);
+    let status_name = enum_to_string(&status);
+
+    let total = artifact_ids.len();
+    let mut failed = 0;
+    for artifact_id in artifact_ids {
+        match update_artifact_status(&artifact_id, &status_name).await {
+            Ok(old_status) => {
+                outln!(
+                    "Artifact {} status: {} -> {}",
+                    artifact_id,
+                    color::artifact_status(&old_status),
+                    new_status
+                );
+                history::record("update-artifact", &[("artifact", &artifact_id)]);
+            }
+            Err(err) if total == 1 => return Err(err),
+            Err(err) => {
+                failed += 1;
+                eprintln!("Could not update artifact {}: {}", artifact_id, err);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(DiceCliError::ArtifactUpdatesFailed { failed, total });
+    }
+
+    Ok(())
+}
+
+/// Checks that a prebuilt runtime archive exists and is not empty
+fn check_runtime_file(path: &Path) -> Result<(), DiceCliError> {
+    let metadata = fs::metadata(path).map_err(|source| DiceCliError::Io {
+        action: "read",
+        path: path.to_path_buf(),
+        source,
+    })?;
+    if !metadata.is_file() || metadata.len() == 0 {
+        return Err(DiceCliError::InvalidArgument {
+            message: format!("{} is not a runtime archive", path.display()),
+        });
+    }
+
+    Ok(())
+}
+
+/// Builds the runtime and validates its WebAssembly modules, all locally: the build output is
+/// the only thing it changes
+fn verify_runtime(build: &RuntimeBuild, format: OutputFormat) -> Result<(), DiceCliError> {
+    let manifest = manifest::load(&build.dir)?;
+    outln!("Validated located in DICE runtime");
+
+    let output_path = build_runtime_output(&manifest, build)?;
+
+    let verification =
+        validate::verify_runtime(&output_path, manifest.target(), manifest.entry_point())?;
+    match format {
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&verification).unwrap()),
+        OutputFormat::Table | OutputFormat::Raw => {
+            outln!(
+                "Runtime {}: {}",
+                verification.path.display(),
+                format_bytes(verification.bytes)
+            );
+            for module in &verification.modules {
+                let entry_point = if module.exports_entry_point {
+                    format!(", exports {}", verification.entry_point)
+                } else {
+                    String::new()
+                };
+                outln!(
+                    "  {}: {}{}",
+                    module.name,
+                    format_bytes(module.bytes),
+                    entry_point
+                );
+            }
+            outln!("Runtime is valid for {}", manifest.target());
+        }
+    }
+
+    Ok(())
+}
+
+/// ID of a runtime of the project named `name`. Pending runtimes never finished uploading, so
+/// they do not count.
+async fn find_runtime_by_name(
+    project_id: &str,
+    name: &str,
+) -> Result<Option<String>, DiceCliError> {
+    let runtimes = try_sdk("list runtimes", || {
+        rust_sdk::api::runtime::list(doc! { "project_id": project_id, "name": name })
+    })
+    .await?;
+
+    Ok(runtimes
+        .into_iter()
+        .find(|runtime| runtime.status != RuntimeStatus::Pending)
+        .map(|runtime| runtime.id.to_string()))
+}
+
+/// Name of a new runtime: `--name`, else the manifest's name, else the name of the runtime
+/// directory. A prebuilt archive has no manifest, so it is only named by `--name`.
+fn runtime_name(
+    name: Option<String>,
+    manifest: Option<&manifest::DiceRuntimeManifest>,
+    dir: &Path,
+) -> Option<String> {
+    name.or_else(|| {
+        let manifest = manifest?;
+        manifest.name.clone().or_else(|| dir_name(dir))
+    })
+}
+
+async fn create_runtime(
+    name: Option<String>,
+    project_id: String,
+    file_name: Option<String>,
+    build: &RuntimeBuild,
+    temp_dir: PathBuf,
+    tags: HashMap<String, String>,
+    if_not_exists: bool,
+) -> Result<String, DiceCliError> {
+    // Prebuilt archives, e.g. from CI, are uploaded as they are, otherwise the runtime
+    // repository is built
+    let manifest = match file_name {
+        Some(_) => None,
+        None => {
+            // Validate that I am in a DICE runtime repository
+            let manifest = manifest::load(&build.dir)?;
+            outln!("Validated located in DICE runtime");
+            Some(manifest)
+        }
+    };
+    let name = required(runtime_name(name, manifest.as_ref(), &build.dir), "--name")?;
+    limits::check_name(&name)?;
+    limits::check_tags(&tags)?;
+
+    // Checked before building, which is what makes re-running a deployment cheap
+    if if_not_exists {
+        if let Some(runtime_id) = find_runtime_by_name(&project_id, &name).await? {
+            outln!("Runtime already exists: {}", runtime_id);
+            return Ok(runtime_id);
+        }
+    }
+
+    let buffer = match (file_name, manifest) {
+        (Some(file_name), _) => {
+            let path = PathBuf::from(file_name);
+            check_runtime_file(&path)?;
+            load_runtime_archive(&path, None, build.skip_validation, &temp_dir)?
+        }
+        (None, Some(manifest)) => {
+            let output_path = build_runtime_output(&manifest, build)?;
+
+            // Check the build output before a runtime record is created for it
+            load_runtime_archive(
+                &output_path,
+                Some(manifest.target()),
+                build.skip_validation,
+                &temp_dir,
+            )?
+        }
+        (None, None) => unreachable!("the manifest is loaded when no file is given"),
+    };
+
+    // Utilizing the rust-sdk, get an upload link
+    let create_runtime_response = progress::create(
+        try_sdk_create("create runtime", || {
+            rust_sdk::api::runtime::create(CreateRuntimeDTO {
+                name: name.clone(),
+                project_id: project_id.clone(),
+                tags: tags.clone(),
+            })
+        }),
+        |response| response.id.clone(),
+    )
+    .await?;
+    cleanup::register_pending_resource("runtime", &create_runtime_response.id);
+
+    // Upload the runtime to DICE
+    upload_archive(
+        &create_runtime_response.id,
+        &create_runtime_response.uri,
+        &buffer,
+        runtime_content_type(&buffer),
+    )
+    .await?;
+    outln!("Successfully uploaded runtime");
+
+    // Set runtime status to active
+    try_sdk("activate runtime", || {
+        rust_sdk::api::runtime::update(
+            create_runtime_response.id.clone(),
+            UpdateRuntimeDTO {
+                status: RuntimeStatus::Active,
+            },
+        )
+    })
+    .await?;
+    cleanup::unregister_pending_resource(&create_runtime_response.id);
+
+    outln!("Created runtime: {}", create_runtime_response.id);
+    history::record(
+        "create-runtime",
+        &[("runtime", &create_runtime_response.id)],
+    );
+
+    Ok(create_runtime_response.id)
+}
+
+/// Creates the runtimes of the `.dice-workspace` manifest one after the other, each with the
+/// name and build command of its own manifest, and prints the ID each one got
+async fn create_workspace_runtimes(
+    project_id: String,
+    build: &RuntimeBuild,
+    temp_dir: PathBuf,
+    tags: HashMap<String, String>,
+    if_not_exists: bool,
+    fail_fast: bool,
+) -> Result<(), DiceCliError> {
+    let workspace = manifest::load_workspace(&build.dir)?;
+    let total = workspace.members.len();
+    let mut rows = vec![];
+    let mut failed = 0;
+    for member in workspace.members {
+        let dir = build.dir.join(&member);
+        let name = manifest::load(&dir)
+            .ok()
+            .and_then(|manifest| manifest.name)
+            .unwrap_or_else(|| member.display().to_string());
+        outln!("Creating runtime {} from {}", name, member.display());
+
+        let member_build = RuntimeBuild {
+            dir,
+            ..build.clone()
+        };
+        let created = create_runtime(
+            None,
+            project_id.clone(),
+            None,
+            &member_build,
+            temp_dir.clone(),
+            tags.clone(),
+            if_not_exists,
+        )
+        .await;
+        match created {
+            Ok(runtime_id) => rows.push(vec![name, runtime_id]),
+            Err(err) if fail_fast => return Err(err),
+            Err(err) => {
+                failed += 1;
+                eprintln!("Could not create runtime {}: {}", name, err);
+                rows.push(vec![name, "failed".to_string()]);
+            }
+        }
+    }
+
+    print_table(&["NAME", "RUNTIME ID"], rows);
+    if failed > 0 {
+        return Err(DiceCliError::WorkspaceRuntimesFailed { failed, total });
+    }
+
+    Ok(())
+}
+
+fn validate_artifact_target(
+    artifact_type: ArtifactKind,
+    entity_type: EntityKind,
+) -> Result<(), String> {
+    match (artifact_type, entity_type) {
+        (ArtifactKind::Input, EntityKind::Project | EntityKind::Job) => Ok(()),
+        (ArtifactKind::Output, EntityKind::JobExecution) => Ok(()),
+        (ArtifactKind::Input, EntityKind::JobExecution) => {
+            Err("input artifacts must be attached to a project or a job".to_string())
+        }
+        (ArtifactKind::Output, _) => {
+            Err("output artifacts must be attached to a job execution".to_string())
+        }
+    }
+}
+
+/// Compresses `file` into an archive of its own in `temp_dir`, so concurrent invocations for
+/// the same file never share or delete each other's archive
+fn pack_artifact(file: &Path, temp_dir: &Path) -> Result<PathBuf, DiceCliError> {
+    let base_name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "artifact".to_string());
+    let tar_file_name = cache::temp_file_path(
+        temp_dir,
+        &format!("{}.{}", base_name, archive::compression().extension()),
+    )?;
+    cleanup::register_temp_file(&tar_file_name);
+
+    archive::create_archive(file, &tar_file_name)?;
+
+    Ok(tar_file_name)
+}
+
+/// Uploads the archive packed for the artifact `id` to its upload link, then deletes it
+async fn upload_packed_artifact(
+    tar_file_name: &Path,
+    id: &str,
+    uri: &str,
+    content_type: Option<&str>,
+) -> Result<(), DiceCliError> {
+    let buffer = read_file(tar_file_name)?;
+    upload_archive(
+        id,
+        uri,
+        &buffer,
+        content_type.unwrap_or(archive::compression().content_type()),
+    )
+    .await?;
+
+    fs::remove_file(tar_file_name).map_err(|source| DiceCliError::Io {
+        action: "delete",
+        path: tar_file_name.to_path_buf(),
+        source,
+    })?;
+    cleanup::unregister_temp_file(tar_file_name);
+
+    Ok(())
+}
+
+async fn create_artifact(
+    entity_id: String,
+    entity_type: EntityKind,
+    artifact_type: ArtifactKind,
+    file_name: String,
+    temp_dir: PathBuf,
+    content_type: Option<String>,
+    tags: HashMap<String, String>,
+) -> Result<String, DiceCliError> {
+    limits::check_tags(&tags)?;
+    let kind = enum_to_string(&ArtifactType::from(artifact_type)).to_lowercase();
+    http::check_upload_size(Path::new(&file_name))?;
+    let tar_file_name = pack_artifact(Path::new(&file_name), &temp_dir)?;
+    let mut tags = tags;
+    if !tags.contains_key(dedupe::CHECKSUM_TAG) {
+        tags.insert(
+            dedupe::CHECKSUM_TAG.to_string(),
+            archive::checksum(Path::new(&file_name))?,
+        );
+    }
+    tags.insert(
+        archive::COMPRESSION_TAG.to_string(),
+        archive::compression().tag().to_string(),
+    );
+
+    // Utilizing the rust SDK, get an upload link
+    let create_artifact_response = progress::create(
+        try_sdk_create("create artifact", || {
+            rust_sdk::api::artifact::create(CreateArtifactDTO {
+                entity_id: entity_id.clone(),
+                entity_type: entity_type.into(),
+                artifact_type: artifact_type.into(),
+                tags: tags.clone(),
+            })
+        }),
+        |response| response.id.clone(),
+    )
+    .await?;
+    cleanup::register_pending_resource("artifact", &create_artifact_response.id);
+
+    upload_packed_artifact(
+        &tar_file_name,
+        &create_artifact_response.id,
+        &create_artifact_response.uri,
+        content_type.as_deref(),
+    )
+    .await?;
+    outln!("Successfully uploaded {} artifact", kind);
+
+    // Set artifact status to active
+    try_sdk("activate artifact", || {
+        rust_sdk::api::artifact::update(
+            create_artifact_response.id.clone(),
+            UpdateArtifactDTO {
+                status: ArtifactStatus::Active,
+            },
+        )
+    })
+    .await?;
+    cleanup::unregister_pending_resource(&create_artifact_response.id);
+
+    outln!("Created {} artifact: {}", kind, create_artifact_response.id);
+    history::record(
+        "create-artifact",
+        &[("artifact", &create_artifact_response.id)],
+    );
+
+    Ok(create_artifact_response.id)
+}
+
+/// How files are uploaded as input artifacts
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct UploadOptions {
+    content_type: Option<String>,
+    /// Reuse an active input artifact with the same contents instead of uploading
+    dedup: bool,
+    /// Look for artifacts with the same contents in every project, not only the target one
+    global_dedup: bool,
+    /// Upload even when an artifact with the same contents exists
+    force_upload: bool,
+}
+
+/// An input artifact created from a file, or an existing one with the same contents
+#[derive(Serialize, Debug)]
+struct UploadedArtifact {
+    artifact_id: String,
+    reused: bool,
+}
+
+async fn create_input_artifact(
+    project_id: String,
+    file_name: String,
+    temp_dir: PathBuf,
+    tags: HashMap<String, String>,
+    options: &UploadOptions,
+) -> Result<UploadedArtifact, DiceCliError> {
+    limits::check_tags(&tags)?;
+    let mut tags = tags;
+    if options.dedup {
+        let checksum = archive::checksum(Path::new(&file_name))?;
+        let scope = Some(project_id.as_str()).filter(|_| !options.global_dedup);
+        match dedupe::find_duplicate(scope, &checksum).await? {
+            Some(artifact_id) if !options.force_upload => {
+                outln!(
+                    "Reused input artifact {} with the same contents as {}",
+                    artifact_id,
+                    file_name
+                );
+                return Ok(UploadedArtifact {
+                    artifact_id,
+                    reused: true,
+                });
+            }
+            Some(artifact_id) => outln!(
+                "Input artifact {} has the same contents as {}, uploading anyway",
+                artifact_id,
+                file_name
+            ),
+            None => (),
+        }
+        tags.insert(dedupe::CHECKSUM_TAG.to_string(), checksum);
+    }
+
+    let artifact_id = create_artifact(
+        project_id,
+        EntityKind::Project,
+        ArtifactKind::Input,
+        file_name,
+        temp_dir,
+        options.content_type.clone(),
+        tags,
+    )
+    .await?;
+
+    Ok(UploadedArtifact {
+        artifact_id,
+        reused: false,
+    })
+}
+
+/// Tag naming the file, relative to the uploaded directory, an input artifact was created from
+const SOURCE_FILENAME_TAG: &str = "source_filename";
+
+/// Files in `dir`, and with `recursive` in its subdirectories, in a stable order
+fn list_files(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>, DiceCliError> {
+    let read_failed = |source| DiceCliError::Io {
+        action: "read",
+        path: dir.to_path_buf(),
+        source,
+    };
+
+    let mut files = vec![];
+    for entry in fs::read_dir(dir).map_err(read_failed)? {
+        let path = entry.map_err(read_failed)?.path();
+        if path.is_dir() {
+            if recursive {
+                files.extend(list_files(&path, true)?);
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Uploads every file of `dir` as its own input artifact, tagged with its file name, running
+/// up to `--max-parallel-uploads` uploads at the same time
+async fn create_input_artifacts_from_directory(
+    project_id: String,
+    dir: PathBuf,
+    recursive: bool,
+    dry_run: bool,
+    temp_dir: PathBuf,
+    tags: HashMap<String, String>,
+    options: UploadOptions,
+) -> Result<(), DiceCliError> {
+    let files: Vec<(String, PathBuf)> = list_files(&dir, recursive)?
+        .into_iter()
+        .map(|path| {
+            let source_filename = path
+                .strip_prefix(&dir)
+                .unwrap_or(&path)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            (source_filename, path)
+        })
+        .collect();
+    if files.is_empty() {
+        outln!("No files to upload in {}", dir.display());
+        return Ok(());
+    }
+
+    if dry_run {
+        let mut total_bytes = 0;
+        let rows = files
+            .iter()
+            .map(|(source_filename, path)| {
+                let bytes = fs::metadata(path).map_or(0, |metadata| metadata.len());
+                total_bytes += bytes;
+                vec![source_filename.clone(), format_bytes(bytes)]
+            })
+            .collect();
+        print_table(&["FILE", "SIZE"], rows);
+        outln!(
+            "Would create {} input artifacts from {} in project {}",
+            files.len(),
+            format_bytes(total_bytes),
+            project_id
+        );
+        return Ok(());
+    }
+
+    // Archiving a file takes disk space too, so whole uploads are bounded and not only the
+    // transfers
+    let semaphore = Arc::new(Semaphore::new(http::max_parallel_uploads()));
+    let task_handles: Vec<_> = files
+        .into_iter()
+        .map(|(source_filename, path)| {
+            let semaphore = semaphore.clone();
+            let project_id = project_id.clone();
+            let temp_dir = temp_dir.clone();
+            let options = options.clone();
+            let mut tags = tags.clone();
+            tags.insert(SOURCE_FILENAME_TAG.to_string(), source_filename.clone());
+
+            let handle = tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await.unwrap();
+                create_input_artifact(
+                    project_id,
+                    path.to_string_lossy().into_owned(),
+                    temp_dir,
+                    tags,
+                    &options,
+                )
+                .await
+            });
+            (source_filename, handle)
+        })
+        .collect();
+
+    let total = task_handles.len();
+    let mut rows = vec![];
+    let mut failed = 0;
+    let mut reused = 0;
+    for (source_filename, handle) in task_handles {
+        let result = handle.await.unwrap_or_else(|err| {
+            Err(DiceCliError::Internal {
+                message: panic_message(err),
+            })
+        });
+        match result {
+            Ok(uploaded) => {
+                let outcome = if uploaded.reused {
+                    reused += 1;
+                    "reused"
+                } else {
+                    "created"
+                };
+                rows.push(vec![
+                    source_filename,
+                    uploaded.artifact_id,
+                    outcome.to_string(),
+                ]);
+            }
+            Err(err) => {
+                eprintln!("Could not upload {}: {}", source_filename, err);
+                failed += 1;
+            }
+        }
+    }
+
+    print_table(&["FILE", "ARTIFACT ID", "OUTCOME"], rows);
+    outln!(
+        "Created {} and reused {} of {} input artifacts",
+        total - failed - reused,
+        reused,
+        total
+    );
+    if failed > 0 {
+        return Err(DiceCliError::UploadsFailed { failed, total });
+    }
+
+    Ok(())
+}
+
+/// Creates an input artifact record and marks it active once `body` has been uploaded.
+/// A streamed body cannot be replayed, so the upload itself is not retried.
+async fn create_input_artifact_from_body(
+    project_id: String,
+    body: reqwest::Body,
+    content_type: Option<String>,
+    content_length: Option<u64>,
+    tags: HashMap<String, String>,
+) -> Result<String, DiceCliError> {
+    limits::check_tags(&tags)?;
+    // Utilizing the rust SDK, get an upload link
+    let create_artifact_response = progress::create(
+        try_sdk_create("create artifact", || {
+            rust_sdk::api::artifact::create(CreateArtifactDTO {
+                entity_id: project_id.clone(),
+                entity_type: EntityType::Project,
+                artifact_type: ArtifactType::Input,
+                tags: tags.clone(),
+            })
+        }),
+        |response| response.id.clone(),
+    )
+    .await?;
+    cleanup::register_pending_resource("artifact", &create_artifact_response.id);
+
+    progress::phase(
+        progress::Phase::Upload,
+        Some(&create_artifact_response.id),
+        put_presigned(
+            create_artifact_response.uri.clone(),
+            body,
+            content_type.as_deref().unwrap_or(RAW_CONTENT_TYPE),
+            content_length,
+        ),
+    )
+    .await?;
+
+    // Set input artifact status to active
+    try_sdk("activate artifact", || {
+        rust_sdk::api::artifact::update(
+            create_artifact_response.id.clone(),
+            UpdateArtifactDTO {
+                status: ArtifactStatus::Active,
+            },
+        )
+    })
+    .await?;
+    cleanup::unregister_pending_resource(&create_artifact_response.id);
+    history::record(
+        "create-artifact",
+        &[("artifact", &create_artifact_response.id)],
+    );
+
+    Ok(create_artifact_response.id)
+}
+
+async fn upload_artifact_from_url(
+    project_id: String,
+    url: String,
+    content_type: Option<String>,
+    tags: HashMap<String, String>,
+) -> Result<(), DiceCliError> {
+    // Start fetching the remote resource before creating the artifact record
+    let source_response =
+        retry::with_configured_retry(|| get_for_download(http::client(), &url)).await?;
+    let content_length = source_response.content_length();
+
+    // Pipe the remote response body straight into the upload without touching the disk
+    let artifact_id = create_input_artifact_from_body(
+        project_id,
+        reqwest::Body::wrap_stream(source_response.bytes_stream()),
+        content_type,
+        content_length,
+        tags,
+    )
+    .await?;
+    outln!("Successfully uploaded input artifact from {}", url);
+    outln!("Created input artifact: {}", artifact_id);
+
+    Ok(())
+}
+
+async fn create_artifact_from_stdin(
+    project_id: String,
+    content_type: Option<String>,
+    tags: HashMap<String, String>,
+) -> Result<(), DiceCliError> {
+    // Refuse to wait on an interactive terminal that will never reach end of input
+    if atty::is(atty::Stream::Stdin) {
+        return Err(DiceCliError::NoStdin);
+    }
+
+    // Stream stdin into the upload in chunks as it arrives
+    let artifact_id = create_input_artifact_from_body(
+        project_id,
+        reqwest::Body::wrap_stream(ReaderStream::new(tokio::io::stdin())),
+        content_type,
+        None,
+        tags,
+    )
+    .await?;
+    outln!("Successfully uploaded input artifact from stdin");
+    outln!("Created input artifact: {}", artifact_id);
+
+    Ok(())
+}
+
+/// Runs `upload` on the file given with `--file`, resolved to an absolute path, or for `-` on
+/// stdin saved to a temporary file named `stdin_name`
+async fn with_input_file<T, F, Fut>(
+    file: String,
+    stdin_name: &str,
+    temp_dir: &Path,
+    upload: F,
+) -> Result<T, DiceCliError>
+where
+    F: FnOnce(String) -> Fut,
+    Fut: Future<Output = Result<T, DiceCliError>>,
+{
+    if file != paths::STDIN {
+        let path = paths::existing(Path::new(&file), "--file")?;
+        return upload(path.to_string_lossy().into_owned()).await;
+    }
+    if atty::is(atty::Stream::Stdin) {
+        return Err(DiceCliError::NoStdin);
+    }
+
+    let dir = cache::temp_file_path(temp_dir, "stdin")?;
+    cleanup::register_temp_file(&dir);
+    let result = match save_input(&mut io::stdin().lock(), stdin_name, &dir) {
+        Ok(path) => upload(path.to_string_lossy().into_owned()).await,
+        Err(err) => Err(err),
+    };
+    let _ = fs::remove_dir_all(&dir);
+    cleanup::unregister_temp_file(&dir);
+
+    result
+}
+
+/// Saves `input` in `dir` as a file named `name`, made safe for this platform, and returns
+/// its path; artifacts are archived under the name of the file
+fn save_input(input: &mut impl Read, name: &str, dir: &Path) -> Result<PathBuf, DiceCliError> {
+    let path = dir.join(archive::safe_file_name(name));
+    let write_failed = |source| DiceCliError::Io {
+        action: "write",
+        path: path.clone(),
+        source,
+    };
+    fs::create_dir_all(dir).map_err(write_failed)?;
+    let mut file = File::create(&path).map_err(write_failed)?;
+    io::copy(input, &mut file).map_err(write_failed)?;
+
+    Ok(path)
+}
+
+/// Copies artifacts into a project as input artifacts, streaming each download straight into
+/// its upload since the API has no server-side copy
+async fn copy_artifacts(
+    project_id: String,
+    artifact_id: Option<String>,
+    job_execution_id: Option<String>,
+    tags: HashMap<String, String>,
+) -> Result<(), DiceCliError> {
+    let artifact_ids = match (artifact_id, job_execution_id) {
+        (Some(artifact_id), _) => vec![artifact_id],
+        (None, Some(job_execution_id)) => try_sdk("list output artifacts", || {
+            rust_sdk::api::artifact::list(doc! {
+                "artifact_type": enum_to_string(&ArtifactType::Output),
+                "entity_id": job_execution_id.clone(),
+                "status": enum_to_string(&ArtifactStatus::Active)
+            })
+        })
+        .await?
+        .into_iter()
+        .map(|artifact| artifact.id.to_string())
+        .collect(),
+        (None, None) => {
+            return Err(DiceCliError::MissingArgument {
+                flag: "--artifact-id",
+            })
+        }
+    };
+
+    for source_id in artifact_ids {
+        let artifact_id =
+            copy_artifact_to_project(source_id.clone(), project_id.clone(), tags.clone()).await?;
+        outln!(
+            "Copied artifact {} to input artifact: {}",
+            source_id,
+            artifact_id
+        );
+    }
+
+    Ok(())
+}
+
+/// Registers the contents of an artifact as a new, active input artifact of another project
+/// and returns its ID. The API has no shallow copies, so the contents are streamed from the
+/// source download into the new upload. The source's tags are kept, with `tags` winning.
+async fn copy_artifact_to_project(
+    source_artifact_id: String,
+    target_project_id: String,
+    tags: HashMap<String, String>,
+) -> Result<String, DiceCliError> {
+    let source = try_sdk("fetch artifact", || {
+        rust_sdk::api::artifact::get(source_artifact_id.clone())
+    })
+    .await?;
+    let download_artifact_response = try_sdk("get a download link", || {
+        rust_sdk::api::artifact::download(source_artifact_id.clone())
+    })
+    .await?;
+    let source_response = retry::with_configured_retry(|| {
+        get_for_download(http::client(), &download_artifact_response.uri)
+    })
+    .await?;
+    let content_type = source_response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let content_length = source_response.content_length();
+
+    create_input_artifact_from_body(
+        target_project_id,
+        reqwest::Body::wrap_stream(source_response.bytes_stream()),
+        content_type,
+        content_length,
+        source.tags.into_iter().chain(tags).collect(),
+    )
+    .await
+}
+
+/// ID of a project named `name`, projects only being named by their name tag
+async fn find_project_by_name(name: &str) -> Result<Option<String>, DiceCliError> {
+    let projects = try_sdk("list projects", || {
+        rust_sdk::api::project::list(doc! { format!("tags.{}", NAME_TAG): name })
+    })
+    .await?;
+
+    Ok(projects.first().map(|project| project.id.to_string()))
+}
+
+async fn create_project(
+    description: String,
+    tags: HashMap<String, String>,
+    if_not_exists: bool,
+) -> Result<(), DiceCliError> {
+    limits::check_description(&description)?;
+    limits::check_tags(&tags)?;
+    if if_not_exists {
+        let name = required(tags.get(NAME_TAG).cloned(), "--name")?;
+        if let Some(project_id) = find_project_by_name(&name).await? {
+            outln!("Project already exists: {}", project_id);
+            return Ok(());
+        }
+    }
+
+    // Utilizing the rust SDK, create a project
+    let project_id = try_sdk_create("create project", || {
+        rust_sdk::api::project::create(CreateProjectDTO {
+            description: description.clone(),
+            tags: tags.clone(),
+        })
+    })
+    .await?;
+
+    outln!("Created project: {}", project_id);
+    history::record("create-project", &[("project", &project_id)]);
+
+    Ok(())
+}
+
+async fn update_project(
+    project_id: String,
+    description: Option<String>,
+    tags: Vec<(String, String)>,
+    remove_tags: Vec<String>,
+) -> Result<(), DiceCliError> {
+    if description.is_none() && tags.is_empty() && remove_tags.is_empty() {
+        return Err(DiceCliError::InvalidArgument {
+            message: "nothing to update, use --description, --tag or --remove-tag".to_string(),
+        });
+    }
+    if let Some((key, _)) = tags.iter().find(|(key, _)| remove_tags.contains(key)) {
+        return Err(DiceCliError::InvalidArgument {
+            message: format!(
+                "tag {} is both set with --tag and removed with --remove-tag",
+                key
+            ),
+        });
+    }
+
+    // The API replaces the whole document, so start from the current one to keep other tags
+    let project = try_sdk("fetch project", || {
+        rust_sdk::api::project::get(project_id.clone())
+    })
+    .await?;
+
+    let description = description.unwrap_or_else(|| project.description.clone());
+    let mut new_tags = project.tags.clone();
+    for key in &remove_tags {
+        if new_tags.remove(key).is_none() {
+            eprintln!(
+                "Warning: project {} has no tag {} to remove",
+                project_id, key
+            );
+        }
+    }
+    new_tags.extend(tags);
+    limits::check_description(&description)?;
+    limits::check_tags(&new_tags)?;
+
+    let mut changes = vec![];
+    if description != project.description {
+        changes.push(format!(
+            "description: {:?} -> {:?}",
+            project.description, description
+        ));
+    }
+    let mut keys: Vec<&String> = project.tags.keys().chain(new_tags.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        match (project.tags.get(key), new_tags.get(key)) {
+            (Some(old), Some(new)) if old != new => {
+                changes.push(format!("tags.{}: {:?} -> {:?}", key, old, new))
+            }
+            (Some(old), None) => changes.push(format!("tags.{}: {:?} -> (removed)", key, old)),
+            (None, Some(new)) => changes.push(format!("tags.{}: (none) -> {:?}", key, new)),
+            _ => (),
+        }
+    }
+
+    if changes.is_empty() {
+        outln!("Project {} is already up to date", project_id);
+        return Ok(());
+    }
+
+    try_sdk("update project", || {
+        rust_sdk::api::project::update(
+            project_id.clone(),
+            UpdateProjectDTO {
+                description: description.clone(),
+                tags: new_tags.clone(),
+            },
+        )
+    })
+    .await?;
+
+    outln!("Updated project {}:", project_id);
+    for change in changes {
+        outln!("  {}", change);
+    }
+    history::record("update-project", &[("project", &project_id)]);
+
+    Ok(())
+}
+
+/// ID of a job of the project named `name` by its name tag
+async fn find_job_by_name(project_id: &str, name: &str) -> Result<Option<String>, DiceCliError> {
+    let jobs = try_sdk("list jobs", || {
+        rust_sdk::api::job::list(doc! {
+            "project_id": project_id,
+            format!("tags.{}", NAME_TAG): name,
+        })
+    })
+    .await?;
+
+    Ok(jobs.first().map(|job| job.id.to_string()))
+}
+
+async fn create_job(
+    project_id: String,
+    runtime_id: String,
+    input_artifact_ids: Vec<String>,
+    tags: HashMap<String, String>,
+) -> Result<String, DiceCliError> {
+    limits::check_input_artifacts(&input_artifact_ids)?;
+    limits::check_tags(&tags)?;
+    limits::check_parameters(&tag_parameters(&tags))?;
+    // Names are only a convention, so a duplicate is allowed but likely a mistake
+    if let Some(name) = tags.get(NAME_TAG) {
+        if let Some(job_id) = find_job_by_name(&project_id, name).await? {
+            eprintln!(
+                "Warning: job {} in project {} is already named \"{}\"",
+                job_id, project_id, name
+            );
+        }
+    }
+
+    // Utilizing the rust SDK, create a job
+    let create_job_response = progress::create(
+        try_sdk_create("create job", || {
+            rust_sdk::api::job::create(CreateJobDTO {
+                project_id: project_id.clone(),
+                runtime_id: runtime_id.clone(),
+                input_artifact_ids: input_artifact_ids.clone(),
+                tags: tags.clone(),
+            })
+        }),
+        |response| response.id.clone(),
+    )
+    .await?;
+
+    outln!("Created job: {}", create_job_response.id);
+    history::record("create-job", &[("job", &create_job_response.id)]);
+
+    Ok(create_job_response.id)
+}
+
+/// Why an entity a new job refers to cannot be used, or `None` when it can. An API error
+/// while the API is reachable is taken as the entity not existing.
+fn reference_problem<T>(
+    fetched: Result<Result<T, DiceCliError>, JoinError>,
+    check: impl FnOnce(T) -> Option<String>,
+) -> Result<Option<String>, DiceCliError> {
+    let fetched = fetched.map_err(|err| DiceCliError::Internal {
+        message: format!("the check of an ID failed: {}", err),
+    })?;
+    match fetched {
+        Ok(entity) => Ok(check(entity)),
+        Err(err) if err.is_unreachable() => Err(err),
+        Err(DiceCliError::Api { message, .. }) => {
+            Ok(Some(format!("could not be fetched: {}", message)))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Fetches the project, runtime and input artifacts of a new job at once, and fails listing
+/// every one that is missing or not active, where the API would only fail with an opaque error
+async fn precheck_create_job(
+    project_id: &str,
+    runtime_id: &str,
+    input_artifact_ids: &[String],
+) -> Result<(), DiceCliError> {
+    let project = {
+        let project_id = project_id.to_string();
+        tokio::spawn(try_sdk("fetch project", move || {
+            rust_sdk::api::project::get(project_id.clone())
+        }))
+    };
+    let runtime = {
+        let runtime_id = runtime_id.to_string();
+        tokio::spawn(try_sdk("fetch runtime", move || {
+            rust_sdk::api::runtime::get(runtime_id.clone())
+        }))
+    };
+    let artifacts: Vec<_> = input_artifact_ids
+        .iter()
+        .map(|artifact_id| {
+            let artifact_id = artifact_id.clone();
+            tokio::spawn(try_sdk("fetch artifact", move || {
+                rust_sdk::api::artifact::get(artifact_id.clone())
+            }))
+        })
+        .collect();
+
+    let mut problems = vec![];
+    if let Some(reason) = reference_problem(project.await, |_| None)? {
+        problems.push(format!("project {} {}", project_id, reason));
+    }
+    let runtime_problem = reference_problem(runtime.await, |runtime| {
+        if runtime.status != RuntimeStatus::Active {
+            Some(format!("is {:?}, not Active", runtime.status))
+        } else if runtime.project_id != project_id {
+            Some(format!("belongs to project {}", runtime.project_id))
+        } else {
+            None
+        }
+    })?;
+    if let Some(reason) = runtime_problem {
+        problems.push(format!("runtime {} {}", runtime_id, reason));
+    }
+    for (artifact_id, artifact) in input_artifact_ids.iter().zip(artifacts) {
+        let artifact_problem = reference_problem(artifact.await, |artifact| {
+            (artifact.status != ArtifactStatus::Active)
+                .then(|| format!("is {:?}, not Active", artifact.status))
+        })?;
+        if let Some(reason) = artifact_problem {
+            problems.push(format!("input artifact {} {}", artifact_id, reason));
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(DiceCliError::InvalidReferences { problems });
+    }
+
+    Ok(())
+}
+
+/// Changes `--clone-job` makes to the source job
+struct JobEdits {
+    runtime_id: Option<String>,
+    replace_inputs: Vec<(String, String)>,
+    add_inputs: Vec<String>,
+    remove_inputs: Vec<String>,
+}
+
+/// Fails unless every artifact exists and is active, so a job is not created with inputs its
+/// executions cannot download
+async fn ensure_artifacts_active(artifact_ids: &[String]) -> Result<(), DiceCliError> {
+    let object_ids = artifact_ids
+        .iter()
+        .map(|id| {
+            ObjectId::parse_str(id).map_err(|_| DiceCliError::InvalidArgument {
+                message: format!("{} is not a valid artifact ID", id),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let artifacts = try_sdk("list artifacts", || {
+        rust_sdk::api::artifact::list(doc! { "_id": { "$in": object_ids.clone() } })
+    })
+    .await?;
+
+    for artifact_id in artifact_ids {
+        let reason = match artifacts
+            .iter()
+            .find(|artifact| &artifact.id.to_string() == artifact_id)
+        {
+            None => "does not exist".to_string(),
+            Some(artifact) if artifact.status != ArtifactStatus::Active => {
+                format!("is {:?}, not Active", artifact.status)
+            }
+            Some(_) => continue,
+        };
+        return Err(DiceCliError::ArtifactUnavailable {
+            artifact_id: artifact_id.clone(),
+            reason,
+        });
+    }
+
+    Ok(())
+}
+
+/// Prints how a cloned job differs from its source, removals in red and additions in green
+fn print_job_diff(
+    source: &Job,
+    runtime_id: &str,
+    input_artifact_ids: &[String],
+    tags: &HashMap<String, String>,
+) {
+    let removed = |line: String| outln!("{}", style(format!("- {}", line)).red());
+    let added = |line: String| outln!("{}", style(format!("+ {}", line)).green());
+
+    outln!("Changes from job {}:", source.id);
+    if runtime_id != source.runtime_id {
+        removed(format!("runtime_id {}", source.runtime_id));
+        added(format!("runtime_id {}", runtime_id));
+    }
+    for id in &source.input_artifact_ids {
+        if !input_artifact_ids.contains(id) {
+            removed(format!("input artifact {}", id));
+        }
+    }
+    for id in input_artifact_ids {
+        if !source.input_artifact_ids.contains(id) {
+            added(format!("input artifact {}", id));
+        }
+    }
+
+    let keys: BTreeSet<&String> = source.tags.keys().chain(tags.keys()).collect();
+    for key in keys {
+        let (old, new) = (source.tags.get(key), tags.get(key));
+        if old == new {
+            continue;
+        }
+        if let Some(old) = old {
+            removed(format!("tag {}={}", key, old));
+        }
+        if let Some(new) = new {
+            added(format!("tag {}={}", key, new));
+        }
+    }
+}
+
+/// Creates a job with the inputs and runtime of another one, changed by `edits`. The source's
+/// tags are kept, `tags` win over them and `cloned_from` records the source.
+async fn clone_job(
+    job_id: String,
+    edits: JobEdits,
+    tags: HashMap<String, String>,
+) -> Result<String, DiceCliError> {
+    let source = try_sdk("fetch job", || rust_sdk::api::job::get(job_id.clone())).await?;
+    let not_an_input = |flag: &str, id: &str| DiceCliError::InvalidArgument {
+        message: format!("{} {}: not an input artifact of job {}", flag, id, job_id),
+    };
+
+    let mut input_artifact_ids = source.input_artifact_ids.clone();
+    for id in &edits.remove_inputs {
+        if !input_artifact_ids.contains(id) {
+            return Err(not_an_input("--remove-input", id));
+        }
+        input_artifact_ids.retain(|input| input != id);
+    }
+    for (old, new) in &edits.replace_inputs {
+        let position = input_artifact_ids
+            .iter()
+            .position(|input| input == old)
+            .ok_or_else(|| not_an_input("--replace-input", old))?;
+        input_artifact_ids[position] = new.clone();
+    }
+    for id in &edits.add_inputs {
+        if !input_artifact_ids.contains(id) {
+            input_artifact_ids.push(id.clone());
+        }
+    }
+    if input_artifact_ids.is_empty() {
+        return Err(DiceCliError::InvalidArgument {
+            message: format!("the clone of job {} would have no input artifacts", job_id),
+        });
+    }
+    ensure_artifacts_active(&input_artifact_ids).await?;
+
+    let runtime_id = edits
+        .runtime_id
+        .unwrap_or_else(|| source.runtime_id.clone());
+    let mut clone_tags = source.tags.clone();
+    clone_tags.extend(tags);
+    clone_tags.insert(CLONED_FROM_TAG.to_string(), job_id.clone());
+
+    print_job_diff(&source, &runtime_id, &input_artifact_ids, &clone_tags);
+    create_job(
+        source.project_id,
+        runtime_id,
+        input_artifact_ids,
+        clone_tags,
+    )
+    .await
+}
+
+fn init_job_file() -> Result<(), DiceCliError> {
+    let path = Path::new(JOB_FILE_NAME);
+    if path.exists() {
+        return Err(DiceCliError::AlreadyExists {
+            path: path.to_path_buf(),
+        });
+    }
+
+    fs::write(path, JOB_FILE_TEMPLATE).map_err(|source| DiceCliError::Io {
+        action: "write",
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    outln!("Created job definition file: {}", JOB_FILE_NAME);
+
+    Ok(())
+}
+
+async fn create_job_execution(
+    job_id: String,
+    tags: HashMap<String, String>,
+) -> Result<String, DiceCliError> {
+    limits::check_tags(&tags)?;
+    limits::check_parameters(&tag_parameters(&tags))?;
+    retry::throttle().await;
+
+    // Utilizing the rust SDK, create a job execution
+    let create_job_execution_response = progress::create(
+        try_sdk_create("create job execution", || {
+            rust_sdk::api::job_execution::create(CreateJobExecutionDTO {
+                job_id: job_id.clone(),
+                tags: tags.clone(),
+            })
+        }),
+        |response| response.id.clone(),
+    )
+    .await?;
+
+    Ok(create_job_execution_response.id)
+}
+
+fn format_parameters(parameters: &BTreeMap<String, String>) -> String {
+    let parameters: Vec<_> = parameters
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    parameters.join(", ")
+}
+
+fn print_parameters(parameters: &BTreeMap<String, String>) {
+    if !parameters.is_empty() {
+        outln!("Parameters: {}", format_parameters(parameters));
+    }
+}
+
+async fn create_job_executions(
+    job_ids: Vec<String>,
+    count: u32,
+    tag_index: Option<String>,
+    tags_template: HashMap<String, String>,
+    concurrency: usize,
+    format: OutputFormat,
+    on_error: OnErrorStrategy,
+) -> Result<(), DiceCliError> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let parameters = tag_parameters(&tags_template);
+
+    // Fan out one task per requested execution, bounded by the semaphore
+    let task_handles: Vec<_> = job_ids
+        .into_iter()
+        .flat_map(|job_id| (0..count).map(move |index| (job_id.clone(), index)))
+        .map(|(job_id, index)| {
+            let semaphore = semaphore.clone();
+            let mut tags = tags_template.clone();
+            if let Some(tag_index) = &tag_index {
+                tags.insert(tag_index.clone(), index.to_string());
+            }
+
+            let handle = tokio::spawn({
+                let job_id = job_id.clone();
+                async move {
+                    // The semaphore is closed to cancel the executions that have not started
+                    let _permit = semaphore.acquire_owned().await.ok()?;
+                    Some(match on_error {
+                        OnErrorStrategy::Retry => {
+                            retry::with_configured_task_retry(
+                                || create_job_execution(job_id.clone(), tags.clone()),
+                                DiceCliError::is_retryable_create,
+                            )
+                            .await
+                        }
+                        _ => create_job_execution(job_id, tags).await,
+                    })
+                }
+            });
+
+            (job_id, handle)
+        })
+        .collect();
+
+    let total = task_handles.len();
+    let mut results = vec![];
+    for (job_id, handle) in task_handles {
+        let result = match handle.await {
+            Ok(None) => continue,
+            Ok(Some(Ok(execution_id))) => CreatedJobExecution {
+                job_id,
+                execution_id: Some(execution_id),
+                error: None,
+                parameters: parameters.clone(),
+            },
+            Ok(Some(Err(err))) => CreatedJobExecution {
+                job_id,
+                execution_id: None,
+                error: Some(err.to_string()),
+                parameters: BTreeMap::new(),
+            },
+            Err(err) => CreatedJobExecution {
+                job_id,
+                execution_id: None,
+                error: Some(panic_message(err)),
+                parameters: BTreeMap::new(),
+            },
+        };
+
+        if let Some(execution_id) = &result.execution_id {
+            history::record(
+                "create-job-execution",
+                &[("job", &result.job_id), ("job_execution", execution_id)],
+            );
+        }
+
+        if format != OutputFormat::Json {
+            match (&result.execution_id, &result.error) {
+                (Some(execution_id), _) => outln!("Created job execution: {}", execution_id),
+                (None, Some(error)) => eprintln!(
+                    "Could not create job execution for job {}: {}",
+                    result.job_id, error
+                ),
+                (None, None) => (),
+            }
+        }
+
+        if result.error.is_some() && on_error == OnErrorStrategy::Stop {
+            semaphore.close();
+        }
+        results.push(result);
+    }
+
+    let failed = results
+        .iter()
+        .filter(|result| result.error.is_some())
+        .count();
+    let created = results.len() - failed;
+    match format {
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&results).unwrap()),
+        OutputFormat::Table | OutputFormat::Raw if total > 1 => {
+            outln!("Created {} of {} job executions", created, total);
+            print_parameters(&parameters);
+            if results.len() < total {
+                outln!(
+                    "Stopped after the first failure, {} job executions were not attempted",
+                    total - results.len()
+                );
+            }
+        }
+        OutputFormat::Table | OutputFormat::Raw => print_parameters(&parameters),
+    }
+
+    if created < total {
+        return Err(DiceCliError::JobExecutionsFailed {
+            failed: total - created,
+            total,
+        });
+    }
+
+    Ok(())
+}
+
+/// Tag key set to the index of each job execution created by `--batch-create-job-executions`
+const BATCH_INDEX_TAG: &str = "batch_index";
+
+async fn batch_create_job_executions(
+    job_id: String,
+    count: u32,
+    tags_template: HashMap<String, String>,
+    concurrency: usize,
+    format: OutputFormat,
+    on_error: OnErrorStrategy,
+) -> Result<(), DiceCliError> {
+    create_job_executions(
+        vec![job_id],
+        count,
+        Some(BATCH_INDEX_TAG.to_string()),
+        tags_template,
+        concurrency,
+        format,
+        on_error,
+    )
+    .await
+}
+
+/// Formats a number of seconds as e.g. `1h 2m 3s`
+fn format_duration(seconds: i64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, _) => format!("{}m {}s", minutes, seconds),
+        _ => format!("{}h {}m {}s", hours, minutes, seconds),
+    }
+}
+
+/// Shown for timestamps and durations an execution does not record yet
+const NOT_AVAILABLE: &str = "—";
+
+/// Formats a timestamp in the local time zone
+fn format_local_time(time: Option<DateTime<Utc>>) -> String {
+    time.map_or(NOT_AVAILABLE.to_string(), |time| {
+        time.with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S %:z")
+            .to_string()
+    })
+}
+
+/// Formats the time between two timestamps, if both are known
+fn format_elapsed(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> String {
+    match (from, to) {
+        (Some(from), Some(to)) => format_duration((to - from).num_seconds().max(0)),
+        _ => NOT_AVAILABLE.to_string(),
+    }
+}
+
+fn print_job_execution_summary(job_execution: &JobExecution, output_artifacts: &[Artifact]) {
+    let created = stats::created_at(&job_execution.id);
+    let finished = stats::is_finished(job_execution);
+    let completed = stats::finished_at(job_execution, &stats::finish_times(output_artifacts));
+
+    outln!("Job execution");
+    outln!("  ID:               {}", job_execution.id);
+    outln!("  Job:              {}", job_execution.job_id);
+    outln!(
+        "  Status:           {}",
+        color::job_execution_status(&job_execution.status)
+    );
+    outln!("  Created:          {}", format_local_time(Some(created)));
+    // The API records no start or end time, so a finished execution is dated by its last
+    // output artifact and a running one is measured up to now
+    if finished {
+        outln!("  Completed:        {}", format_local_time(completed));
+    }
+    let end = match job_execution.status {
+        JobExecutionStatus::Running => Some(Utc::now()),
+        _ => completed,
+    };
+    outln!("  Duration:         {}", format_elapsed(Some(created), end));
+    outln!("  Output artifacts: {}", output_artifacts.len());
+    let parameters = tag_parameters(&job_execution.tags);
+    if !parameters.is_empty() {
+        outln!("  Parameters:       {}", format_parameters(&parameters));
+    }
+    if !job_execution.tags.is_empty() {
+        outln!("  Tags:             {}", format_tags(&job_execution.tags));
+    }
+}
+
+async fn get_job(job_id: String, format: OutputFormat) -> Result<(), DiceCliError> {
+    let job = try_sdk("fetch job", || rust_sdk::api::job::get(job_id.clone())).await?;
+
+    match format {
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&job).unwrap()),
+        OutputFormat::Table | OutputFormat::Raw => {
+            outln!("Job");
+            outln!("  ID:               {}", job.id);
+            if let Some(name) = job.tags.get(NAME_TAG) {
+                outln!("  Name:             {}", name);
+            }
+            outln!("  Project:          {}", job.project_id);
+            outln!("  Runtime:          {}", job.runtime_id);
+            outln!("  Input artifacts:  {}", job.input_artifact_ids.join(", "));
+            let parameters = tag_parameters(&job.tags);
+            if !parameters.is_empty() {
+                outln!("  Parameters:       {}", format_parameters(&parameters));
+            }
+            if !job.tags.is_empty() {
+                outln!("  Tags:             {}", format_tags(&job.tags));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn get_job_execution(
+    job_execution_id: String,
+    format: OutputFormat,
+) -> Result<(), DiceCliError> {
+    // Utilizing the rust SDK, get an existing job execution
+    let job_execution = try_sdk("fetch job execution", || {
+        rust_sdk::api::job_execution::get(job_execution_id.clone())
+    })
+    .await?;
+
+    match format {
+        // The whole document, with any fields the summary leaves out
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&job_execution).unwrap()),
+        OutputFormat::Raw => outln!("Job execution: {:?}", job_execution),
+        OutputFormat::Table => {
+            let output_artifacts = try_sdk("list output artifacts", || {
+                rust_sdk::api::artifact::list(doc! {
+                    "artifact_type": enum_to_string(&ArtifactType::Output),
+                    "entity_id": job_execution.id,
+                })
+            })
+            .await?;
+            print_job_execution_summary(&job_execution, &output_artifacts);
+        }
+    }
+
+    Ok(())
+}
+
+/// Size of a download, asking for a single byte so presigned GET links can be used
+async fn artifact_download_size(client: &reqwest::Client, uri: &str) -> Option<u64> {
+    let response = client
+        .get(uri)
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .send()
+        .await
+        .ok()?;
+
+    match response.headers().get(reqwest::header::CONTENT_RANGE) {
+        Some(content_range) => content_range
+            .to_str()
+            .ok()?
+            .rsplit('/')
+            .next()?
+            .parse()
+            .ok(),
+        None if response.status().is_success() => response.content_length(),
+        None => None,
+    }
+}
+
+/// Sends a GET request, treating any unsuccessful status as a failed download
+async fn get_for_download(
+    client: reqwest::Client,
+    uri: &str,
+) -> Result<reqwest::Response, DiceCliError> {
+    let response =
+        client
+            .get(uri)
+            .send()
+            .await
+            .map_err(|source| DiceCliError::ConnectionFailed {
+                uri_host: uri_host(uri),
+                source,
+            })?;
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(DiceCliError::RateLimited {
+            uri_host: uri_host(uri),
+            retry_after: http::retry_after(&response),
+        });
+    }
+    if !response.status().is_success() {
+        return Err(DiceCliError::DownloadFailed {
+            uri_host: uri_host(uri),
+            status: response.status().as_u16(),
+        });
+    }
+
+    Ok(response)
+}
+
+/// Downloads and unpacks an output artifact into `job_root_path`, returning the size and
+/// SHA-256 digest of the downloaded archive
+async fn download_output_artifact(
+    client: reqwest::Client,
+    artifact_id: String,
+    uri: String,
+    job_root_path: PathBuf,
+    shared_directory: Option<Arc<archive::ClaimedEntries>>,
+    temp_dir: PathBuf,
+    progress_bar: ProgressBar,
+) -> Result<(u64, String), DiceCliError> {
+    //  Download artifact into a partial file, only taking its final name once complete.
+    //  Artifacts may be plain, gzip or zstd compressed tarballs, extraction detects which.
+    let tar_file_path = cache::temp_file_path(&temp_dir, &format!("{}.tar", artifact_id))?;
+    let partial_file_path = tar_file_path.with_extension("tar.partial");
+    cleanup::register_temp_file(&partial_file_path);
+
+    let (bytes, digest) = progress::phase(progress::Phase::Download, Some(&artifact_id), async {
+        let mut response =
+            retry::with_configured_retry(|| get_for_download(client.clone(), &uri)).await?;
+
+        let write_failed = |source| DiceCliError::Io {
+            action: "write",
+            path: partial_file_path.clone(),
+            source,
+        };
+        let mut artifact_file = File::create(&partial_file_path).map_err(write_failed)?;
+        let mut transfer = progress::Transfer::download(&artifact_id, response.content_length());
+        let mut hasher = Sha256::new();
+        let mut bytes = 0;
+        while let Some(chunk) =
+            response
+                .chunk()
+                .await
+                .map_err(|source| DiceCliError::ConnectionFailed {
+                    uri_host: uri_host(&uri),
+                    source,
+                })?
+        {
+            artifact_file.write_all(&chunk).map_err(write_failed)?;
+            hasher.update(&chunk);
+            bytes += chunk.len() as u64;
+            progress_bar.inc(chunk.len() as u64);
+            transfer.advance(chunk.len() as u64);
+        }
+
+        Ok((bytes, format!("{:x}", hasher.finalize())))
+    })
+    .await?;
+
+    cleanup::register_temp_file(&tar_file_path);
+    fs::rename(&partial_file_path, &tar_file_path).map_err(|source| DiceCliError::Io {
+        action: "move downloaded artifact to",
+        path: tar_file_path.clone(),
+        source,
+    })?;
+    cleanup::unregister_temp_file(&partial_file_path);
+
+    //  Untar the artifact into the job directory
+    progress::phase_blocking(progress::Phase::Extract, Some(&artifact_id), || {
+        archive::extract_archive_atomically(
+            &tar_file_path,
+            &job_root_path,
+            &artifact_id,
+            shared_directory.as_deref(),
+        )
+    })?;
+
+    //  Delete tar file
+    fs::remove_file(&tar_file_path).map_err(|source| DiceCliError::Io {
+        action: "delete",
+        path: tar_file_path.clone(),
+        source,
+    })?;
+    cleanup::unregister_temp_file(&tar_file_path);
+
+    Ok((bytes, digest))
+}
+
+/// An output artifact resolved to a download link, with the directory it is unpacked into
+struct OutputArtifactDownload {
+    artifact_id: String,
+    name: Option<String>,
+    uri: String,
+    size: Option<u64>,
+    job_root_path: PathBuf,
+    /// Whether other artifacts are extracted into the same directory
+    shared_directory: bool,
+}
+
+/// Creates the directory of a job execution and resolves the download links and sizes of
+/// its output artifacts that match the filters, also returning how many were listed
+async fn resolve_output_artifacts(
+    client: &reqwest::Client,
+    job_execution_id: ObjectId,
+    output_dir: &Path,
+    options: &DownloadOptions,
+) -> Result<(Vec<OutputArtifactDownload>, usize), DiceCliError> {
+    // Create directory for job
+    let job_root_path = output_dir.join(job_execution_id.to_string());
+    fs::create_dir_all(&job_root_path).map_err(|source| DiceCliError::Io {
+        action: "create",
+        path: job_root_path.clone(),
+        source,
+    })?;
+
+    // Get list of output artifacts for job execution
+    let artifacts = try_sdk("list output artifacts", || {
+        rust_sdk::api::artifact::list(doc! {
+            "artifact_type": enum_to_string(&ArtifactType::Output),
+            "entity_id": job_execution_id,
+            "status": enum_to_string(&ArtifactStatus::Active)
+        })
+    })
+    .await?;
+    let listed = artifacts.len();
+
+    let mut downloads = vec![];
+    let mut used_names = HashSet::new();
+    for artifact in artifacts {
+        if !options.matches(&artifact.tags) {
+            continue;
+        }
+
+        // Unnamed artifacts and repeated names fall back to the artifact ID
+        let artifact_path = match artifact.tags.get(NAME_TAG) {
+            _ if options.flatten => job_root_path.clone(),
+            Some(name) => {
+                let name = archive::safe_file_name(name);
+                if used_names.insert(name.clone()) {
+                    job_root_path.join(name)
+                } else {
+                    job_root_path.join(format!("{}-{}", name, artifact.id))
+                }
+            }
+            None => job_root_path.join(artifact.id.to_string()),
+        };
+
+        // Directories of their own are only moved into place once complete
+        if !options.flatten && artifact_path.exists() && !options.force {
+            outln!(
+                "Skipping artifact {}, already extracted to {}",
+                artifact.id,
+                artifact_path.display()
+            );
+            continue;
+        }
+
+        let download_artifact_response = try_sdk("get a download link", || {
+            rust_sdk::api::artifact::download(artifact.id.to_string())
+        })
+        .await?;
+        let size = artifact_download_size(client, &download_artifact_response.uri).await;
+
+        downloads.push(OutputArtifactDownload {
+            artifact_id: artifact.id.to_string(),
+            name: artifact.tags.get(NAME_TAG).cloned(),
+            uri: download_artifact_response.uri,
+            size,
+            job_root_path: artifact_path,
+            shared_directory: options.flatten,
+        });
+    }
+
+    Ok((downloads, listed))
+}
+
+/// Options shared by the commands that download output artifacts
+#[derive(Clone, Debug)]
+struct DownloadOptions {
+    ignore_space_check: bool,
+    /// Extract all artifacts of a job execution into one directory instead of one each
+    flatten: bool,
+    on_error: OnErrorStrategy,
+    filter_tags: Vec<(String, String)>,
+    filter_name: Option<String>,
+    allow_empty: bool,
+    force: bool,
+    max_parallel: usize,
+    verbose: bool,
+}
+
+impl DownloadOptions {
+    fn is_filtered(&self) -> bool {
+        !self.filter_tags.is_empty() || self.filter_name.is_some()
+    }
+
+    /// Whether an artifact has every `--filter-tag` and a name matching `--filter-name`
+    fn matches(&self, tags: &HashMap<String, String>) -> bool {
+        let name_matches = match &self.filter_name {
+            Some(pattern) => tags
+                .get(NAME_TAG)
+                .is_some_and(|name| glob_match(pattern, name)),
+            None => true,
+        };
+        name_matches
+            && self
+                .filter_tags
+                .iter()
+                .all(|(key, value)| tags.get(key) == Some(value))
+    }
+
+    /// Reports how many of the `listed` artifacts the filters kept, failing when they kept
+    /// none unless `--allow-empty` is set
+    fn check_matched(&self, matched: usize, listed: usize) -> Result<(), DiceCliError> {
+        if !self.is_filtered() {
+            return Ok(());
+        }
+
+        outln!(
+            "{} of {} output artifacts match the filters",
+            matched,
+            listed
+        );
+        if matched == 0 && !self.allow_empty {
+            return Err(DiceCliError::NoArtifactsMatched { listed });
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks the free space of `output_dir`, then downloads and unpacks all artifacts in
+/// parallel, adding each downloaded and failed artifact to `report`
+async fn run_output_artifact_downloads(
+    client: reqwest::Client,
+    downloads: Vec<OutputArtifactDownload>,
+    output_dir: &Path,
+    temp_dir: PathBuf,
+    options: &DownloadOptions,
+    report: &mut DownloadReport,
+) -> Result<(), DiceCliError> {
+    let known_size: u64 = downloads.iter().filter_map(|download| download.size).sum();
+    outln!(
+        "Downloading {} artifacts, {} total",
+        downloads.len(),
+        format_bytes(known_size)
+    );
+    for download in downloads.iter().filter(|download| download.size.is_none()) {
+        outln!("Size of artifact {} is unknown", download.artifact_id);
+    }
+
+    if !options.ignore_space_check {
+        let available = fs2::available_space(output_dir).map_err(|source| DiceCliError::Io {
+            action: "determine the free space of",
+            path: output_dir.to_path_buf(),
+            source,
+        })?;
+        if known_size > available {
+            return Err(DiceCliError::NotEnoughDiskSpace {
+                path: output_dir.to_path_buf(),
+                needed: format_bytes(known_size),
+                available: format_bytes(available),
+            });
+        }
+    }
+
+    let template = if options.verbose {
+        "{bar:40} {bytes}/{total_bytes} ({eta}) {msg}"
+    } else {
+        "{bar:40} {bytes}/{total_bytes} ({eta})"
+    };
+    let progress_bar =
+        ProgressBar::new(known_size).with_style(ProgressStyle::with_template(template).unwrap());
+    if progress::is_json() {
+        progress_bar.set_draw_target(ProgressDrawTarget::hidden());
+    }
+
+    // For each artifact, download it, untar it, and then remove the tar file, bounded by the
+    // semaphore
+    let on_error = options.on_error;
+    let semaphore = Arc::new(Semaphore::new(options.max_parallel.max(1)));
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let claimed = Arc::new(archive::ClaimedEntries::default());
+    let task_handles: Vec<_> = downloads
+        .into_iter()
+        .map(|download| {
+            let client = client.clone();
+            let temp_dir = temp_dir.clone();
+            let progress_bar = progress_bar.clone();
+            let semaphore = semaphore.clone();
+            let in_flight = in_flight.clone();
+            let shared_directory = download.shared_directory.then(|| claimed.clone());
+            let artifact_id = download.artifact_id.clone();
+            let handle = tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await.unwrap();
+                let show_in_flight = |count: usize| {
+                    progress_bar.set_message(format!("{} downloads in flight", count))
+                };
+                show_in_flight(in_flight.fetch_add(1, Ordering::SeqCst) + 1);
+
+                let attempt = || {
+                    download_output_artifact(
+                        client.clone(),
+                        download.artifact_id.clone(),
+                        download.uri.clone(),
+                        download.job_root_path.clone(),
+                        shared_directory.clone(),
+                        temp_dir.clone(),
+                        progress_bar.clone(),
+                    )
+                };
+                let result = match on_error {
+                    OnErrorStrategy::Retry => {
+                        retry::with_configured_task_retry(attempt, DiceCliError::is_retryable).await
+                    }
+                    _ => attempt().await,
+                };
+                show_in_flight(in_flight.fetch_sub(1, Ordering::SeqCst) - 1);
+
+                match result {
+                    Ok((bytes, sha256)) => Ok(ArtifactReceipt {
+                        artifact_id: download.artifact_id,
+                        name: download.name,
+                        path: download.job_root_path,
+                        bytes,
+                        sha256,
+                    }),
+                    // Name the artifact, several are downloaded at once
+                    Err(source) => Err(DiceCliError::Artifact {
+                        artifact_id: download.artifact_id,
+                        source: Box::new(source),
+                    }),
+                }
+            });
+
+            (artifact_id, handle)
+        })
+        .collect();
+
+    let total = task_handles.len();
+    let mut failed = 0;
+    let mut task_handles = task_handles.into_iter();
+    while let Some((artifact_id, handle)) = task_handles.next() {
+        let result = handle.await.map_err(|err| DiceCliError::Internal {
+            message: format!("could not download output artifact: {}", panic_message(err)),
+        });
+        let err = match result.and_then(|result| result) {
+            Ok(downloaded) => {
+                progress_bar.suspend(|| {
+                    outln!(
+                        "Extracted artifact {} into {}",
+                        downloaded.artifact_id,
+                        downloaded.path.display()
+                    )
+                });
+                report.output_artifacts.push(downloaded);
+                continue;
+            }
+            Err(err) => err,
+        };
+        report
+            .failed
+            .push(FailedStep::new(Some(artifact_id.clone()), &err));
+
+        if options.on_error == OnErrorStrategy::Stop {
+            for (artifact_id, handle) in task_handles {
+                handle.abort();
+                let cancelled = DiceCliError::Internal {
+                    message: "download cancelled after an earlier failure".to_string(),
+                };
+                report
+                    .failed
+                    .push(FailedStep::new(Some(artifact_id), &cancelled));
+            }
+            progress_bar.abandon();
+            return Err(err);
+        }
+        progress_bar.suspend(|| eprintln!("Error: {}", err));
+        failed += 1;
+    }
+    progress_bar.finish();
+
+    if failed > 0 {
+        return Err(DiceCliError::DownloadsFailed { failed, total });
+    }
+
+    Ok(())
+}
+
+/// Time between status checks while waiting for a job execution to finish
+const JOB_EXECUTION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Whether to keep polling a job execution, failing once it has been unfinished for longer
+/// than the timeout
+fn keep_waiting(
+    id: &str,
+    status: &JobExecutionStatus,
+    elapsed: Duration,
+    timeout: Duration,
+) -> Result<bool, DiceCliError> {
+    if !matches!(
+        status,
+        JobExecutionStatus::Pending | JobExecutionStatus::Running
+    ) {
+        return Ok(false);
+    }
+    if elapsed >= timeout {
+        return Err(DiceCliError::WaitTimedOut {
+            id: id.to_string(),
+            status: format!("{:?}", status),
+            seconds: timeout.as_secs(),
+        });
+    }
+    Ok(true)
+}
+
+/// Checks that the output artifacts of a job execution in this status may be downloaded,
+/// returning whether they may be incomplete because it has not completed
+fn check_downloadable(
+    id: &str,
+    status: &JobExecutionStatus,
+    allow_status: &[String],
+) -> Result<bool, DiceCliError> {
+    if *status == JobExecutionStatus::Completed {
+        return Ok(false);
+    }
+    if !allow_status.contains(&enum_to_string(status)) {
+        return Err(DiceCliError::JobExecutionNotCompleted {
+            id: id.to_string(),
+            status: format!("{:?}", status),
+        });
+    }
+    Ok(true)
+}
+
+/// Polls a job execution until it is no longer pending or running
+async fn wait_for_job_execution(
+    job_execution_id: String,
+    timeout: Duration,
+) -> Result<JobExecution, DiceCliError> {
+    let started = Instant::now();
+    loop {
+        let job_execution = try_sdk("fetch job execution", || {
+            rust_sdk::api::job_execution::get(job_execution_id.clone())
+        })
+        .await?;
+        if !keep_waiting(
+            &job_execution_id,
+            &job_execution.status,
+            started.elapsed(),
+            timeout,
+        )? {
+            return Ok(job_execution);
+        }
+
+        outln!(
+            "Waiting for job execution {} to finish, it is {}",
+            job_execution_id,
+            color::job_execution_status(&job_execution.status)
+        );
+        tokio::time::sleep(JOB_EXECUTION_POLL_INTERVAL).await;
+    }
+}
+
+async fn download_output_artifacts(
+    job_execution_id: String,
+    output_dir: PathBuf,
+    temp_dir: PathBuf,
+    options: DownloadOptions,
+    allow_status: Vec<String>,
+    wait: Option<Duration>,
+    receipt_path: Option<PathBuf>,
+) -> Result<(), DiceCliError> {
+    // Utilizing the rust SDK, get an existing job execution
+    let started = Instant::now();
+    let job_execution = match wait {
+        Some(timeout) => {
+            let id = job_execution_id.clone();
+            progress::phase(
+                progress::Phase::Poll,
+                Some(&id),
+                wait_for_job_execution(job_execution_id, timeout),
+            )
+            .await?
+        }
+        None => {
+            try_sdk("fetch job execution", || {
+                rust_sdk::api::job_execution::get(job_execution_id.clone())
+            })
+            .await?
+        }
+    };
+
+    let status = format!("{:?}", job_execution.status);
+    if check_downloadable(
+        &job_execution.id.to_string(),
+        &job_execution.status,
+        &allow_status,
+    )? {
+        eprintln!("Warning: job execution {} is {}", job_execution.id, status);
+        eprintln!("Warning: only the output artifacts it has produced so far are downloaded, they may be incomplete");
+    }
+
+    let mut receipt = match &receipt_path {
+        Some(_) => {
+            let job = try_sdk("fetch job", || {
+                rust_sdk::api::job::get(job_execution.job_id.clone())
+            })
+            .await?;
+            let mut receipt = Receipt::new(&job, job_execution.id.to_string());
+            receipt.time("fetch", started);
+            Some(receipt)
+        }
+        None => None,
+    };
+
+    // Resolve download links and sizes up front so the whole download can be checked
+    let client = http::client();
+    let mut report = DownloadReport::default();
+    let resolving = Instant::now();
+    let resolved = resolve_output_artifacts(&client, job_execution.id, &output_dir, &options)
+        .await
+        .and_then(|(downloads, listed)| {
+            options.check_matched(downloads.len(), listed)?;
+            Ok(downloads)
+        });
+    let result = match resolved {
+        Ok(downloads) => {
+            if let Some(receipt) = &mut receipt {
+                receipt.time("resolve", resolving);
+            }
+            let downloading = Instant::now();
+            let result = run_output_artifact_downloads(
+                client,
+                downloads,
+                &output_dir,
+                temp_dir,
+                &options,
+                &mut report,
+            )
+            .await;
+            if let Some(receipt) = &mut receipt {
+                receipt.time("download", downloading);
+            }
+            result
+        }
+        Err(err) => Err(err),
+    };
+
+    // The receipt is also written when the download failed part way
+    if let (Some(mut receipt), Some(receipt_path)) = (receipt, receipt_path) {
+        receipt.report = report;
+        receipt.write(&output_dir.join(receipt_path), result.as_ref().err())?;
+    }
+    result?;
+    outln!(
+        "Downloaded the output artifacts of job execution {}, which was {} when they were fetched",
+        job_execution.id,
+        status
+    );
+
+    Ok(())
+}
+
+/// Downloads the output artifacts of every completed execution of a job, each into a
+/// directory named after the execution
+async fn batch_download_artifacts_by_job(
+    job_id: String,
+    output_dir: PathBuf,
+    temp_dir: PathBuf,
+    options: DownloadOptions,
+) -> Result<(), DiceCliError> {
+    let job_executions = try_sdk("list job executions", || {
+        rust_sdk::api::job_execution::list(doc! {
+            "job_id": job_id.clone(),
+            "status": enum_to_string(&JobExecutionStatus::Completed),
+        })
+    })
+    .await?;
+    outln!(
+        "Found {} completed job executions for job {}",
+        job_executions.len(),
+        job_id
+    );
+
+    let client = http::client();
+    let mut downloads = vec![];
+    let mut listed = 0;
+    for job_execution in job_executions {
+        let (resolved, count) =
+            resolve_output_artifacts(&client, job_execution.id, &output_dir, &options).await?;
+        downloads.extend(resolved);
+        listed += count;
+    }
+    options.check_matched(downloads.len(), listed)?;
+
+    run_output_artifact_downloads(
+        client,
+        downloads,
+        &output_dir,
+        temp_dir,
+        &options,
+        &mut DownloadReport::default(),
+    )
+    .await
+}
+
+/// Lists the input artifacts of a project matching `conditions`, built from the tag, date and
+/// name filters of the command line
+async fn list_input_artifacts(
+    project_id: String,
+    conditions: Document,
+    sort_by: SortBy,
+    limit: Option<usize>,
+    page: usize,
+    format: OutputFormat,
+) -> Result<(), DiceCliError> {
+    let mut filter = doc! {
+        "entity_id": project_id,
+        "artifact_type": enum_to_string(&ArtifactType::Input),
+    };
+    filter.extend(conditions);
+
+    // Utilizing the rust SDK, list the input artifacts of the project
+    let mut artifacts = try_sdk("list input artifacts", || {
+        rust_sdk::api::artifact::list(filter.clone())
+    })
+    .await?;
+
+    match sort_by {
+        SortBy::Id | SortBy::Created => artifacts.sort_by_key(|artifact| artifact.id),
+        SortBy::Status => artifacts.sort_by_key(|artifact| enum_to_string(&artifact.status)),
+    }
+    let artifacts = paginate(artifacts, limit, page);
+
+    match format {
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&artifacts).unwrap()),
+        OutputFormat::Table | OutputFormat::Raw => print_table(
+            &["ID", "NAME", "STATUS", "TAGS", "CREATED"],
+            artifacts
+                .iter()
+                .map(|artifact| {
+                    vec![
+                        artifact.id.to_string(),
+                        artifact.tags.get(NAME_TAG).cloned().unwrap_or_default(),
+                        format!("{:?}", artifact.status),
+                        format_tags(&artifact.tags),
+                        artifact
+                            .id
+                            .timestamp()
+                            .try_to_rfc3339_string()
+                            .unwrap_or_default(),
+                    ]
+                })
+                .collect(),
+        ),
+    }
+
+    Ok(())
+}
+
+async fn run(args: Arguments) -> Result<(), DiceCliError> {
+    let temp_dir = cache::cache_dir(args.temp_dir.as_deref());
+    if args.use_names {
+        eprintln!("Warning: --use-names is no longer needed, artifacts are extracted into directories named after them unless --flatten is given");
+    }
+    let created = DateRange {
+        from: args.filter_by_date_from,
+        to: args.filter_by_date_to,
+    };
+    let download_options = DownloadOptions {
+        ignore_space_check: args.ignore_space_check,
+        flatten: args.flatten,
+        on_error: args.on_error.unwrap_or(OnErrorStrategy::Stop),
+        filter_tags: args.filter_tags,
+        filter_name: args.filter_name,
+        allow_empty: args.allow_empty,
+        force: args.force,
+        max_parallel: args.max_parallel_downloads,
+        verbose: args.verbose,
+    };
+    let runtime_dir = match &args.runtime_dir {
+        Some(runtime_dir) => paths::existing(runtime_dir, "--runtime-dir")?,
+        None => PathBuf::from("."),
+    };
+    let runtime_build = RuntimeBuild {
+        dir: runtime_dir,
+        command: args.build_command.clone(),
+        skip_build: args.skip_build,
+        skip_validation: args.skip_validation,
+        quiet: args.quiet_build,
+    };
+    let upload_options = UploadOptions {
+        content_type: args.content_type.clone(),
+        dedup: !args.no_dedup,
+        global_dedup: args.global_dedup,
+        force_upload: args.force_upload,
+    };
+
+    // Checked before anything is created, so a batch never stops halfway on a bad argument
+    if let Some(description) = &args.description {
+        limits::check_description(description)?;
+    }
+    limits::check_tags(&creation_tags(
+        &args.default_tags,
+        args.tags.clone(),
+        args.name.clone(),
+    ))?;
+    limits::check_input_artifacts(args.input_artifact_ids.as_deref().unwrap_or_default())?;
+
+    if args.create_runtime && args.all {
+        if args.name.is_some() || args.file.is_some() {
+            return Err(DiceCliError::InvalidArgument {
+                message: "--all takes the name of each runtime from its own manifest, so --name and --file cannot be given".to_string(),
+            });
+        }
+        create_workspace_runtimes(
+            required(args.project_id, "--project-id")?,
+            &runtime_build,
+            temp_dir,
+            creation_tags(&args.default_tags, args.tags, None),
+            args.if_not_exists,
+            args.fail_fast,
+        )
+        .await
+    } else if args.create_runtime {
+        create_runtime(
+            args.name,
+            required(args.project_id, "--project-id")?,
+            existing_file(args.file)?,
+            &runtime_build,
+            temp_dir,
+            creation_tags(&args.default_tags, args.tags, None),
+            args.if_not_exists,
+        )
+        .await
+        .map(|_| ())
+    } else if args.verify_runtime {
+        verify_runtime(&runtime_build, args.format)
+    } else if args.upload_runtime {
+        upload_runtime(
+            required(args.runtime_id, "--runtime-id")?,
+            existing_file(args.file)?,
+            args.force,
+            &runtime_build,
+            temp_dir,
+        )
+        .await
+    } else if args.update_runtime {
+        update_runtime(
+            required(args.runtime_id, "--runtime-id")?,
+            required(args.status, "--status")?,
+            args.tags,
+        )
+        .await
     } else if args.create_input_artifact {
-        create_input_artifact(
-            args.project_id.expect("--project-id required"),
-            args.file.expect("--file required"),
+        let project_id = required(args.project_id, "--project-id")?;
+        let tags = creation_tags(&args.default_tags, args.tags, args.name);
+        if args.queue {
+            let file = required(args.file, "--file")?;
+            if file == paths::STDIN {
+                return Err(DiceCliError::InvalidArgument {
+                    message: "--queue cannot replay an upload from stdin, save it to a file first"
+                        .to_string(),
+                });
+            }
+            let operation = queue::Creation::InputArtifact {
+                project_id,
+                file: paths::existing(Path::new(&file), "--file")?,
+                tags,
+                options: upload_options,
+            };
+            return queue::run_or_enqueue(operation, &temp_dir).await;
+        }
+        let uploaded = with_input_file(
+            required(args.file, "--file")?,
+            &args.stdin_name,
+            &temp_dir.clone(),
+            |file_name| {
+                create_input_artifact(project_id, file_name, temp_dir, tags, &upload_options)
+            },
         )
-        .await;
+        .await?;
+        if args.format == OutputFormat::Json {
+            outln!("{}", serde_json::to_string_pretty(&uploaded).unwrap());
+        }
+        Ok(())
+    } else if args.create_input_artifacts_from_directory {
+        create_input_artifacts_from_directory(
+            required(args.project_id, "--project-id")?,
+            paths::existing(&required(args.directory, "--directory")?, "--directory")?,
+            args.recursive,
+            args.dry_run,
+            temp_dir,
+            creation_tags(&args.default_tags, args.tags, args.name),
+            upload_options,
+        )
+        .await
+    } else if args.create_artifact {
+        let artifact_type = required(args.artifact_type, "--type")?;
+        let entity_type = required(args.entity_type, "--entity-type")?;
+        validate_artifact_target(artifact_type, entity_type)
+            .map_err(|message| DiceCliError::InvalidArgument { message })?;
+
+        let entity_id = required(args.entity_id, "--entity-id")?;
+        let tags = creation_tags(&args.default_tags, args.tags, args.name);
+        with_input_file(
+            required(args.file, "--file")?,
+            &args.stdin_name,
+            &temp_dir.clone(),
+            |file_name| {
+                create_artifact(
+                    entity_id,
+                    entity_type,
+                    artifact_type,
+                    file_name,
+                    temp_dir,
+                    args.content_type,
+                    tags,
+                )
+            },
+        )
+        .await
+        .map(|_| ())
+    } else if args.upload_artifact_from_url {
+        upload_artifact_from_url(
+            required(args.project_id, "--project-id")?,
+            required(args.url, "--url")?,
+            args.content_type,
+            creation_tags(&args.default_tags, args.tags, args.name),
+        )
+        .await
+    } else if args.copy_artifact {
+        copy_artifacts(
+            required(args.project_id, "--project-id")?,
+            args.artifact_id,
+            args.job_execution_id,
+            creation_tags(&args.default_tags, args.tags, args.name),
+        )
+        .await
+    } else if args.create_artifact_from_stdin {
+        create_artifact_from_stdin(
+            required(args.project_id, "--project-id")?,
+            args.content_type,
+            creation_tags(&args.default_tags, args.tags, args.name),
+        )
+        .await
     } else if args.create_project {
-        create_project(args.description.expect("--description required")).await;
+        create_project(
+            required(args.description, "--description")?,
+            creation_tags(&args.default_tags, args.tags, args.name),
+            args.if_not_exists,
+        )
+        .await
+    } else if args.update_project {
+        update_project(
+            required(args.project_id, "--project-id")?,
+            args.description,
+            args.tags,
+            args.remove_tags,
+        )
+        .await
     } else if args.create_job {
-        create_job(
-            args.project_id.expect("--project-id required"),
-            args.runtime_id.expect("--runtime-id required"),
-            args.input_artifact_ids
-                .expect("--input-artifact-ids required"),
+        let (project_id, runtime_id, input_artifact_ids, tags) = match args.job_file {
+            Some(job_file) => {
+                let job = read_job_definition_file(&job_file)?;
+                (
+                    job.project_id,
+                    job.runtime_id,
+                    job.input_artifact_ids,
+                    param_tags(
+                        creation_tags(&args.default_tags, job.tags, args.name),
+                        args.params_file.as_deref(),
+                        args.params,
+                    )?,
+                )
+            }
+            None => {
+                let mut input_artifact_ids = args.input_artifact_ids.unwrap_or_default();
+                if let Some(input_artifact_ids_file) = args.input_artifact_ids_file {
+                    for id in read_ids_file(&input_artifact_ids_file)? {
+                        if !input_artifact_ids.contains(&id) {
+                            input_artifact_ids.push(id);
+                        }
+                    }
+                }
+                if input_artifact_ids.is_empty() {
+                    return Err(DiceCliError::MissingArgument {
+                        flag: "--input-artifact-ids or --input-artifact-ids-file",
+                    });
+                }
+                (
+                    required(args.project_id, "--project-id")?,
+                    required(args.runtime_id, "--runtime-id")?,
+                    input_artifact_ids,
+                    param_tags(
+                        creation_tags(&args.default_tags, args.tags, args.name),
+                        args.params_file.as_deref(),
+                        args.params,
+                    )?,
+                )
+            }
+        };
+
+        // Before the pre-check, which fetches every input artifact
+        limits::check_input_artifacts(&input_artifact_ids)?;
+        limits::check_tags(&tags)?;
+        if !args.no_precheck {
+            match precheck_create_job(&project_id, &runtime_id, &input_artifact_ids).await {
+                // The API checks the IDs itself when the queued job is created
+                Err(err) if args.queue && err.is_unreachable() => (),
+                result => result?,
+            }
+        }
+        if args.if_not_exists {
+            let name = required(tags.get(NAME_TAG).cloned(), "--name")?;
+            if let Some(job_id) = find_job_by_name(&project_id, &name).await? {
+                outln!("Job already exists: {}", job_id);
+                return Ok(());
+            }
+        }
+        if args.queue {
+            let operation = queue::Creation::Job {
+                project_id,
+                runtime_id,
+                input_artifact_ids,
+                tags,
+            };
+            return queue::run_or_enqueue(operation, &temp_dir).await;
+        }
+        create_job(project_id, runtime_id, input_artifact_ids, tags)
+            .await
+            .map(|_| ())
+    } else if args.clone_job {
+        let edits = JobEdits {
+            runtime_id: args.runtime_id,
+            replace_inputs: args.replace_input,
+            add_inputs: args.add_input,
+            remove_inputs: args.remove_input,
+        };
+        let job_id = clone_job(
+            required(args.job_id, "--job-id")?,
+            edits,
+            param_tags(
+                creation_tags(&args.default_tags, args.tags, args.name),
+                args.params_file.as_deref(),
+                args.params,
+            )?,
         )
-        .await;
+        .await?;
+
+        if args.and_execute {
+            let job_execution_id = create_job_execution(
+                job_id.clone(),
+                creation_tags(&args.default_tags, vec![], None),
+            )
+            .await?;
+            outln!("Created job execution: {}", job_execution_id);
+            history::record(
+                "create-job-execution",
+                &[("job", &job_id), ("job_execution", &job_execution_id)],
+            );
+        }
+        Ok(())
+    } else if args.init_runtime {
+        scaffold::init_runtime(args.name, args.build_system)
+    } else if args.init_job_file {
+        init_job_file()
+    } else if args.deduplicate_artifacts {
+        dedupe::deduplicate_artifacts(
+            required(args.project_id, "--project-id")?,
+            args.delete_duplicates,
+        )
+        .await
+    } else if args.delete_job {
+        delete::delete_job(
+            required(args.job_id, "--job-id")?,
+            args.yes,
+            args.cascade,
+            args.dry_run,
+        )
+        .await
+    } else if args.delete_runtime {
+        delete::delete_runtime(
+            required(args.runtime_id, "--runtime-id")?,
+            args.yes,
+            args.cascade,
+            args.dry_run,
+        )
+        .await
+    } else if args.delete_project {
+        delete::delete_project(
+            required(args.project_id, "--project-id")?,
+            args.yes,
+            args.cascade,
+            args.dry_run,
+        )
+        .await
+    } else if args.export_project {
+        export::export_project(
+            required(args.project_id, "--project-id")?,
+            &required(
+                args.export_file.or(args.file.map(PathBuf::from)),
+                "--export-file",
+            )?,
+        )
+        .await
+    } else if args.import_project {
+        export::import_project(
+            &paths::existing(Path::new(&required(args.file, "--file")?), "--file")?,
+            args.include_artifacts,
+            args.source_profile,
+            args.force,
+        )
+        .await
+    } else if args.history {
+        history::show_history(args.last, args.action, args.format);
+        Ok(())
+    } else if args.list_queue {
+        queue::list(args.format)
+    } else if args.flush_queue {
+        queue::flush(&temp_dir).await
+    } else if args.cache_info {
+        cache::info(&temp_dir)
+    } else if args.clean_cache {
+        cache::clean(&temp_dir)
+    } else if args.smoke_test {
+        smoke::smoke_test(
+            required(args.runtime_id, "--runtime-id")?,
+            temp_dir,
+            Duration::from_secs(args.wait_timeout),
+            args.keep,
+        )
+        .await
+    } else if args.doctor {
+        if !doctor::doctor(args.output_dir, args.format).await {
+            return Err(DiceCliError::ChecksFailed);
+        }
+        Ok(())
     } else if args.create_job_execution {
-        create_job_execution(args.job_id.expect("--job-id required")).await;
+        let job_ids = match args.jobs_file {
+            Some(jobs_file) => read_ids_file(&jobs_file)?,
+            None => vec![required(args.job_id, "--job-id or --jobs-file")?],
+        };
+        let tags = param_tags(
+            creation_tags(&args.default_tags, args.tags, args.name),
+            args.params_file.as_deref(),
+            args.params,
+        )?;
+
+        if args.queue {
+            let job_id =
+                match (job_ids.as_slice(), args.count.unwrap_or(1)) {
+                    ([job_id], 1) => job_id.clone(),
+                    _ => return Err(DiceCliError::InvalidArgument {
+                        message:
+                            "--queue creates a single job execution, without --jobs-file or --count"
+                                .to_string(),
+                    }),
+                };
+            let operation = queue::Creation::JobExecution { job_id, tags };
+            return queue::run_or_enqueue(operation, &temp_dir).await;
+        }
+        create_job_executions(
+            job_ids,
+            args.count.unwrap_or(1),
+            args.tag_index,
+            tags,
+            args.concurrency,
+            args.format,
+            args.on_error.unwrap_or(OnErrorStrategy::Continue),
+        )
+        .await
+    } else if args.batch_create_job_executions {
+        batch_create_job_executions(
+            required(args.job_id, "--job-id")?,
+            required(args.count, "--count")?,
+            param_tags(
+                creation_tags(&args.default_tags, args.tags, args.name),
+                args.params_file.as_deref(),
+                args.params,
+            )?,
+            args.concurrency,
+            args.format,
+            args.on_error.unwrap_or(OnErrorStrategy::Continue),
+        )
+        .await
+    } else if args.get_job {
+        get_job(required(args.job_id, "--job-id")?, args.format).await
     } else if args.get_job_execution {
-        get_job_execution(args.job_execution_id.expect("--job-execution-id required")).await;
+        get_job_execution(
+            required(args.job_execution_id, "--job-execution-id")?,
+            args.format,
+        )
+        .await
+    } else if args.project_summary {
+        stats::project_summary(required(args.project_id, "--project-id")?, args.format).await
+    } else if args.runtime_stats {
+        stats::runtime_usage_stats(required(args.runtime_id, "--runtime-id")?, args.format).await
+    } else if args.get_job_execution_logs {
+        logs::job_execution_logs(
+            required(args.job_execution_id, "--job-execution-id")?,
+            args.tail,
+            args.follow,
+            args.format,
+        )
+        .await
+    } else if args.job_execution_top {
+        let job_id = required(args.job_id, "--job-id")?;
+        let mut filter = doc! { "job_id": job_id.clone() };
+        filter.extend(
+            list_filter(
+                args.filter.as_deref(),
+                &args.tags,
+                created,
+                args.name_filter.as_deref(),
+            )?
+            .to_document(parse_job_execution_status)?,
+        );
+        top::job_execution_top(
+            job_id,
+            filter,
+            Duration::from_secs(args.interval.max(1)),
+            args.limit.unwrap_or(top::DEFAULT_RECENT),
+            args.forever,
+        )
+        .await
+    } else if args.watch_job {
+        top::watch_job(
+            required(args.job_id, "--job-id")?,
+            Duration::from_secs(args.interval.max(1)),
+        )
+        .await
+    } else if args.job_execution_stats {
+        stats::job_execution_stats(
+            required(args.job_id, "--job-id")?,
+            args.since,
+            created,
+            args.format,
+        )
+        .await
     } else if args.download_output_artifacts {
-        download_output_artifacts(args.job_execution_id.expect("--job-execution-id required"))
+        download_output_artifacts(
+            required(args.job_execution_id, "--job-execution-id")?,
+            args.output_dir,
+            temp_dir,
+            download_options,
+            args.allow_status,
+            args.wait.then(|| Duration::from_secs(args.wait_timeout)),
+            args.receipt,
+        )
+        .await
+    } else if args.batch_download_artifacts_by_job {
+        batch_download_artifacts_by_job(
+            required(args.job_id, "--job-id")?,
+            args.output_dir,
+            temp_dir,
+            download_options,
+        )
+        .await
+    } else if args.inspect_artifact {
+        inspect::inspect_artifact(required(args.artifact_id, "--artifact-id")?, args.format).await
+    } else if args.update_artifact {
+        let mut artifact_ids = args.ids;
+        artifact_ids.extend(args.artifact_id);
+        if artifact_ids.is_empty() {
+            return Err(DiceCliError::MissingArgument {
+                flag: "--artifact-id or --ids",
+            });
+        }
+        if !args.tags.is_empty() || !args.remove_tags.is_empty() {
+            return Err(DiceCliError::InvalidArgument {
+                message: "artifact tags cannot be updated yet, only --status is supported"
+                    .to_string(),
+            });
+        }
+        update_artifacts(artifact_ids, required(args.status, "--status")?).await
+    } else if args.list_input_artifacts {
+        let mut filter = list_filter(
+            args.filter.as_deref(),
+            &args.tags,
+            created,
+            args.name_filter.as_deref(),
+        )?;
+        // Inactive artifacts are deprecated, so they are only listed on request
+        if !args.all && !filter.constrains(&Field::Status) {
+            filter = filter.and(Field::Status, Op::Ne, "inactive");
+        }
+        let conditions = filter.to_document(|status| {
+            enum_from_string::<ArtifactStatus>(status).map(|status| enum_to_string(&status))
+        })?;
+
+        list_input_artifacts(
+            required(args.project_id, "--project-id")?,
+            conditions,
+            args.sort_by,
+            args.limit,
+            args.page,
+            args.format,
+        )
+        .await
+    } else if args.diff_job_executions {
+        diff::diff_job_executions(
+            required(args.job_execution_id_a, "--job-execution-id-a")?,
+            required(args.job_execution_id_b, "--job-execution-id-b")?,
+            temp_dir,
+            download_options,
+            args.keep,
+        )
+        .await
+    } else if let Some(query) = args.search {
+        search::search_all(query, args.search_type, args.format).await
+    } else {
+        Ok(())
+    }
+}
+
+/// Resolves an optional `--file` that is read from to an absolute path
+fn existing_file(file: Option<String>) -> Result<Option<String>, DiceCliError> {
+    file.map(|file| {
+        paths::existing(Path::new(&file), "--file").map(|path| path.to_string_lossy().into_owned())
+    })
+    .transpose()
+}
+
+/// Whether the command works on local files only, so it runs offline without asking the API
+/// for its version; `--doctor` checks the API itself
+fn is_local_command(args: &Arguments) -> bool {
+    args.verify_runtime
+        || args.init_runtime
+        || args.init_job_file
+        || args.history
+        || args.list_queue
+        || args.cache_info
+        || args.clean_cache
+        || args.doctor
+}
+
+/// Reports an error with its hint, cleans up partial state and exits
+fn exit_with_error(err: DiceCliError, format: OutputFormat) -> ! {
+    match format {
+        OutputFormat::Json => eprintln!("{}", err.to_json()),
+        OutputFormat::Table | OutputFormat::Raw => eprintln!("Error: {}", err),
+    }
+    cleanup::cleanup();
+    output::flush();
+    process::exit(err.exit_code() as i32);
+}
+
+fn print_exit_codes() {
+    print_table(
+        &["CODE", "MEANING"],
+        ExitCode::ALL
+            .iter()
+            .map(|code| vec![(*code as i32).to_string(), code.description().to_string()])
+            .collect(),
+    );
+    outln!();
+    outln!("An interrupted command exits with 130, and invalid flags make clap exit with 2.");
+}
+
+/// What is left to do on the async runtime once the API settings are exported
+enum Start {
+    /// `--init-config`, with the settings to validate and save
+    InitConfig(config::Config),
+    /// `--login`, with the API location and key to validate and store
+    Login(String, String),
+    /// Any other command, with the settings of the active profile
+    Run(config::Config, Arguments),
+}
+
+fn main() {
+    silence_sdk_panics();
+    let matches = Arguments::command().get_matches();
+    let args = Arguments::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    // First, so that nothing is printed to stdout before
+    output::configure(args.output_file.as_deref())
+        .unwrap_or_else(|err| exit_with_error(err, args.format));
+
+    // The SDK reads its API settings from the environment, which all threads share, so they
+    // are exported before the runtime starts its worker threads
+    let format = args.format;
+    if let Some(start) = prepare(&matches, args) {
+        let runtime = tokio::runtime::Runtime::new().unwrap_or_else(|err| {
+            let message = format!("could not start the async runtime: {}", err);
+            exit_with_error(DiceCliError::Internal { message }, format)
+        });
+        runtime.block_on(run_start(start, format));
+    }
+    output::flush();
+}
+
+/// Applies the settings and exports the API settings, or runs one of the actions that
+/// return early and need no API
+fn prepare(matches: &ArgMatches, mut args: Arguments) -> Option<Start> {
+    retry::configure(
+        args.retries,
+        Duration::from_millis(args.retry_delay),
+        Duration::from_secs(args.max_backoff),
+        args.verbose,
+    );
+    retry::configure_throttle(args.rps);
+    color::configure(args.no_color, output::is_file());
+    progress::configure(args.progress);
+    archive::configure_compression(args.compression, args.compression_level)
+        .unwrap_or_else(|err| exit_with_error(err, args.format));
+    http::configure_proxy(args.proxy.take(), args.verbose)
+        .unwrap_or_else(|err| exit_with_error(err, args.format));
+    http::configure_ca_cert(args.ca_cert.as_deref())
+        .unwrap_or_else(|err| exit_with_error(err, args.format));
+
+    if args.help_exit_codes {
+        print_exit_codes();
+        return None;
+    }
+    if let Some(shell) = args.completions {
+        completions::print_completions(shell, &mut Arguments::command());
+        return None;
+    }
+
+    // Configuration management works on the file itself, before it is applied
+    if args.list_profiles || args.add_profile || args.delete_profile {
+        let result = if args.list_profiles {
+            config::list_profiles()
+        } else if args.add_profile {
+            required(args.name, "--name").and_then(|name| {
+                config::add_profile(
+                    name,
+                    config::Config {
+                        api_url: args.api_url,
+                        api_key: args.api_key,
+                        default_project_id: args.project_id,
+                        version_check: None,
+                        max_parallel_uploads: None,
+                        default_tags: HashMap::new(),
+                        smoke_test_runtime_id: None,
+                        limits: None,
+                    },
+                )
+            })
+        } else {
+            required(args.name, "--name").and_then(config::delete_profile)
+        };
+        if let Err(err) = result {
+            exit_with_error(err, args.format);
+        }
+        return None;
+    }
+
+    if args.init_project {
+        let project_config =
+            required(args.project_id, "--project-id").map(|project_id| config::ProjectConfig {
+                project_id: Some(project_id),
+                runtime_id: args.runtime_id,
+                default_tags: args.tags.into_iter().collect(),
+            });
+        if let Err(err) = project_config.and_then(config::init_project) {
+            exit_with_error(err, args.format);
+        }
+        return None;
+    }
+    if args.init_config {
+        return config::prompt_init()
+            .unwrap_or_else(|err| exit_with_error(err, args.format))
+            .map(Start::InitConfig);
+    }
+
+    let config = config::load(args.profile.as_deref())
+        .unwrap_or_else(|err| exit_with_error(err, args.format));
+    if args.show_config {
+        config::show(&config, matches);
+        return None;
+    }
+    if args.login {
+        let (api_url, api_key) =
+            config::login_credentials(&config, args.api_url.take(), args.api_key.take())
+                .unwrap_or_else(|err| exit_with_error(err, args.format));
+        return Some(Start::Login(api_url, api_key));
+    }
+    if args.logout {
+        if let Err(err) = config::logout() {
+            exit_with_error(err, args.format);
+        }
+        return None;
+    }
+    config.apply(args.api_url.take(), args.api_key.take());
+
+    Some(Start::Run(config, args))
+}
+
+/// Runs what [`prepare`] left to do once the API settings are exported
+async fn run_start(start: Start, format: OutputFormat) {
+    let result = match start {
+        Start::InitConfig(config) => config::init(config).await,
+        Start::Login(api_url, api_key) => config::login(api_url, api_key).await,
+        Start::Run(config, args) => {
+            start_command(config, args).await;
+            Ok(())
+        }
+    };
+    if let Err(err) = result {
+        exit_with_error(err, format);
+    }
+}
+
+/// Applies the settings of the profile and the project directory and runs the command, or
+/// one of the actions that return early
+async fn start_command(config: config::Config, mut args: Arguments) {
+    let cache_dir = cache::cache_dir(args.temp_dir.as_deref());
+    if args.version || args.version_info {
+        let check = args.check || args.version_info;
+        if let Err(err) = version::print_version(&cache_dir, check, args.format).await {
+            exit_with_error(err, args.format);
+        }
+        return;
+    }
+    if args.self_update {
+        if let Err(err) = update::self_update(args.check, args.release.as_deref()).await {
+            exit_with_error(err, args.format);
+        }
+        return;
+    }
+
+    // Completion must never fail loudly, so an unreadable project file is ignored
+    if let Some(resource) = args.complete_ids {
+        let project_id = args
+            .project_id
+            .or(config::load_project_config()
+                .ok()
+                .and_then(|project| project.project_id))
+            .or(config.default_project_id);
+        completions::complete_ids(resource, project_id).await;
+        return;
+    }
+
+    http::configure_uploads(
+        args.max_parallel_uploads
+            .or(config.max_parallel_uploads)
+            .unwrap_or(http::DEFAULT_MAX_PARALLEL_UPLOADS),
+        args.artifact_size_limit,
+    );
+    limits::configure(config.limits.unwrap_or_default());
+
+    if !args.no_version_check && config.version_check != Some(false) && !is_local_command(&args) {
+        version::warn_on_mismatch(&cache_dir).await;
+    }
+
+    // Flags and variables win over the project directory, which wins over the profile
+    let project_config =
+        config::load_project_config().unwrap_or_else(|err| exit_with_error(err, args.format));
+    args.project_id = args
+        .project_id
+        .or(project_config.project_id)
+        .or(config.default_project_id);
+    // The smoke test runs its designated runtime, not the one of the project directory
+    args.runtime_id = if args.smoke_test {
+        args.runtime_id.or(config.smoke_test_runtime_id.clone())
+    } else {
+        args.runtime_id.or(project_config.runtime_id)
+    };
+    if !args.no_default_tags {
+        args.default_tags = config.default_tags.clone();
+        args.default_tags.extend(project_config.default_tags);
+    }
+
+    let format = args.format;
+    if let Err(err) = cleanup::run_until_interrupted(run(args)).await {
+        exit_with_error(err, format);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn defines_valid_arguments() {
+        Arguments::command().debug_assert();
+    }
+
+    #[test]
+    fn accepts_the_valid_artifact_targets() {
+        for (artifact_type, entity_type) in [
+            (ArtifactKind::Input, EntityKind::Project),
+            (ArtifactKind::Input, EntityKind::Job),
+            (ArtifactKind::Output, EntityKind::JobExecution),
+        ] {
+            assert_eq!(
+                validate_artifact_target(artifact_type, entity_type),
+                Ok(()),
+                "{:?} {:?}",
+                artifact_type,
+                entity_type
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_the_invalid_artifact_targets() {
+        for (artifact_type, entity_type, message) in [
+            (
+                ArtifactKind::Input,
+                EntityKind::JobExecution,
+                "input artifacts must be attached to a project or a job",
+            ),
+            (
+                ArtifactKind::Output,
+                EntityKind::Project,
+                "output artifacts must be attached to a job execution",
+            ),
+            (
+                ArtifactKind::Output,
+                EntityKind::Job,
+                "output artifacts must be attached to a job execution",
+            ),
+        ] {
+            assert_eq!(
+                validate_artifact_target(artifact_type, entity_type),
+                Err(message.to_string())
+            );
+        }
+    }
+
+    fn tags(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn merges_the_default_tags_into_created_resources() {
+        let defaults = tags(&[("team", "genomics"), ("cost-center", "42")]);
+        assert_eq!(
+            creation_tags(&defaults, tags(&[("run", "7")]), None),
+            tags(&[("team", "genomics"), ("cost-center", "42"), ("run", "7")])
+        );
+        assert_eq!(creation_tags(&defaults, [], None), defaults);
+        assert_eq!(
+            creation_tags(&HashMap::new(), tags(&[("run", "7")]), None),
+            tags(&[("run", "7")])
+        );
+    }
+
+    #[test]
+    fn prefers_explicit_tags_over_the_defaults() {
+        let defaults = tags(&[("team", "genomics"), ("cost-center", "42")]);
+        assert_eq!(
+            creation_tags(&defaults, tags(&[("team", "imaging")]), None),
+            tags(&[("team", "imaging"), ("cost-center", "42")])
+        );
+    }
+
+    #[test]
+    fn prefers_the_name_over_any_name_tag() {
+        let defaults = tags(&[(NAME_TAG, "default")]);
+        assert_eq!(
+            creation_tags(&defaults, tags(&[(NAME_TAG, "tagged")]), None),
+            tags(&[(NAME_TAG, "tagged")])
+        );
+        assert_eq!(
+            creation_tags(
+                &defaults,
+                tags(&[(NAME_TAG, "tagged")]),
+                Some("named".to_string())
+            ),
+            tags(&[(NAME_TAG, "named")])
+        );
+    }
+
+    #[test]
+    fn applies_repeated_tag_flags_in_order() {
+        let args = Arguments::try_parse_from([
+            "dice",
+            "--tag",
+            "team=imaging",
+            "--tag",
+            "note=a=b",
+            "--tag",
+            "team=genomics",
+        ])
+        .unwrap();
+        let defaults = tags(&[("team", "default"), ("owner", "ops")]);
+        assert_eq!(
+            creation_tags(&defaults, args.tags, None),
+            tags(&[("team", "genomics"), ("note", "a=b"), ("owner", "ops")])
+        );
+        assert!(Arguments::try_parse_from(["dice", "--tag", "=value"]).is_err());
+        assert!(Arguments::try_parse_from(["dice", "--tag", "novalue"]).is_err());
+    }
+
+    #[test]
+    fn round_trips_stdin_through_an_archive() {
+        let root = tempfile::tempdir().unwrap();
+        let input = b"sample,value\n1,\xff\n".to_vec();
+
+        let path = save_input(
+            &mut input.as_slice(),
+            "data.csv",
+            &root.path().join("stdin"),
+        )
+        .unwrap();
+        assert_eq!(path.file_name().unwrap(), "data.csv");
+        assert_eq!(fs::read(&path).unwrap(), input);
+
+        let archive_path = root.path().join("stdin.archive");
+        archive::create_archive(&path, &archive_path).unwrap();
+        let extracted = root.path().join("extracted");
+        archive::extract_archive(&archive_path, &extracted).unwrap();
+        assert_eq!(fs::read(extracted.join("data.csv")).unwrap(), input);
+    }
+
+    #[test]
+    fn saves_stdin_under_a_safe_name() {
+        let root = tempfile::tempdir().unwrap();
+        let path = save_input(&mut "x".as_bytes(), "../escape.txt", root.path()).unwrap();
+        assert_eq!(path.parent().unwrap(), root.path());
+        assert_eq!(fs::read_to_string(path).unwrap(), "x");
+    }
+
+    #[test]
+    fn skips_the_version_check_for_local_commands() {
+        for flag in [
+            "--verify-runtime",
+            "--init-runtime",
+            "--init-job-file",
+            "--history",
+            "--list-queue",
+            "--cache-info",
+            "--clean-cache",
+            "--doctor",
+        ] {
+            let args = Arguments::try_parse_from(["dice", flag]).unwrap();
+            assert!(is_local_command(&args), "{}", flag);
+        }
+        for flag in [
+            "--list-input-artifacts",
+            "--create-project",
+            "--get-job-execution",
+        ] {
+            let args = Arguments::try_parse_from(["dice", flag]).unwrap();
+            assert!(!is_local_command(&args), "{}", flag);
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_an_invalid_artifact_target_before_any_api_call() {
+        // The entity and the file do not exist, so getting past the check would fail otherwise
+        let temp_dir = env::temp_dir().join(format!("dice-test-{}", Uuid::new_v4()));
+        let args = Arguments::try_parse_from([
+            "dice",
+            "--create-artifact",
+            "--type",
+            "output",
+            "--entity-type",
+            "project",
+            "--entity-id",
+            "000000000000000000000000",
+            "--file",
+            "missing.txt",
+            "--temp-dir",
+            temp_dir.to_str().unwrap(),
+        ])
+        .unwrap();
+
+        match run(args).await {
+            Err(DiceCliError::InvalidArgument { message }) => {
+                assert_eq!(
+                    message,
+                    "output artifacts must be attached to a job execution"
+                )
+            }
+            other => panic!("expected an invalid argument, got {:?}", other),
+        }
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[tokio::test]
+    async fn rejects_artifact_tag_updates_before_any_api_call() {
+        for flags in [&["--tag", "stage=old"][..], &["--remove-tag", "stage"]] {
+            let mut argv = vec![
+                "dice",
+                "--update-artifact",
+                "--artifact-id",
+                "000000000000000000000000",
+                "--status",
+                "Inactive",
+            ];
+            argv.extend(flags);
+            let args = Arguments::try_parse_from(argv).unwrap();
+
+            match run(args).await {
+                Err(DiceCliError::InvalidArgument { message }) => assert_eq!(
+                    message,
+                    "artifact tags cannot be updated yet, only --status is supported"
+                ),
+                other => panic!("expected an invalid argument, got {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_the_statuses_artifacts_do_not_have() {
+        for status in ["Pending", "Archived", "Deprecated"] {
+            let result = update_artifacts(
+                vec!["000000000000000000000000".to_string()],
+                status.to_string(),
+            )
             .await;
+            assert!(
+                matches!(result, Err(DiceCliError::InvalidArgument { .. })),
+                "{}: {:?}",
+                status,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn waits_only_while_a_job_execution_is_unfinished() {
+        let timeout = Duration::from_secs(60);
+        let early = Duration::from_secs(5);
+        for status in [JobExecutionStatus::Pending, JobExecutionStatus::Running] {
+            assert!(keep_waiting("id", &status, early, timeout).unwrap());
+            assert!(matches!(
+                keep_waiting("id", &status, timeout, timeout),
+                Err(DiceCliError::WaitTimedOut { seconds: 60, .. })
+            ));
+        }
+        for status in [JobExecutionStatus::Completed, JobExecutionStatus::Failed] {
+            assert!(!keep_waiting("id", &status, early, timeout).unwrap());
+            assert!(!keep_waiting("id", &status, timeout * 2, timeout).unwrap());
+        }
+    }
+
+    #[test]
+    fn downloads_unfinished_job_executions_only_in_allowed_statuses() {
+        let allow_status = vec![parse_job_execution_status("failed").unwrap()];
+
+        assert!(!check_downloadable("id", &JobExecutionStatus::Completed, &[]).unwrap());
+        assert!(!check_downloadable("id", &JobExecutionStatus::Completed, &allow_status).unwrap());
+        assert!(check_downloadable("id", &JobExecutionStatus::Failed, &allow_status).unwrap());
+        for status in [JobExecutionStatus::Pending, JobExecutionStatus::Running] {
+            assert!(matches!(
+                check_downloadable("id", &status, &allow_status),
+                Err(DiceCliError::JobExecutionNotCompleted { .. })
+            ));
+        }
+        assert!(matches!(
+            check_downloadable("id", &JobExecutionStatus::Failed, &[]),
+            Err(DiceCliError::JobExecutionNotCompleted { .. })
+        ));
+    }
+
+    /// Accepts `count` uploads and answers them only once all have arrived, so the uploads
+    /// overlap, returning the bodies received
+    async fn serve_uploads(count: usize) -> (String, tokio::task::JoinHandle<Vec<Vec<u8>>>) {
+        let (listener, uri) = test_server::bind("/upload").await;
+        let server = tokio::spawn(async move {
+            let mut uploads = vec![];
+            for _ in 0..count {
+                uploads.push(test_server::accept(&listener).await);
+            }
+
+            let mut bodies = vec![];
+            for (socket, body) in uploads {
+                test_server::respond(socket, test_server::OK).await;
+                bodies.push(body);
+            }
+            bodies
+        });
+
+        (uri, server)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn uploads_intact_archives_of_the_same_file_concurrently() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = source_dir.path().join("inputs.csv");
+        let contents = "id,value\n".repeat(10_000);
+        fs::write(&file, &contents).unwrap();
+        let (uri, server) = serve_uploads(2).await;
+
+        // Both flows pack the same file into the same directory before either upload finishes
+        let flow = |id: &'static str| {
+            let file = file.clone();
+            let temp_dir = temp_dir.path().to_path_buf();
+            let uri = uri.clone();
+            async move {
+                let tar_file_name = pack_artifact(&file, &temp_dir)?;
+                upload_packed_artifact(&tar_file_name, id, &uri, None).await
+            }
+        };
+        let (first, second) = tokio::join!(flow("first"), flow("second"));
+        first.unwrap();
+        second.unwrap();
+
+        let bodies = server.await.unwrap();
+        assert_eq!(bodies.len(), 2);
+        for body in bodies {
+            let received = tempfile::tempdir().unwrap();
+            let archive_path = received.path().join("upload.tar");
+            fs::write(&archive_path, body).unwrap();
+            let unpacked = received.path().join("unpacked");
+            archive::extract_archive(&archive_path, &unpacked).unwrap();
+            assert_eq!(
+                fs::read_to_string(unpacked.join("inputs.csv")).unwrap(),
+                contents
+            );
+        }
+        // Each flow deleted its own archive and nothing else was left behind
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn accepts_path_as_an_alias_of_runtime_dir() {
+        for flag in ["--path", "--runtime-dir"] {
+            let args =
+                Arguments::try_parse_from(["dice", "--create-runtime", flag, "runtimes/encoder"])
+                    .unwrap();
+            assert_eq!(args.runtime_dir, Some(PathBuf::from("runtimes/encoder")));
+        }
+    }
+
+    #[test]
+    fn names_runtimes_after_the_flag_the_manifest_or_the_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("encoder");
+        fs::create_dir(&dir).unwrap();
+        let named = manifest::DiceRuntimeManifest {
+            name: Some("from-manifest".to_string()),
+            ..Default::default()
+        };
+        let unnamed = manifest::DiceRuntimeManifest::default();
+
+        let given = Some("from-flag".to_string());
+        assert_eq!(
+            runtime_name(given.clone(), Some(&named), &dir).as_deref(),
+            Some("from-flag")
+        );
+        assert_eq!(
+            runtime_name(None, Some(&named), &dir).as_deref(),
+            Some("from-manifest")
+        );
+        assert_eq!(
+            runtime_name(None, Some(&unnamed), &dir).as_deref(),
+            Some("encoder")
+        );
+        // `..` is named after the directory it points to
+        assert_eq!(
+            runtime_name(None, Some(&unnamed), &dir.join("..")),
+            root.path()
+                .canonicalize()
+                .unwrap()
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        );
+        // A prebuilt archive is not named after any directory
+        assert_eq!(runtime_name(None, None, &dir), None);
+        assert_eq!(
+            runtime_name(given, None, &dir).as_deref(),
+            Some("from-flag")
+        );
+    }
+
+    /// Runs `--create-runtime --skip-build --path dir`, which fails before any API call when
+    /// the build output is missing
+    async fn create_runtime_in(dir: &Path) -> Result<(), DiceCliError> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let args = Arguments::try_parse_from([
+            "dice",
+            "--create-runtime",
+            "--project-id",
+            "000000000000000000000000",
+            "--skip-build",
+            "--path",
+            dir.to_str().unwrap(),
+            "--temp-dir",
+            temp_dir.path().to_str().unwrap(),
+        ])
+        .unwrap();
+
+        run(args).await
+    }
+
+    #[tokio::test]
+    async fn resolves_the_build_output_against_the_runtime_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("encoder");
+        fs::create_dir_all(dir.join(".dice")).unwrap();
+        let canonical = dir.canonicalize().unwrap();
+
+        // The default output path is named after the runtime directory
+        fs::write(dir.join(".dice/runtime.toml"), "").unwrap();
+        match create_runtime_in(&dir).await {
+            Err(DiceCliError::RuntimeNotBuilt { path }) => assert_eq!(
+                path,
+                canonical.join("target/wasm32-wasi/release/encoder.tar")
+            ),
+            other => panic!("expected a missing build output, got {:?}", other),
+        }
+
+        // A relative output path in the manifest is relative to the runtime directory
+        fs::write(
+            dir.join(".dice/runtime.toml"),
+            "wasm_output_path = \"out/encoder.tar\"",
+        )
+        .unwrap();
+        match create_runtime_in(&dir).await {
+            Err(DiceCliError::RuntimeNotBuilt { path }) => {
+                assert_eq!(path, canonical.join("out/encoder.tar"))
+            }
+            other => panic!("expected a missing build output, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn looks_for_the_manifest_in_the_runtime_dir() {
+        let dir = tempfile::tempdir().unwrap();
+
+        match create_runtime_in(dir.path()).await {
+            Err(DiceCliError::NotInDiceRuntime { cwd }) => {
+                assert_eq!(cwd, dir.path().canonicalize().unwrap())
+            }
+            other => panic!("expected the directory to be rejected, got {:?}", other),
+        }
     }
 }
@@ -0,0 +1,109 @@
+use serde_derive::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{error::DiceCliError, parse_error, read_to_string};
+
+/// Directory that marks the root of a runtime repository
+const MANIFEST_DIR: &str = ".dice";
+
+/// File listing the runtime repositories of a workspace
+const WORKSPACE_FILE: &str = ".dice-workspace";
+
+/// Manifest file names looked up in `.dice`, in order of preference
+const MANIFEST_FILES: [&str; 2] = ["runtime.toml", "config.toml"];
+
+const DEFAULT_TARGET: &str = "wasm32-wasi";
+
+/// Function WASI commands are started with
+const DEFAULT_ENTRY_POINT: &str = "_start";
+
+/// Runtime settings read from `.dice/runtime.toml`:
+///
+/// ```toml
+/// name = "my-runtime"
+/// build_command = "cargo build --release --target wasm32-wasi"
+/// wasm_output_path = "target/wasm32-wasi/release/my-runtime.tar"
+/// supported_target = "wasm32-wasi"
+/// entry_point = "_start"
+/// ```
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DiceRuntimeManifest {
+    pub name: Option<String>,
+    pub build_command: Option<String>,
+    pub wasm_output_path: Option<PathBuf>,
+    pub supported_target: Option<String>,
+    /// Function the runtime's WebAssembly module must export
+    pub entry_point: Option<String>,
+}
+
+impl DiceRuntimeManifest {
+    pub fn target(&self) -> &str {
+        self.supported_target.as_deref().unwrap_or(DEFAULT_TARGET)
+    }
+
+    pub fn entry_point(&self) -> &str {
+        self.entry_point.as_deref().unwrap_or(DEFAULT_ENTRY_POINT)
+    }
+
+    /// Archive, or bare WebAssembly binary, produced by the build of the repository at `root`
+    pub fn output_path(&self, root: &Path) -> PathBuf {
+        let output_path = self.wasm_output_path.clone().unwrap_or_else(|| {
+            let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+            let name = root.file_name().unwrap_or_default().to_string_lossy();
+            Path::new("target")
+                .join(self.target())
+                .join("release")
+                .join(format!("{}.tar", name))
+        });
+        root.join(output_path)
+    }
+}
+
+/// Runtime repositories of a monorepo, read from `.dice-workspace`:
+///
+/// ```toml
+/// members = ["runtimes/encoder", "runtimes/decoder"]
+/// ```
+#[derive(Deserialize, Debug)]
+pub struct DiceWorkspace {
+    /// Directories relative to the workspace root, each with its own `.dice` manifest
+    pub members: Vec<PathBuf>,
+}
+
+/// Manifest of the runtime repository at `root`. Older repositories mark themselves
+/// with a `.dice` file, which is read as the manifest itself.
+pub fn manifest_path(root: &Path) -> Option<PathBuf> {
+    let marker = root.join(MANIFEST_DIR);
+    if marker.is_file() {
+        return Some(marker);
+    }
+
+    MANIFEST_FILES
+        .iter()
+        .map(|file| marker.join(file))
+        .find(|path| path.is_file())
+}
+
+/// Where a new manifest is created
+pub fn default_manifest_path(root: &Path) -> PathBuf {
+    root.join(MANIFEST_DIR).join(MANIFEST_FILES[0])
+}
+
+pub fn load(root: &Path) -> Result<DiceRuntimeManifest, DiceCliError> {
+    let path = manifest_path(root).ok_or_else(|| DiceCliError::NotInDiceRuntime {
+        cwd: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+    })?;
+
+    toml::from_str(&read_to_string(&path)?).map_err(|err| parse_error(&path, err))
+}
+
+pub fn load_workspace(root: &Path) -> Result<DiceWorkspace, DiceCliError> {
+    let path = root.join(WORKSPACE_FILE);
+    if !path.is_file() {
+        return Err(DiceCliError::NotInDiceWorkspace {
+            root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+        });
+    }
+
+    toml::from_str(&read_to_string(&path)?).map_err(|err| parse_error(&path, err))
+}
@@ -0,0 +1,90 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::error::DiceCliError;
+
+/// File the output goes to instead of stdout, set once from `--output-file`
+static OUTPUT_FILE: OnceLock<Mutex<BufWriter<File>>> = OnceLock::new();
+
+/// Prints to the command output, like `print!`
+macro_rules! out {
+    ($($arg:tt)*) => {
+        $crate::output::print(&format!($($arg)*))
+    };
+}
+
+/// Prints a line to the command output, like `println!`
+macro_rules! outln {
+    () => {
+        $crate::output::print("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::output::print(&format!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+pub(crate) use {out, outln};
+
+/// Sends the command output to `path`, created or truncated, instead of stdout; errors and
+/// progress still go to stderr
+pub fn configure(path: Option<&Path>) -> Result<(), DiceCliError> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let file = File::create(path).map_err(|source| DiceCliError::Io {
+        action: "create",
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let _ = OUTPUT_FILE.set(Mutex::new(BufWriter::new(file)));
+    Ok(())
+}
+
+/// Whether the output goes to a file rather than stdout
+pub fn is_file() -> bool {
+    OUTPUT_FILE.get().is_some()
+}
+
+/// Writer of the command output, for output that is copied rather than formatted
+pub struct Output;
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match OUTPUT_FILE.get() {
+            Some(file) => file.lock().unwrap().write(buf),
+            None => io::stdout().write(buf),
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        // Whole writes are kept together when tasks print at the same time
+        match OUTPUT_FILE.get() {
+            Some(file) => file.lock().unwrap().write_all(buf),
+            None => io::stdout().lock().write_all(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match OUTPUT_FILE.get() {
+            Some(file) => file.lock().unwrap().flush(),
+            None => io::stdout().flush(),
+        }
+    }
+}
+
+/// Writes `text` to the command output, failing loudly like `print!` does
+pub fn print(text: &str) {
+    if let Err(err) = Output.write_all(text.as_bytes()) {
+        panic!("failed printing the output: {}", err);
+    }
+}
+
+/// Writes out what is buffered, before the process exits
+pub fn flush() {
+    let _ = Output.flush();
+}
@@ -0,0 +1,168 @@
+use directories::BaseDirs;
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+};
+
+use crate::error::DiceCliError;
+
+/// `--file` value that reads the artifact from stdin
+pub const STDIN: &str = "-";
+
+/// Expands a leading `~` to the home directory, and `$VARIABLE` or `${VARIABLE}` to the value
+/// of the environment variable. Used as the value parser of path arguments, so the shell not
+/// expanding them (quoted, or given in a `DICE_` variable) still works.
+pub fn expand(raw: &str) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = raw;
+    if rest == "~" || rest.starts_with("~/") {
+        let dirs = BaseDirs::new().ok_or("could not determine the home directory")?;
+        expanded.push_str(&dirs.home_dir().to_string_lossy());
+        rest = &rest[1..];
+    }
+
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, remainder) = match after.strip_prefix('{') {
+            Some(braced) => {
+                let end = braced
+                    .find('}')
+                    .ok_or_else(|| format!("unclosed ${{ in {}", raw))?;
+                (&braced[..end], &braced[end + 1..])
+            }
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                after.split_at(end)
+            }
+        };
+
+        // A `$` that does not start a variable name is kept as is
+        if name.is_empty() {
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+        let value = env::var(name)
+            .map_err(|_| format!("environment variable {} in {} is not set", name, raw))?;
+        expanded.push_str(&value);
+        rest = remainder;
+    }
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
+
+/// [`expand`] for arguments parsed as a `PathBuf`
+pub fn expand_path(raw: &str) -> Result<PathBuf, String> {
+    expand(raw).map(PathBuf::from)
+}
+
+/// Resolves a path that is read from to an absolute one, so a typo is reported before any
+/// request is made
+pub fn existing(path: &Path, flag: &'static str) -> Result<PathBuf, DiceCliError> {
+    path.canonicalize().map_err(|source| match source.kind() {
+        io::ErrorKind::NotFound => DiceCliError::PathNotFound {
+            flag,
+            path: path.to_path_buf(),
+        },
+        _ => DiceCliError::Io {
+            action: "read",
+            path: path.to_path_buf(),
+            source,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home() -> String {
+        BaseDirs::new()
+            .unwrap()
+            .home_dir()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn expands_a_leading_tilde() {
+        assert_eq!(expand("~").unwrap(), home());
+        assert_eq!(
+            expand("~/data/in.csv").unwrap(),
+            format!("{}/data/in.csv", home())
+        );
+        // Only a tilde starting the path stands for the home directory
+        assert_eq!(expand("~user/in.csv").unwrap(), "~user/in.csv");
+        assert_eq!(expand("data/~/in.csv").unwrap(), "data/~/in.csv");
+    }
+
+    #[test]
+    fn expands_variables() {
+        env::set_var("DICE_TEST_PATHS_DIR", "/srv/dice");
+        env::set_var("DICE_TEST_PATHS_NAME", "runs");
+        assert_eq!(
+            expand("$DICE_TEST_PATHS_DIR/in.csv").unwrap(),
+            "/srv/dice/in.csv"
+        );
+        assert_eq!(
+            expand("${DICE_TEST_PATHS_DIR}_old/in.csv").unwrap(),
+            "/srv/dice_old/in.csv"
+        );
+        assert_eq!(
+            expand("~/$DICE_TEST_PATHS_NAME").unwrap(),
+            format!("{}/runs", home())
+        );
+        assert_eq!(
+            expand_path("$DICE_TEST_PATHS_DIR").unwrap(),
+            PathBuf::from("/srv/dice")
+        );
+    }
+
+    #[test]
+    fn keeps_a_dollar_that_starts_no_variable() {
+        assert_eq!(expand("price$.csv").unwrap(), "price$.csv");
+        assert_eq!(expand("total$").unwrap(), "total$");
+    }
+
+    #[test]
+    fn rejects_unset_variables_and_unclosed_braces() {
+        env::remove_var("DICE_TEST_PATHS_UNSET");
+        assert_eq!(
+            expand("$DICE_TEST_PATHS_UNSET/in.csv").unwrap_err(),
+            "environment variable DICE_TEST_PATHS_UNSET in $DICE_TEST_PATHS_UNSET/in.csv is not set"
+        );
+        assert_eq!(
+            expand("${DICE_TEST_PATHS_DIR/in.csv").unwrap_err(),
+            "unclosed ${ in ${DICE_TEST_PATHS_DIR/in.csv"
+        );
+    }
+
+    #[test]
+    fn resolves_an_existing_path_to_an_absolute_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("in.csv");
+        std::fs::write(&file, "a").unwrap();
+
+        let resolved = existing(&file, "--file").unwrap();
+        assert!(resolved.is_absolute());
+        assert_eq!(resolved, file.canonicalize().unwrap());
+    }
+
+    #[test]
+    fn reports_a_missing_path_with_its_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.csv");
+
+        match existing(&missing, "--file") {
+            Err(DiceCliError::PathNotFound { flag, path }) => {
+                assert_eq!(flag, "--file");
+                assert_eq!(path, missing);
+            }
+            other => panic!("expected a missing path, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use clap::ValueEnum;
+use serde_derive::Serialize;
+use std::{
+    future::Future,
+    io::{self, Cursor, Write},
+    pin::Pin,
+    sync::OnceLock,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio_util::io::ReaderStream;
+
+use crate::error::DiceCliError;
+
+/// How the progress of long running steps is reported
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// Progress bars and messages for people
+    Bar,
+    /// Newline-delimited JSON events on stderr for programs wrapping the CLI
+    Json,
+}
+
+/// Progress mode of this invocation, set once from `--progress`
+static MODE: OnceLock<ProgressMode> = OnceLock::new();
+
+/// Bytes transferred between two progress events of the same transfer
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+/// Step of a command that reports a start and a finish or error event
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Build,
+    Create,
+    Upload,
+    Poll,
+    Download,
+    Extract,
+}
+
+/// A progress event; the field names and values are a stable interface, documented in the
+/// help of `--progress`
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    Start {
+        phase: Phase,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<&'a str>,
+    },
+    Finish {
+        phase: Phase,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<&'a str>,
+    },
+    Error {
+        phase: Phase,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<&'a str>,
+        code: &'static str,
+        message: String,
+    },
+    UploadProgress {
+        id: &'a str,
+        bytes: u64,
+        total: Option<u64>,
+    },
+    DownloadProgress {
+        id: &'a str,
+        bytes: u64,
+        total: Option<u64>,
+    },
+}
+
+pub fn configure(mode: ProgressMode) {
+    let _ = MODE.set(mode);
+}
+
+pub fn is_json() -> bool {
+    MODE.get() == Some(&ProgressMode::Json)
+}
+
+/// Writes an event as one line of JSON in JSON mode. The stderr lock is held for the whole
+/// line, so events of concurrent tasks never interleave, and the line is flushed right away.
+fn emit(event: &Event) {
+    if !is_json() {
+        return;
+    }
+
+    let line = serde_json::to_string(event).unwrap();
+    let mut stderr = io::stderr().lock();
+    let _ = writeln!(stderr, "{}", line);
+    let _ = stderr.flush();
+}
+
+fn start(phase: Phase, id: Option<&str>) {
+    emit(&Event::Start { phase, id });
+}
+
+/// Reports the end of a phase, `id` naming what it worked on or created
+fn report<T>(phase: Phase, id: Option<&str>, result: &Result<T, DiceCliError>) {
+    match result {
+        Ok(_) => emit(&Event::Finish { phase, id }),
+        Err(err) => {
+            let rendered = err.to_string();
+            let message = match rendered.split_once("\nhint: ") {
+                Some((message, _)) => message.to_string(),
+                None => rendered,
+            };
+            emit(&Event::Error {
+                phase,
+                id,
+                code: err.code(),
+                message,
+            });
+        }
+    }
+}
+
+/// Runs a step, reporting its start and then its finish or error
+pub async fn phase<T>(
+    phase: Phase,
+    id: Option<&str>,
+    step: impl Future<Output = Result<T, DiceCliError>>,
+) -> Result<T, DiceCliError> {
+    start(phase, id);
+    let result = step.await;
+    report(phase, id, &result);
+    result
+}
+
+/// [`phase`] for steps that block, like builds and extraction
+pub fn phase_blocking<T>(
+    phase: Phase,
+    id: Option<&str>,
+    step: impl FnOnce() -> Result<T, DiceCliError>,
+) -> Result<T, DiceCliError> {
+    start(phase, id);
+    let result = step();
+    report(phase, id, &result);
+    result
+}
+
+/// Creates a resource, reporting it as the create phase that finishes with the new ID
+pub async fn create<T>(
+    step: impl Future<Output = Result<T, DiceCliError>>,
+    created_id: impl Fn(&T) -> String,
+) -> Result<T, DiceCliError> {
+    start(Phase::Create, None);
+    let result = step.await;
+    let id = result.as_ref().ok().map(created_id);
+    report(Phase::Create, id.as_deref(), &result);
+    result
+}
+
+/// Counts the bytes of an upload or download, emitting a progress event every
+/// [`PROGRESS_STEP`] bytes and once complete
+pub struct Transfer {
+    id: String,
+    upload: bool,
+    total: Option<u64>,
+    bytes: u64,
+    reported: u64,
+}
+
+impl Transfer {
+    pub fn upload(id: &str, total: Option<u64>) -> Transfer {
+        Transfer {
+            id: id.to_string(),
+            upload: true,
+            total,
+            bytes: 0,
+            reported: 0,
+        }
+    }
+
+    pub fn download(id: &str, total: Option<u64>) -> Transfer {
+        Transfer {
+            upload: false,
+            ..Transfer::upload(id, total)
+        }
+    }
+
+    pub fn advance(&mut self, bytes: u64) {
+        self.bytes += bytes;
+        if self.bytes - self.reported < PROGRESS_STEP && Some(self.bytes) != self.total {
+            return;
+        }
+
+        self.reported = self.bytes;
+        let (id, bytes, total) = (self.id.as_str(), self.bytes, self.total);
+        if self.upload {
+            emit(&Event::UploadProgress { id, bytes, total });
+        } else {
+            emit(&Event::DownloadProgress { id, bytes, total });
+        }
+    }
+}
+
+/// Reader that reports the bytes read from it as the progress of a transfer
+struct ProgressReader<R> {
+    inner: R,
+    transfer: Transfer,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ProgressReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = buf.filled().len() - before;
+        if read > 0 {
+            self.transfer.advance(read as u64);
+        }
+        poll
+    }
+}
+
+/// The body of an upload, streamed to report `upload_progress` events in JSON mode
+pub fn upload_body(id: &str, contents: Vec<u8>) -> reqwest::Body {
+    if !is_json() {
+        return contents.into();
+    }
+
+    let total = Some(contents.len() as u64);
+    reqwest::Body::wrap_stream(ReaderStream::new(ProgressReader {
+        inner: Cursor::new(contents),
+        transfer: Transfer::upload(id, total),
+    }))
+}
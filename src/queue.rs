@@ -0,0 +1,323 @@
+use chrono::Utc;
+use directories::ProjectDirs;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+use crate::{
+    archive, config, create_input_artifact, create_job, create_job_execution, error::DiceCliError,
+    history, output::outln, print_table, write_atomically, OutputFormat, UploadOptions,
+};
+
+/// A resource creation that `--queue` can save while the API is unreachable
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "create", rename_all = "snake_case")]
+pub enum Creation {
+    InputArtifact {
+        project_id: String,
+        file: PathBuf,
+        tags: HashMap<String, String>,
+        options: UploadOptions,
+    },
+    Job {
+        project_id: String,
+        runtime_id: String,
+        input_artifact_ids: Vec<String>,
+        tags: HashMap<String, String>,
+    },
+    JobExecution {
+        job_id: String,
+        tags: HashMap<String, String>,
+    },
+}
+
+/// An operation saved in the queue directory, one JSON file each
+#[derive(Serialize, Deserialize, Debug)]
+struct QueuedOperation {
+    queued_at: String,
+    profile: String,
+    #[serde(flatten)]
+    operation: Creation,
+    /// Checksum of the file an upload reads, so a file that changed is not uploaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_checksum: Option<String>,
+}
+
+/// A queued operation as listed by `--list-queue`
+#[derive(Serialize, Debug)]
+struct ListedOperation<'a> {
+    id: String,
+    #[serde(flatten)]
+    queued: &'a QueuedOperation,
+}
+
+impl Creation {
+    /// File the operation uploads, if any
+    fn file(&self) -> Option<&Path> {
+        match self {
+            Creation::InputArtifact { file, .. } => Some(file),
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Creation::InputArtifact {
+                project_id, file, ..
+            } => format!(
+                "create input artifact from {} in project {}",
+                file.display(),
+                project_id
+            ),
+            Creation::Job {
+                project_id,
+                input_artifact_ids,
+                ..
+            } => format!(
+                "create job with {} input artifacts in project {}",
+                input_artifact_ids.len(),
+                project_id
+            ),
+            Creation::JobExecution { job_id, .. } => {
+                format!("create job execution of job {}", job_id)
+            }
+        }
+    }
+
+    async fn run(&self, temp_dir: &Path) -> Result<(), DiceCliError> {
+        match self {
+            Creation::InputArtifact {
+                project_id,
+                file,
+                tags,
+                options,
+            } => create_input_artifact(
+                project_id.clone(),
+                file.to_string_lossy().into_owned(),
+                temp_dir.to_path_buf(),
+                tags.clone(),
+                options,
+            )
+            .await
+            .map(|_| ()),
+            Creation::Job {
+                project_id,
+                runtime_id,
+                input_artifact_ids,
+                tags,
+            } => create_job(
+                project_id.clone(),
+                runtime_id.clone(),
+                input_artifact_ids.clone(),
+                tags.clone(),
+            )
+            .await
+            .map(|_| ()),
+            Creation::JobExecution { job_id, tags } => {
+                let job_execution_id = create_job_execution(job_id.clone(), tags.clone()).await?;
+                outln!("Created job execution: {}", job_execution_id);
+                history::record(
+                    "create-job-execution",
+                    &[("job", job_id), ("job_execution", &job_execution_id)],
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Location of the queue, `~/.local/share/dice/queue` on Linux
+fn queue_dir() -> Result<PathBuf, DiceCliError> {
+    ProjectDirs::from("", "", "dice")
+        .map(|dirs| dirs.data_dir().join("queue"))
+        .ok_or(DiceCliError::NoHomeDirectory)
+}
+
+/// Saves an operation to the end of the queue
+fn enqueue(operation: Creation) -> Result<String, DiceCliError> {
+    let file_checksum = operation.file().map(archive::checksum).transpose()?;
+    let queued = QueuedOperation {
+        queued_at: Utc::now().to_rfc3339(),
+        profile: config::active_profile().to_string(),
+        operation,
+        file_checksum,
+    };
+
+    // Names start with the time in milliseconds, so they sort in the order they were queued
+    let id = format!("{:013}-{}", Utc::now().timestamp_millis(), Uuid::new_v4());
+    let dir = queue_dir()?;
+    let path = dir.join(format!("{}.json", id));
+    let write_failed = |source| DiceCliError::Io {
+        action: "write",
+        path: path.clone(),
+        source,
+    };
+    fs::create_dir_all(&dir).map_err(write_failed)?;
+    write_atomically(
+        &path,
+        serde_json::to_string_pretty(&queued).unwrap().as_bytes(),
+    )
+    .map_err(write_failed)?;
+
+    Ok(id)
+}
+
+/// Queued operations in the order they were queued, with the file each is stored in
+fn load() -> Result<Vec<(PathBuf, QueuedOperation)>, DiceCliError> {
+    let dir = queue_dir()?;
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let read_failed = |source| DiceCliError::Io {
+        action: "read",
+        path: dir.clone(),
+        source,
+    };
+    let mut paths = vec![];
+    for entry in fs::read_dir(&dir).map_err(read_failed)? {
+        let path = entry.map_err(read_failed)?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let contents = fs::read_to_string(&path).map_err(|source| DiceCliError::Io {
+                action: "read",
+                path: path.clone(),
+                source,
+            })?;
+            let queued = serde_json::from_str(&contents).map_err(|err| DiceCliError::Parse {
+                path: path.clone(),
+                message: err.to_string(),
+            })?;
+            Ok((path, queued))
+        })
+        .collect()
+}
+
+fn queue_id(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Runs an operation, saving it to the queue instead of failing when the API is unreachable
+pub async fn run_or_enqueue(operation: Creation, temp_dir: &Path) -> Result<(), DiceCliError> {
+    match operation.run(temp_dir).await {
+        Err(err) if err.is_unreachable() => {
+            let description = operation.describe();
+            let id = enqueue(operation)?;
+            eprintln!("Warning: the DICE API is unreachable: {}", err);
+            outln!("Queued {}: {}", id, description);
+            outln!("Run `dice --flush-queue` once the connection is back");
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+pub fn list(format: OutputFormat) -> Result<(), DiceCliError> {
+    let queued = load()?;
+
+    match format {
+        OutputFormat::Json => {
+            let listed: Vec<ListedOperation> = queued
+                .iter()
+                .map(|(path, queued)| ListedOperation {
+                    id: queue_id(path),
+                    queued,
+                })
+                .collect();
+            outln!("{}", serde_json::to_string_pretty(&listed).unwrap());
+        }
+        OutputFormat::Table | OutputFormat::Raw => print_table(
+            &["ID", "QUEUED", "PROFILE", "OPERATION"],
+            queued
+                .into_iter()
+                .map(|(path, queued)| {
+                    vec![
+                        queue_id(&path),
+                        queued.queued_at,
+                        queued.profile,
+                        queued.operation.describe(),
+                    ]
+                })
+                .collect(),
+        ),
+    }
+
+    Ok(())
+}
+
+/// Runs a queued operation with the profile it was queued with, once its file is unchanged
+async fn replay(queued: &QueuedOperation, temp_dir: &Path) -> Result<(), DiceCliError> {
+    if let (Some(file), Some(file_checksum)) = (queued.operation.file(), &queued.file_checksum) {
+        if &archive::checksum(file)? != file_checksum {
+            return Err(DiceCliError::QueuedFileChanged {
+                path: file.to_path_buf(),
+            });
+        }
+    }
+
+    if queued.profile != config::active_profile() {
+        config::with_profile(&queued.profile, queued.operation.run(temp_dir)).await
+    } else {
+        queued.operation.run(temp_dir).await
+    }
+}
+
+/// Replays the queued operations in order, removing each one that succeeds. Operations that
+/// fail stay queued; flushing stops while the API is still unreachable.
+pub async fn flush(temp_dir: &Path) -> Result<(), DiceCliError> {
+    let queued = load()?;
+    if queued.is_empty() {
+        outln!("No operations are queued");
+        return Ok(());
+    }
+
+    let total = queued.len();
+    let mut failed = 0;
+    for (replayed, (path, queued)) in queued.into_iter().enumerate() {
+        let id = queue_id(&path);
+        match replay(&queued, temp_dir).await {
+            Ok(()) => {
+                fs::remove_file(&path).map_err(|source| DiceCliError::Io {
+                    action: "delete",
+                    path: path.clone(),
+                    source,
+                })?;
+                outln!("Replayed {}: {}", id, queued.operation.describe());
+            }
+            Err(err) if err.is_unreachable() => {
+                eprintln!(
+                    "The DICE API is still unreachable, {} operations stay queued",
+                    total - replayed
+                );
+                return Err(err);
+            }
+            Err(err) => {
+                failed += 1;
+                eprintln!("Could not replay {}: {}", id, err);
+                eprintln!("  it stays queued, delete {} to drop it", path.display());
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(DiceCliError::QueueFlushFailed { failed, total });
+    }
+
+    Ok(())
+}
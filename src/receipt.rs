@@ -0,0 +1,121 @@
+use rust_sdk::model::job::Job;
+use serde_derive::Serialize;
+use serde_json::Value;
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use crate::{error::DiceCliError, output::outln, write_atomically};
+
+/// Whether everything a receipt describes succeeded
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReceiptStatus {
+    Complete,
+    Partial,
+}
+
+/// An output artifact that was downloaded and unpacked
+#[derive(Serialize, Debug)]
+pub struct ArtifactReceipt {
+    pub artifact_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// A step that failed, naming the artifact when the failure belongs to one
+#[derive(Serialize, Debug)]
+pub struct FailedStep {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artifact_id: Option<String>,
+    pub error: Value,
+}
+
+impl FailedStep {
+    pub fn new(artifact_id: Option<String>, err: &DiceCliError) -> Self {
+        FailedStep {
+            artifact_id,
+            error: err.to_json()["error"].clone(),
+        }
+    }
+}
+
+/// Outcome of downloading a set of output artifacts
+#[derive(Serialize, Debug, Default)]
+pub struct DownloadReport {
+    pub output_artifacts: Vec<ArtifactReceipt>,
+    pub failed: Vec<FailedStep>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PhaseTiming {
+    pub phase: &'static str,
+    pub seconds: f64,
+}
+
+/// Provenance record of downloading the output artifacts of a job execution, written with
+/// `--receipt`
+#[derive(Serialize, Debug)]
+pub struct Receipt {
+    pub cli_version: &'static str,
+    pub status: ReceiptStatus,
+    pub project_id: String,
+    pub job_id: String,
+    pub job_execution_id: String,
+    pub runtime_id: String,
+    pub input_artifact_ids: Vec<String>,
+    #[serde(flatten)]
+    pub report: DownloadReport,
+    pub timings: Vec<PhaseTiming>,
+}
+
+impl Receipt {
+    pub fn new(job: &Job, job_execution_id: String) -> Self {
+        Receipt {
+            cli_version: env!("CARGO_PKG_VERSION"),
+            status: ReceiptStatus::Complete,
+            project_id: job.project_id.clone(),
+            job_id: job.id.to_string(),
+            job_execution_id,
+            runtime_id: job.runtime_id.clone(),
+            input_artifact_ids: job.input_artifact_ids.clone(),
+            report: DownloadReport::default(),
+            timings: vec![],
+        }
+    }
+
+    /// Records how long a phase took since it `started`
+    pub fn time(&mut self, phase: &'static str, started: Instant) {
+        self.timings.push(PhaseTiming {
+            phase,
+            seconds: started.elapsed().as_secs_f64(),
+        });
+    }
+
+    /// Writes the receipt, marking it partial when anything failed; `error` is the failure
+    /// that ended the command, if it is not already listed
+    pub fn write(mut self, path: &Path, error: Option<&DiceCliError>) -> Result<(), DiceCliError> {
+        if let Some(err) = error {
+            if self.report.failed.is_empty() {
+                self.report.failed.push(FailedStep::new(None, err));
+            }
+        }
+        if !self.report.failed.is_empty() {
+            self.status = ReceiptStatus::Partial;
+        }
+
+        let contents = serde_json::to_string_pretty(&self).unwrap();
+        write_atomically(path, contents.as_bytes()).map_err(|source| DiceCliError::Io {
+            action: "write",
+            path: path.to_path_buf(),
+            source,
+        })?;
+        outln!("Wrote receipt {}", path.display());
+
+        Ok(())
+    }
+}
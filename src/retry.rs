@@ -0,0 +1,375 @@
+use std::{
+    future::Future,
+    sync::{Mutex, OnceLock},
+    time::{Duration, SystemTime},
+};
+use tokio::time::Instant;
+
+use crate::error::DiceCliError;
+
+/// Retries, delay between attempts and longest backoff, set from `--retries`, `--retry-delay`
+/// and `--max-backoff`, and whether each retry is reported, from `--verbose`
+static RETRY_POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+
+/// Spacing of batch requests, set from `--rps`
+static THROTTLE: OnceLock<Throttle> = OnceLock::new();
+
+tokio::task_local! {
+    /// Set while a whole task is retried for `--on-error retry`, so the requests it makes are
+    /// tried once each instead of retrying inside every retry of the task
+    static TASK_RETRY: ();
+}
+
+const DEFAULT_RETRIES: u32 = 3;
+
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(1000);
+
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug)]
+struct RetryPolicy {
+    retries: u32,
+    delay: Duration,
+    max_backoff: Duration,
+    verbose: bool,
+}
+
+#[derive(Debug)]
+struct Throttle {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+pub fn configure(retries: u32, delay: Duration, max_backoff: Duration, verbose: bool) {
+    let _ = RETRY_POLICY.set(RetryPolicy {
+        retries,
+        delay,
+        max_backoff,
+        verbose,
+    });
+}
+
+/// Limits batch requests to `rps` per second; without it they are not throttled
+pub fn configure_throttle(rps: Option<f64>) {
+    if let Some(rps) = rps.filter(|rps| rps.is_finite() && *rps > 0.0) {
+        let _ = THROTTLE.set(Throttle {
+            interval: Duration::from_secs_f64(1.0 / rps),
+            next: Mutex::new(Instant::now()),
+        });
+    }
+}
+
+/// Waits for the next slot of the `--rps` budget, so parallel tasks together stay below it
+pub async fn throttle() {
+    let Some(throttle) = THROTTLE.get() else {
+        return;
+    };
+    let slot = {
+        let mut next = throttle.next.lock().unwrap();
+        let slot = (*next).max(Instant::now());
+        *next = slot + throttle.interval;
+        slot
+    };
+    tokio::time::sleep_until(slot).await;
+}
+
+/// Exponential backoff from `delay`, capped at `max_backoff`, with up to half of it taken off
+/// at random so parallel tasks do not retry in lockstep
+fn backoff(delay: Duration, max_backoff: Duration, attempt: u32) -> Duration {
+    let backoff = delay
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(max_backoff);
+    let jitter = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |now| now.subsec_nanos()) as f64
+        / 1e9;
+    backoff.mul_f64(1.0 - jitter / 2.0)
+}
+
+/// Runs `f` until it succeeds, fails with an error `should_retry` rejects or runs out of retries
+async fn retry_while<F, Fut, T>(
+    mut f: F,
+    policy: RetryPolicy,
+    should_retry: fn(&DiceCliError) -> bool,
+) -> Result<T, DiceCliError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, DiceCliError>>,
+{
+    let mut attempt = 0;
+    loop {
+        match f().await {
+            Err(err) if attempt < policy.retries && should_retry(&err) => {
+                attempt += 1;
+                // Rate limited requests wait as long as the server asks, or back off
+                let delay = if err.is_rate_limited() {
+                    err.retry_after()
+                        .unwrap_or_else(|| backoff(policy.delay, policy.max_backoff, attempt))
+                        .min(policy.max_backoff)
+                } else {
+                    policy.delay
+                };
+                if policy.verbose {
+                    eprintln!(
+                        "{}, retrying in {:.1}s ({}/{})",
+                        err.to_string().lines().next().unwrap_or_default(),
+                        delay.as_secs_f64(),
+                        attempt,
+                        policy.retries
+                    );
+                }
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn configured_policy() -> RetryPolicy {
+    RETRY_POLICY.get().copied().unwrap_or(RetryPolicy {
+        retries: DEFAULT_RETRIES,
+        delay: DEFAULT_RETRY_DELAY,
+        max_backoff: DEFAULT_MAX_BACKOFF,
+        verbose: false,
+    })
+}
+
+/// Runs `f` with the retry policy configured on the command line, or once when the task it
+/// belongs to is already retried as a whole
+pub async fn with_configured_retry<F, Fut, T>(f: F) -> Result<T, DiceCliError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, DiceCliError>>,
+{
+    retry_while(f, request_policy(), DiceCliError::is_retryable).await
+}
+
+/// Like [`with_configured_retry`] for requests that create a resource, which are only sent
+/// again when the server cannot have created it
+pub async fn with_configured_create_retry<F, Fut, T>(f: F) -> Result<T, DiceCliError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, DiceCliError>>,
+{
+    retry_while(f, request_policy(), DiceCliError::is_retryable_create).await
+}
+
+/// The configured policy, without retries when the task around the request is retried
+fn request_policy() -> RetryPolicy {
+    let mut policy = configured_policy();
+    if TASK_RETRY.try_with(|_| ()).is_ok() {
+        policy.retries = 0;
+    }
+    policy
+}
+
+/// Runs a whole task with the configured retry policy for `--on-error retry`, starting it
+/// over on the errors `should_retry` accepts; the requests inside it are not retried on
+/// their own
+pub async fn with_configured_task_retry<F, Fut, T>(
+    mut f: F,
+    should_retry: fn(&DiceCliError) -> bool,
+) -> Result<T, DiceCliError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, DiceCliError>>,
+{
+    retry_while(
+        || TASK_RETRY.scope((), f()),
+        configured_policy(),
+        should_retry,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_for_download, test_server};
+    use std::sync::Arc;
+
+    /// Serves `responses` in order, one per connection, recording when each request arrived
+    async fn serve(responses: Vec<&'static str>) -> (String, Arc<Mutex<Vec<Instant>>>) {
+        let (listener, uri) = test_server::bind("/artifact").await;
+        let arrivals = Arc::new(Mutex::new(vec![]));
+        let recorded = arrivals.clone();
+
+        tokio::spawn(async move {
+            for response in responses {
+                let (socket, _) = test_server::accept(&listener).await;
+                recorded.lock().unwrap().push(Instant::now());
+                test_server::respond(socket, response).await;
+            }
+        });
+
+        (uri, arrivals)
+    }
+
+    /// A client that reaches the local server even when a proxy is configured
+    fn local_client() -> reqwest::Client {
+        reqwest::Client::builder().no_proxy().build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn backs_off_on_rate_limits_until_the_request_succeeds() {
+        let (uri, arrivals) = serve(vec![
+            "HTTP/1.1 429 Too Many Requests\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 429 Too Many Requests\r\nretry-after: 1\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok",
+        ])
+        .await;
+        let policy = RetryPolicy {
+            retries: 3,
+            delay: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            verbose: false,
+        };
+
+        let client = local_client();
+        let response = retry_while(
+            || get_for_download(client.clone(), &uri),
+            policy,
+            DiceCliError::is_retryable,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+
+        let arrivals = arrivals.lock().unwrap();
+        assert_eq!(arrivals.len(), 3);
+        // Without Retry-After the first backoff is the delay less up to half of it in jitter
+        let first = arrivals[1] - arrivals[0];
+        assert!(
+            first >= Duration::from_millis(100) && first < Duration::from_millis(900),
+            "{:?}",
+            first
+        );
+        // The server asked for a second
+        let second = arrivals[2] - arrivals[1];
+        assert!(
+            second >= Duration::from_secs(1) && second < Duration::from_millis(1800),
+            "{:?}",
+            second
+        );
+    }
+
+    #[tokio::test]
+    async fn caps_retry_after_at_the_longest_backoff() {
+        let (uri, arrivals) = serve(vec![
+            "HTTP/1.1 429 Too Many Requests\r\nretry-after: 3600\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            test_server::OK,
+        ])
+        .await;
+        let policy = RetryPolicy {
+            retries: 1,
+            delay: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(300),
+            verbose: false,
+        };
+
+        let client = local_client();
+        retry_while(
+            || get_for_download(client.clone(), &uri),
+            policy,
+            DiceCliError::is_retryable,
+        )
+        .await
+        .unwrap();
+
+        let arrivals = arrivals.lock().unwrap();
+        let waited = arrivals[1] - arrivals[0];
+        assert!(
+            waited >= Duration::from_millis(300) && waited < Duration::from_secs(2),
+            "{:?}",
+            waited
+        );
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_configured_retries() {
+        let (uri, arrivals) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+        ])
+        .await;
+        let policy = RetryPolicy {
+            retries: 1,
+            delay: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+            verbose: false,
+        };
+
+        let client = local_client();
+        let result = retry_while(
+            || get_for_download(client.clone(), &uri),
+            policy,
+            DiceCliError::is_retryable,
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(DiceCliError::DownloadFailed { status: 503, .. })
+        ));
+        assert_eq!(arrivals.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_a_task_at_one_layer() {
+        let _ = RETRY_POLICY.set(RetryPolicy {
+            retries: 2,
+            delay: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+            verbose: false,
+        });
+        let (uri, arrivals) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+            9
+        ])
+        .await;
+
+        let client = local_client();
+        let result = with_configured_task_retry(
+            || async { with_configured_retry(|| get_for_download(client.clone(), &uri)).await },
+            DiceCliError::is_retryable,
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(DiceCliError::DownloadFailed { status: 503, .. })
+        ));
+        // One request per attempt of the task instead of (retries + 1)^2
+        assert_eq!(arrivals.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_a_task_on_permanent_errors() {
+        let (uri, arrivals) = serve(vec![
+            "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+            4
+        ])
+        .await;
+
+        let client = local_client();
+        let result = with_configured_task_retry(
+            || async { with_configured_retry(|| get_for_download(client.clone(), &uri)).await },
+            DiceCliError::is_retryable,
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(DiceCliError::DownloadFailed { status: 404, .. })
+        ));
+        assert_eq!(arrivals.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn doubles_the_backoff_up_to_the_cap() {
+        let delay = Duration::from_millis(100);
+        let max_backoff = Duration::from_millis(350);
+        for (attempt, full) in [(1, 100), (2, 200), (3, 350), (10, 350)] {
+            let backoff = backoff(delay, max_backoff, attempt);
+            let full = Duration::from_millis(full);
+            assert!(backoff <= full && backoff >= full / 2, "{:?}", backoff);
+        }
+    }
+}
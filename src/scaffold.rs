@@ -0,0 +1,140 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{error::DiceCliError, get_current_dir, manifest, output::outln, BuildSystem};
+
+const MAKE_MANIFEST_TEMPLATE: &str = r#"# Name of the runtime, used when --name is not given
+name = "{name}"
+
+# The runtime is built with `make clean` and `make build` unless a build_command is set
+wasm_output_path = "target/wasm32-wasi/release/{package}.tar"
+
+supported_target = "wasm32-wasi"
+"#;
+
+const CARGO_MANIFEST_TEMPLATE: &str = r#"# Name of the runtime, used when --name is not given
+name = "{name}"
+
+build_command = "cargo build --release"
+
+# A bare .wasm binary is packed into a tarball before it is uploaded
+wasm_output_path = "target/wasm32-wasi/release/{package}.wasm"
+
+supported_target = "wasm32-wasi"
+"#;
+
+const MAKEFILE_TEMPLATE: &str = "TARGET := wasm32-wasi
+RELEASE_DIR := target/$(TARGET)/release
+
+build:
+\tcargo build --release --target $(TARGET)
+\ttar -cf $(RELEASE_DIR)/{package}.tar -C $(RELEASE_DIR) {package}.wasm
+
+clean:
+\tcargo clean
+
+.PHONY: build clean
+";
+
+const CARGO_TOML_TEMPLATE: &str = r#"[package]
+name = "{package}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+"#;
+
+const CARGO_CONFIG_TEMPLATE: &str = r#"[build]
+target = "wasm32-wasi"
+"#;
+
+const MAIN_TEMPLATE: &str = r#"fn main() {
+    outln!("Hello from {name}!");
+}
+"#;
+
+/// Cargo package name for a runtime name: lowercase alphanumerics separated by dashes
+fn package_name(name: &str) -> String {
+    let package = name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if package.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        package
+    } else {
+        format!("runtime-{}", package)
+            .trim_end_matches('-')
+            .to_string()
+    }
+}
+
+/// Creates a runtime project in the current directory: a `.dice/runtime.toml` manifest, the
+/// build files for `build_system` and a hello world entry point. Existing files are kept.
+pub fn init_runtime(name: Option<String>, build_system: BuildSystem) -> Result<(), DiceCliError> {
+    if let Some(existing) = manifest::manifest_path(Path::new(".")) {
+        return Err(DiceCliError::AlreadyExists { path: existing });
+    }
+
+    let name = name.unwrap_or_else(get_current_dir);
+    let package = package_name(&name);
+    let render = |template: &str| {
+        template
+            .replace("{name}", &name)
+            .replace("{package}", &package)
+    };
+
+    let mut files: Vec<(PathBuf, String)> = match build_system {
+        BuildSystem::Make => vec![
+            (
+                manifest::default_manifest_path(Path::new(".")),
+                render(MAKE_MANIFEST_TEMPLATE),
+            ),
+            (PathBuf::from("Makefile"), render(MAKEFILE_TEMPLATE)),
+        ],
+        BuildSystem::Cargo => vec![
+            (
+                manifest::default_manifest_path(Path::new(".")),
+                render(CARGO_MANIFEST_TEMPLATE),
+            ),
+            (
+                Path::new(".cargo").join("config.toml"),
+                CARGO_CONFIG_TEMPLATE.to_string(),
+            ),
+        ],
+    };
+    files.push((PathBuf::from("Cargo.toml"), render(CARGO_TOML_TEMPLATE)));
+    files.push((Path::new("src").join("main.rs"), render(MAIN_TEMPLATE)));
+
+    for (path, contents) in files {
+        if path.exists() {
+            outln!("Kept existing {}", path.display());
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|source| DiceCliError::Io {
+                action: "create",
+                path: parent.to_path_buf(),
+                source,
+            })?;
+        }
+        fs::write(&path, contents).map_err(|source| DiceCliError::Io {
+            action: "write",
+            path: path.clone(),
+            source,
+        })?;
+        outln!("Created {}", path.display());
+    }
+
+    outln!();
+    outln!("Next steps:");
+    outln!("  1. rustup target add wasm32-wasi");
+    outln!("  2. Write your runtime in src/main.rs");
+    outln!("  3. dice --create-runtime --project-id <project id>");
+
+    Ok(())
+}
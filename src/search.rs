@@ -0,0 +1,216 @@
+use clap::ValueEnum;
+use mongodb::bson::{doc, Document};
+use serde_derive::Serialize;
+
+use crate::{
+    contains_text, enum_to_string, error::DiceCliError, output::outln, print_table, try_sdk,
+    OutputFormat, NAME_TAG,
+};
+
+/// Type of resource `--search` looks through
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchType {
+    Project,
+    Runtime,
+    Job,
+    Execution,
+    Artifact,
+}
+
+#[derive(Serialize, Debug)]
+struct Match {
+    id: String,
+    name: String,
+    details: String,
+}
+
+/// Matches grouped by type, in the order they are printed
+#[derive(Serialize, Debug)]
+struct SearchResults {
+    projects: Vec<Match>,
+    runtimes: Vec<Match>,
+    jobs: Vec<Match>,
+    job_executions: Vec<Match>,
+    artifacts: Vec<Match>,
+}
+
+/// Filter matching resources whose name tag, or one of `fields`, contains `query`
+fn query_filter(query: &str, fields: &[&str]) -> Document {
+    let name_field = format!("tags.{}", NAME_TAG);
+    let conditions: Vec<Document> = fields
+        .iter()
+        .copied()
+        .chain([name_field.as_str()])
+        .map(|field| doc! { field: contains_text(query) })
+        .collect();
+    doc! { "$or": conditions }
+}
+
+async fn search_projects(query: &str) -> Result<Vec<Match>, DiceCliError> {
+    let projects = try_sdk("list projects", || {
+        rust_sdk::api::project::list(query_filter(query, &["description"]))
+    })
+    .await?;
+
+    Ok(projects
+        .into_iter()
+        .map(|project| Match {
+            id: project.id.to_string(),
+            name: project.tags.get(NAME_TAG).cloned().unwrap_or_default(),
+            details: project.description,
+        })
+        .collect())
+}
+
+async fn search_runtimes(query: &str) -> Result<Vec<Match>, DiceCliError> {
+    let runtimes = try_sdk("list runtimes", || {
+        rust_sdk::api::runtime::list(query_filter(query, &["name"]))
+    })
+    .await?;
+
+    Ok(runtimes
+        .into_iter()
+        .map(|runtime| Match {
+            id: runtime.id.to_string(),
+            name: runtime.name,
+            details: format!("{:?}, project {}", runtime.status, runtime.project_id),
+        })
+        .collect())
+}
+
+async fn search_jobs(query: &str) -> Result<Vec<Match>, DiceCliError> {
+    let jobs = try_sdk("list jobs", || {
+        rust_sdk::api::job::list(query_filter(query, &[]))
+    })
+    .await?;
+
+    Ok(jobs
+        .into_iter()
+        .map(|job| Match {
+            id: job.id.to_string(),
+            name: job.tags.get(NAME_TAG).cloned().unwrap_or_default(),
+            details: format!("runtime {}, project {}", job.runtime_id, job.project_id),
+        })
+        .collect())
+}
+
+async fn search_job_executions(query: &str) -> Result<Vec<Match>, DiceCliError> {
+    let job_executions = try_sdk("list job executions", || {
+        rust_sdk::api::job_execution::list(query_filter(query, &[]))
+    })
+    .await?;
+
+    Ok(job_executions
+        .into_iter()
+        .map(|job_execution| Match {
+            id: job_execution.id.to_string(),
+            name: job_execution
+                .tags
+                .get(NAME_TAG)
+                .cloned()
+                .unwrap_or_default(),
+            details: format!("{:?}, job {}", job_execution.status, job_execution.job_id),
+        })
+        .collect())
+}
+
+async fn search_artifacts(query: &str) -> Result<Vec<Match>, DiceCliError> {
+    let artifacts = try_sdk("list artifacts", || {
+        rust_sdk::api::artifact::list(query_filter(query, &[]))
+    })
+    .await?;
+
+    Ok(artifacts
+        .into_iter()
+        .map(|artifact| Match {
+            id: artifact.id.to_string(),
+            name: artifact.tags.get(NAME_TAG).cloned().unwrap_or_default(),
+            details: format!(
+                "{} {}, {:?}",
+                enum_to_string(&artifact.artifact_type).to_lowercase(),
+                enum_to_string(&artifact.entity_type).to_lowercase(),
+                artifact.status
+            ),
+        })
+        .collect())
+}
+
+/// Runs `search` unless another type was asked for
+async fn search_if<F>(wanted: bool, search: impl FnOnce() -> F) -> Result<Vec<Match>, DiceCliError>
+where
+    F: std::future::Future<Output = Result<Vec<Match>, DiceCliError>>,
+{
+    if wanted {
+        search().await
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// Searches every type of resource, or only `search_type`, for names containing `query`;
+/// projects also match on their description and runtimes on their own name field
+pub async fn search_all(
+    query: String,
+    search_type: Option<SearchType>,
+    format: OutputFormat,
+) -> Result<(), DiceCliError> {
+    if query.trim().is_empty() {
+        return Err(DiceCliError::InvalidArgument {
+            message: "--search needs some text to look for".to_string(),
+        });
+    }
+    let wanted = |candidate: SearchType| search_type.unwrap_or(candidate) == candidate;
+
+    let (projects, runtimes, jobs, job_executions, artifacts) = tokio::join!(
+        search_if(wanted(SearchType::Project), || search_projects(&query)),
+        search_if(wanted(SearchType::Runtime), || search_runtimes(&query)),
+        search_if(wanted(SearchType::Job), || search_jobs(&query)),
+        search_if(wanted(SearchType::Execution), || search_job_executions(
+            &query
+        )),
+        search_if(wanted(SearchType::Artifact), || search_artifacts(&query)),
+    );
+    let results = SearchResults {
+        projects: projects?,
+        runtimes: runtimes?,
+        jobs: jobs?,
+        job_executions: job_executions?,
+        artifacts: artifacts?,
+    };
+
+    match format {
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&results).unwrap()),
+        OutputFormat::Table | OutputFormat::Raw => {
+            let groups = [
+                ("Projects", results.projects),
+                ("Runtimes", results.runtimes),
+                ("Jobs", results.jobs),
+                ("Job executions", results.job_executions),
+                ("Artifacts", results.artifacts),
+            ];
+            let mut found = false;
+            for (title, matches) in groups {
+                if matches.is_empty() {
+                    continue;
+                }
+                if found {
+                    outln!();
+                }
+                found = true;
+                outln!("{} ({})", title, matches.len());
+                print_table(
+                    &["ID", "NAME", "DETAILS"],
+                    matches
+                        .into_iter()
+                        .map(|found| vec![found.id, found.name, found.details])
+                        .collect(),
+                );
+            }
+            if !found {
+                outln!("Nothing matches \"{}\"", query);
+            }
+        }
+    }
+
+    Ok(())
+}
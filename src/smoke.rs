@@ -0,0 +1,216 @@
+use console::style;
+use rust_sdk::model::{job_execution::Status as JobExecutionStatus, project::CreateProjectDTO};
+use std::{
+    collections::HashMap,
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+use crate::{
+    create_input_artifact, create_job, create_job_execution, delete, download_output_artifacts,
+    error::DiceCliError, output::outln, try_sdk_create, wait_for_job_execution, DownloadOptions,
+    OnErrorStrategy, UploadOptions, NAME_TAG,
+};
+
+/// Contents of the input artifact, which the test runtime is expected to copy to its output
+const SMOKE_TEST_INPUT: &[u8] = b"DICE smoke test\n";
+
+const INPUT_FILE_NAME: &str = "dice-smoke-test.txt";
+
+/// Tag marking every resource the smoke test creates, so leftovers can be found
+const SMOKE_TEST_TAG: &str = "smoke_test";
+
+/// Runs one step, printing whether it passed and how long it took
+async fn step<T>(
+    name: &str,
+    run: impl Future<Output = Result<T, DiceCliError>>,
+) -> Result<T, DiceCliError> {
+    let started = Instant::now();
+    let result = run.await;
+    let status = match &result {
+        Ok(_) => style("PASS").green(),
+        Err(_) => style("FAIL").red(),
+    };
+    outln!(
+        "[{}] {} ({:.1}s)",
+        status,
+        name,
+        started.elapsed().as_secs_f64()
+    );
+    result
+}
+
+/// Whether any file under `dir` holds exactly the smoke test input
+fn contains_input(dir: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let path = entry.path();
+        if path.is_dir() {
+            contains_input(&path)
+        } else {
+            fs::read(&path).is_ok_and(|contents| contents == SMOKE_TEST_INPUT)
+        }
+    })
+}
+
+/// Steps from the project to the checked output; `project_id` is set as soon as the project
+/// exists, so it can be deleted whichever step fails
+async fn run_steps(
+    runtime_id: &str,
+    work_dir: &Path,
+    timeout: Duration,
+    project_id: &mut Option<String>,
+) -> Result<(), DiceCliError> {
+    let tags = HashMap::from([
+        (NAME_TAG.to_string(), "dice-smoke-test".to_string()),
+        (SMOKE_TEST_TAG.to_string(), "true".to_string()),
+    ]);
+
+    let created_project_id = step(
+        "create project",
+        try_sdk_create("create project", || {
+            rust_sdk::api::project::create(CreateProjectDTO {
+                description: "Temporary project of `dice --smoke-test`".to_string(),
+                tags: tags.clone(),
+            })
+        }),
+    )
+    .await?;
+    outln!("  project {}", created_project_id);
+    let project_id = project_id.insert(created_project_id);
+
+    let input_path = work_dir.join(INPUT_FILE_NAME);
+    fs::write(&input_path, SMOKE_TEST_INPUT).map_err(|source| DiceCliError::Io {
+        action: "write",
+        path: input_path.clone(),
+        source,
+    })?;
+    let options = UploadOptions {
+        content_type: None,
+        dedup: false,
+        global_dedup: false,
+        force_upload: true,
+    };
+    let artifact = step(
+        "upload input artifact",
+        create_input_artifact(
+            project_id.clone(),
+            input_path.to_string_lossy().into_owned(),
+            work_dir.to_path_buf(),
+            tags.clone(),
+            &options,
+        ),
+    )
+    .await?;
+
+    let job_id = step(
+        "create job",
+        create_job(
+            project_id.clone(),
+            runtime_id.to_string(),
+            vec![artifact.artifact_id],
+            tags.clone(),
+        ),
+    )
+    .await?;
+
+    let job_execution_id = step(
+        "create job execution",
+        create_job_execution(job_id, tags.clone()),
+    )
+    .await?;
+
+    step("wait for the job execution", async {
+        let job_execution = wait_for_job_execution(job_execution_id.clone(), timeout).await?;
+        if job_execution.status != JobExecutionStatus::Completed {
+            return Err(DiceCliError::JobExecutionNotCompleted {
+                id: job_execution_id.clone(),
+                status: format!("{:?}", job_execution.status),
+            });
+        }
+        Ok(())
+    })
+    .await?;
+
+    let output_dir = work_dir.join("output");
+    let download_options = DownloadOptions {
+        ignore_space_check: true,
+        flatten: false,
+        on_error: OnErrorStrategy::Stop,
+        filter_tags: vec![],
+        filter_name: None,
+        allow_empty: false,
+        force: true,
+        max_parallel: 1,
+        verbose: false,
+    };
+    step(
+        "download output artifacts",
+        download_output_artifacts(
+            job_execution_id,
+            output_dir.clone(),
+            work_dir.to_path_buf(),
+            download_options,
+            vec![],
+            None,
+            None,
+        ),
+    )
+    .await?;
+
+    step("verify the output", async {
+        if !contains_input(&output_dir) {
+            return Err(DiceCliError::SmokeTestOutputMismatch { path: output_dir });
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Runs a job end to end in a temporary project with the test runtime `runtime_id`: upload an
+/// input, create a job and an execution, wait for it, download and check the output. Everything
+/// created is deleted afterwards unless `keep` is set.
+pub async fn smoke_test(
+    runtime_id: String,
+    temp_dir: PathBuf,
+    timeout: Duration,
+    keep: bool,
+) -> Result<(), DiceCliError> {
+    let work_dir = temp_dir.join(format!("smoke-test-{}", Uuid::new_v4()));
+    fs::create_dir_all(&work_dir).map_err(|source| DiceCliError::Io {
+        action: "write",
+        path: work_dir.clone(),
+        source,
+    })?;
+
+    let mut project_id = None;
+    let result = run_steps(&runtime_id, &work_dir, timeout, &mut project_id).await;
+
+    let cleaned_up = match project_id {
+        Some(project_id) if keep => {
+            outln!(
+                "Kept project {} and its resources, and the files in {}",
+                project_id,
+                work_dir.display()
+            );
+            return result;
+        }
+        Some(project_id) => {
+            step(
+                "delete everything created",
+                delete::delete_project(project_id, true, true, false),
+            )
+            .await
+        }
+        None => Ok(()),
+    };
+    let _ = fs::remove_dir_all(&work_dir);
+
+    // The failed step matters more than a failed clean up
+    result.and(cleaned_up)
+}
@@ -0,0 +1,555 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use mongodb::bson::{doc, oid::ObjectId};
+use rust_sdk::model::{
+    artifact::{Artifact, ArtifactType},
+    job_execution::{JobExecution, Status as JobExecutionStatus},
+};
+use serde_derive::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    date_filter, enum_to_string, error::DiceCliError, output::outln, print_table, try_sdk,
+    DateRange, OutputFormat,
+};
+
+/// Wall-clock durations in seconds of the finished executions, from their creation to their
+/// last output artifact
+#[derive(Serialize, Debug)]
+struct DurationStats {
+    min: i64,
+    median: i64,
+    p95: i64,
+    max: i64,
+}
+
+#[derive(Serialize, Debug)]
+struct JobExecutionStats {
+    job_id: String,
+    total: usize,
+    by_status: BTreeMap<String, usize>,
+    /// Completed executions out of all that completed or failed
+    success_rate: Option<f64>,
+    durations: Option<DurationStats>,
+    /// Finished executions without output artifacts, whose end is not known
+    missing_timing: usize,
+}
+
+/// Parses a duration such as `30m`, `24h` or `7d`
+fn parse_since(since: &str) -> Result<Duration, DiceCliError> {
+    let invalid = || DiceCliError::InvalidArgument {
+        message: format!(
+            "invalid --since \"{}\", expected a number followed by s, m, h or d",
+            since
+        ),
+    };
+
+    let unit_index = since.char_indices().last().map_or(0, |(index, _)| index);
+    let (amount, unit) = since.split_at(unit_index);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+
+    match unit {
+        "s" => Ok(Duration::seconds(amount)),
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        _ => Err(invalid()),
+    }
+}
+
+/// When a resource was created, which the API records in the timestamp of its ID
+pub fn created_at(id: &ObjectId) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(id.timestamp().timestamp_millis())
+        .unwrap()
+}
+
+pub fn is_finished(job_execution: &JobExecution) -> bool {
+    !matches!(
+        job_execution.status,
+        JobExecutionStatus::Pending | JobExecutionStatus::Running
+    )
+}
+
+/// Latest creation time of the artifacts of each entity, from (entity ID, artifact ID) pairs
+fn latest_by_entity<'a>(
+    artifacts: impl IntoIterator<Item = (String, &'a ObjectId)>,
+) -> HashMap<String, DateTime<Utc>> {
+    let mut latest: HashMap<String, DateTime<Utc>> = HashMap::new();
+    for (entity_id, artifact_id) in artifacts {
+        let created = created_at(artifact_id);
+        latest
+            .entry(entity_id)
+            .and_modify(|time| *time = (*time).max(created))
+            .or_insert(created);
+    }
+    latest
+}
+
+/// When each execution produced its last output artifact, by execution ID. The API records
+/// no end time for an execution, so a finished execution is taken to have ended then.
+pub fn finish_times(output_artifacts: &[Artifact]) -> HashMap<String, DateTime<Utc>> {
+    latest_by_entity(
+        output_artifacts
+            .iter()
+            .map(|artifact| (artifact.entity_id.to_string(), &artifact.id)),
+    )
+}
+
+/// [`finish_times`] of the finished executions among `job_executions`
+pub async fn fetch_finish_times(
+    job_executions: &[JobExecution],
+) -> Result<HashMap<String, DateTime<Utc>>, DiceCliError> {
+    let finished_ids: Vec<ObjectId> = job_executions
+        .iter()
+        .filter(|job_execution| is_finished(job_execution))
+        .map(|job_execution| job_execution.id)
+        .collect();
+    if finished_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let output_artifacts = try_sdk("list output artifacts", || {
+        rust_sdk::api::artifact::list(doc! {
+            "artifact_type": enum_to_string(&ArtifactType::Output),
+            "entity_id": { "$in": finished_ids.clone() },
+        })
+    })
+    .await?;
+    Ok(finish_times(&output_artifacts))
+}
+
+/// When a finished execution ended, if it has output artifacts to date it by
+pub fn finished_at(
+    job_execution: &JobExecution,
+    finish_times: &HashMap<String, DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    if !is_finished(job_execution) {
+        return None;
+    }
+    finish_times.get(&job_execution.id.to_string()).copied()
+}
+
+/// Sorted wall-clock durations in seconds of executions given as (creation, end) times, and
+/// how many of them have no end to measure up to
+fn durations(
+    timings: impl IntoIterator<Item = (DateTime<Utc>, Option<DateTime<Utc>>)>,
+) -> (Vec<i64>, usize) {
+    let mut durations = vec![];
+    let mut missing_timing = 0;
+    for (created, finished) in timings {
+        match finished {
+            Some(finished) => durations.push((finished - created).num_seconds().max(0)),
+            None => missing_timing += 1,
+        }
+    }
+    durations.sort_unstable();
+    (durations, missing_timing)
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[i64], percent: f64) -> i64 {
+    let rank = ((percent / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+pub async fn job_execution_stats(
+    job_id: String,
+    since: Option<String>,
+    created: DateRange,
+    format: OutputFormat,
+) -> Result<(), DiceCliError> {
+    let since = since.as_deref().map(parse_since).transpose()?;
+
+    let mut filter = doc! { "job_id": job_id.clone() };
+    date_filter(&mut filter, created);
+    let executions = try_sdk("list job executions", || {
+        rust_sdk::api::job_execution::list(filter.clone())
+    })
+    .await?;
+
+    // Executions are filtered on their creation time, which is part of their ID
+    let executions: Vec<_> = executions
+        .into_iter()
+        .filter(|execution| {
+            since.is_none_or(|since| Utc::now() - created_at(&execution.id) <= since)
+        })
+        .collect();
+    let finish_times = fetch_finish_times(&executions).await?;
+
+    let total = executions.len();
+    let by_status = count_by(&executions, |execution| enum_to_string(&execution.status));
+    let (durations, missing_timing) = durations(
+        executions
+            .iter()
+            .filter(|execution| is_finished(execution))
+            .map(|execution| {
+                (
+                    created_at(&execution.id),
+                    finished_at(execution, &finish_times),
+                )
+            }),
+    );
+    let completed = by_status.get("Completed").copied().unwrap_or(0);
+    let failed = by_status.get("Failed").copied().unwrap_or(0);
+    let stats = JobExecutionStats {
+        job_id,
+        total,
+        success_rate: (completed + failed > 0)
+            .then(|| completed as f64 / (completed + failed) as f64),
+        durations: (!durations.is_empty()).then(|| DurationStats {
+            min: durations[0],
+            median: percentile(&durations, 50.0),
+            p95: percentile(&durations, 95.0),
+            max: durations[durations.len() - 1],
+        }),
+        by_status,
+        missing_timing,
+    };
+
+    match format {
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&stats).unwrap()),
+        OutputFormat::Table | OutputFormat::Raw => {
+            print_table(
+                &["STATUS", "COUNT"],
+                stats
+                    .by_status
+                    .iter()
+                    .map(|(status, count)| vec![status.clone(), count.to_string()])
+                    .collect(),
+            );
+            outln!();
+
+            match &stats.durations {
+                Some(durations) => print_table(
+                    &["MIN", "MEDIAN", "P95", "MAX"],
+                    vec![[
+                        durations.min,
+                        durations.median,
+                        durations.p95,
+                        durations.max,
+                    ]
+                    .iter()
+                    .map(|seconds| format!("{}s", seconds))
+                    .collect()],
+                ),
+                None => outln!("No finished executions with output artifacts to time"),
+            }
+            if stats.missing_timing > 0 {
+                outln!(
+                    "{} finished executions without output artifacts to time",
+                    stats.missing_timing
+                );
+            }
+
+            match stats.success_rate {
+                Some(success_rate) => outln!(
+                    "Success rate: {:.1}% of {} finished executions",
+                    success_rate * 100.0,
+                    completed + failed
+                ),
+                None => outln!("Success rate: no finished executions"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Jobs that reference a runtime and the state of their executions
+#[derive(Serialize, Debug)]
+pub struct RuntimeUsage {
+    runtime_id: String,
+    jobs: usize,
+    completed_executions: usize,
+    pending_executions: usize,
+    running_executions: usize,
+}
+
+impl RuntimeUsage {
+    fn new(runtime_id: String, jobs: usize) -> Self {
+        RuntimeUsage {
+            runtime_id,
+            jobs,
+            completed_executions: 0,
+            pending_executions: 0,
+            running_executions: 0,
+        }
+    }
+
+    fn count(&mut self, status: &JobExecutionStatus) {
+        match status {
+            JobExecutionStatus::Completed => self.completed_executions += 1,
+            JobExecutionStatus::Pending => self.pending_executions += 1,
+            JobExecutionStatus::Running => self.running_executions += 1,
+            JobExecutionStatus::Failed => (),
+        }
+    }
+
+    /// Warning about executions that will still run the runtime, if there are any
+    fn unfinished_warning(&self) -> Option<String> {
+        (self.pending_executions + self.running_executions > 0).then(|| {
+            format!(
+                "Warning: runtime {} still has {} pending and {} running job executions",
+                self.runtime_id, self.pending_executions, self.running_executions
+            )
+        })
+    }
+
+    /// Warns when executions that will still run the runtime exist
+    pub fn warn_unfinished(&self) {
+        if let Some(warning) = self.unfinished_warning() {
+            eprintln!("{}", warning);
+        }
+    }
+}
+
+pub async fn runtime_usage(runtime_id: String) -> Result<RuntimeUsage, DiceCliError> {
+    let job_ids: Vec<String> = try_sdk("list jobs", || {
+        rust_sdk::api::job::list(doc! { "runtime_id": runtime_id.clone() })
+    })
+    .await?
+    .into_iter()
+    .map(|job| job.id.to_string())
+    .collect();
+
+    let mut usage = RuntimeUsage::new(runtime_id, job_ids.len());
+    if job_ids.is_empty() {
+        return Ok(usage);
+    }
+
+    let executions = try_sdk("list job executions", || {
+        rust_sdk::api::job_execution::list(doc! { "job_id": { "$in": job_ids.clone() } })
+    })
+    .await?;
+    for execution in executions {
+        usage.count(&execution.status);
+    }
+
+    Ok(usage)
+}
+
+pub async fn runtime_usage_stats(
+    runtime_id: String,
+    format: OutputFormat,
+) -> Result<(), DiceCliError> {
+    let usage = runtime_usage(runtime_id).await?;
+
+    match format {
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&usage).unwrap()),
+        OutputFormat::Table | OutputFormat::Raw => {
+            print_table(
+                &["JOBS", "COMPLETED", "PENDING", "RUNNING"],
+                vec![[
+                    usage.jobs,
+                    usage.completed_executions,
+                    usage.pending_executions,
+                    usage.running_executions,
+                ]
+                .iter()
+                .map(usize::to_string)
+                .collect()],
+            );
+            usage.warn_unfinished();
+        }
+    }
+
+    Ok(())
+}
+
+/// Resource counts of a project
+#[derive(Serialize, Debug)]
+struct ProjectSummary {
+    project_id: String,
+    runtimes: BTreeMap<String, usize>,
+    jobs: usize,
+    job_executions: BTreeMap<String, usize>,
+    input_artifacts: usize,
+    output_artifacts: usize,
+}
+
+fn count_by<T>(items: &[T], key: impl Fn(&T) -> String) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for item in items {
+        *counts.entry(key(item)).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Joins counts by status such as `3 active, 1 inactive`, or `0` when there are none
+fn format_counts(
+    counts: &BTreeMap<String, usize>,
+    format: impl Fn(&str, usize) -> String,
+) -> String {
+    if counts.is_empty() {
+        return "0".to_string();
+    }
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(status, count)| format(status, *count))
+        .collect();
+    counts.join(", ")
+}
+
+pub async fn project_summary(project_id: String, format: OutputFormat) -> Result<(), DiceCliError> {
+    // Executions and output artifacts hang off jobs, so only these can be fetched at once
+    let (runtimes, jobs, input_artifacts) = tokio::join!(
+        try_sdk("list runtimes", || {
+            rust_sdk::api::runtime::list(doc! { "project_id": project_id.clone() })
+        }),
+        try_sdk("list jobs", || {
+            rust_sdk::api::job::list(doc! { "project_id": project_id.clone() })
+        }),
+        try_sdk("list input artifacts", || {
+            rust_sdk::api::artifact::list(doc! {
+                "artifact_type": enum_to_string(&ArtifactType::Input),
+                "entity_id": project_id.clone(),
+            })
+        }),
+    );
+    let (runtimes, jobs, input_artifacts) = (runtimes?, jobs?, input_artifacts?);
+
+    let job_ids: Vec<String> = jobs.iter().map(|job| job.id.to_string()).collect();
+    let executions = try_sdk("list job executions", || {
+        rust_sdk::api::job_execution::list(doc! { "job_id": { "$in": job_ids.clone() } })
+    })
+    .await?;
+    let execution_ids: Vec<_> = executions.iter().map(|execution| execution.id).collect();
+    let output_artifacts = try_sdk("list output artifacts", || {
+        rust_sdk::api::artifact::list(doc! {
+            "artifact_type": enum_to_string(&ArtifactType::Output),
+            "entity_id": { "$in": execution_ids.clone() },
+        })
+    })
+    .await?;
+
+    let summary = ProjectSummary {
+        project_id,
+        runtimes: count_by(&runtimes, |runtime| {
+            enum_to_string(&runtime.status).to_lowercase()
+        }),
+        jobs: jobs.len(),
+        job_executions: count_by(&executions, |execution| {
+            enum_to_string(&execution.status).to_lowercase()
+        }),
+        input_artifacts: input_artifacts.len(),
+        output_artifacts: output_artifacts.len(),
+    };
+
+    match format {
+        OutputFormat::Json => outln!("{}", serde_json::to_string_pretty(&summary).unwrap()),
+        OutputFormat::Table | OutputFormat::Raw => {
+            let runtimes = format_counts(&summary.runtimes, |status, count| {
+                format!("{} {}", count, status)
+            });
+            let executions = format_counts(&summary.job_executions, |status, count| {
+                format!("{} {}", status, count)
+            });
+            outln!(
+                "Runtimes: {} | Jobs: {} | Executions: {} | Input artifacts: {} | Output artifacts: {}",
+                runtimes,
+                summary.jobs,
+                executions,
+                summary.input_artifacts,
+                summary.output_artifacts
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    fn object_id(seconds: u32) -> ObjectId {
+        let mut bytes = [0; 12];
+        bytes[..4].copy_from_slice(&seconds.to_be_bytes());
+        ObjectId::from_bytes(bytes)
+    }
+
+    #[test]
+    fn parses_since_durations() {
+        assert_eq!(parse_since("45s").unwrap(), Duration::seconds(45));
+        assert_eq!(parse_since("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_since("24h").unwrap(), Duration::hours(24));
+        assert_eq!(parse_since("7d").unwrap(), Duration::days(7));
+        for invalid in ["", "d", "24", "24w", "1.5h", "h24"] {
+            assert!(
+                matches!(
+                    parse_since(invalid),
+                    Err(DiceCliError::InvalidArgument { .. })
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn takes_nearest_rank_percentiles() {
+        let sorted: Vec<i64> = (1..=20).collect();
+        assert_eq!(percentile(&sorted, 50.0), 10);
+        assert_eq!(percentile(&sorted, 95.0), 19);
+        assert_eq!(percentile(&sorted, 100.0), 20);
+        assert_eq!(percentile(&sorted, 0.0), 1);
+
+        assert_eq!(percentile(&[7], 50.0), 7);
+        assert_eq!(percentile(&[7], 95.0), 7);
+        assert_eq!(percentile(&[3, 9], 50.0), 3);
+    }
+
+    #[test]
+    fn measures_executions_up_to_their_end_and_counts_the_rest() {
+        let (durations, missing_timing) = durations([
+            (time(0), Some(time(90))),
+            (time(10), None),
+            (time(20), Some(time(50))),
+            // Clock skew never makes a negative duration
+            (time(30), Some(time(25))),
+        ]);
+
+        assert_eq!(durations, [0, 30, 90]);
+        assert_eq!(missing_timing, 1);
+    }
+
+    #[test]
+    fn warns_about_executions_that_will_still_run_the_runtime() {
+        let mut usage = RuntimeUsage::new("runtime".to_string(), 2);
+        for status in [
+            JobExecutionStatus::Completed,
+            JobExecutionStatus::Failed,
+            JobExecutionStatus::Completed,
+        ] {
+            usage.count(&status);
+        }
+        assert_eq!(usage.completed_executions, 2);
+        assert_eq!(usage.unfinished_warning(), None);
+
+        usage.count(&JobExecutionStatus::Pending);
+        usage.count(&JobExecutionStatus::Running);
+        usage.count(&JobExecutionStatus::Pending);
+        assert_eq!(
+            usage.unfinished_warning().as_deref(),
+            Some("Warning: runtime runtime still has 2 pending and 1 running job executions")
+        );
+    }
+
+    #[test]
+    fn ends_executions_at_their_last_output_artifact() {
+        let (first, second, third) = (object_id(100), object_id(300), object_id(200));
+        let latest = latest_by_entity([
+            ("execution".to_string(), &first),
+            ("execution".to_string(), &second),
+            ("execution".to_string(), &third),
+            ("other".to_string(), &first),
+        ]);
+
+        assert_eq!(latest["execution"].timestamp(), 300);
+        assert_eq!(latest["other"].timestamp(), 100);
+        assert_eq!(latest.len(), 2);
+    }
+}
@@ -0,0 +1,52 @@
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Answer with an empty body
+pub const OK: &str = "HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+
+/// Listens on a free local port, returning the listener and the URI of `path` on it
+pub async fn bind(path: &str) -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let uri = format!("http://{}{}", listener.local_addr().unwrap(), path);
+    (listener, uri)
+}
+
+/// Accepts the next connection and reads one request from it, returning the connection to
+/// answer on and the request body
+pub async fn accept(listener: &TcpListener) -> (TcpStream, Vec<u8>) {
+    let (mut socket, _) = listener.accept().await.unwrap();
+    let mut request = vec![];
+    let header_end = loop {
+        read_more(&mut socket, &mut request).await;
+        if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+    let headers = String::from_utf8_lossy(&request[..header_end]).to_lowercase();
+    let content_length: usize = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length: "))
+        .map_or(0, |length| length.trim().parse().unwrap());
+    while request.len() < header_end + content_length {
+        read_more(&mut socket, &mut request).await;
+    }
+    (socket, request.split_off(header_end))
+}
+
+/// Appends the next bytes the client sent to `request`, failing the test when the client
+/// hung up instead of waiting for more forever
+async fn read_more(socket: &mut TcpStream, request: &mut Vec<u8>) {
+    let mut buffer = [0; 8192];
+    let read = socket.read(&mut buffer).await.unwrap();
+    if read == 0 {
+        panic!("connection closed before the request ended");
+    }
+    request.extend_from_slice(&buffer[..read]);
+}
+
+/// Writes a raw HTTP response and closes the connection
+pub async fn respond(mut socket: TcpStream, response: &str) {
+    socket.write_all(response.as_bytes()).await.unwrap();
+}
@@ -0,0 +1,290 @@
+use chrono::{DateTime, Utc};
+use comfy_table::{presets, ContentArrangement, Table};
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    terminal::{Clear, ClearType},
+    tty::IsTty,
+};
+use mongodb::bson::{doc, Document};
+use rust_sdk::model::job_execution::{JobExecution, Status as JobExecutionStatus};
+use std::{collections::HashMap, io, time::Duration};
+
+use crate::{
+    color,
+    error::DiceCliError,
+    format_duration,
+    output::outln,
+    print_table,
+    stats::{self, created_at, is_finished},
+    try_sdk,
+};
+
+/// Width of the completion bar in characters
+const BAR_WIDTH: usize = 30;
+
+/// Executions shown in the recently updated list unless `--limit` is given
+pub const DEFAULT_RECENT: usize = 10;
+
+/// Last change of an execution the API lets us date: its last output artifact once it has
+/// finished, and its creation otherwise
+fn last_update(
+    job_execution: &JobExecution,
+    finish_times: &HashMap<String, DateTime<Utc>>,
+) -> DateTime<Utc> {
+    stats::finished_at(job_execution, finish_times).unwrap_or_else(|| created_at(&job_execution.id))
+}
+
+fn completion_bar(finished: usize, total: usize) -> String {
+    let ratio = match total {
+        0 => 1.0,
+        total => finished as f64 / total as f64,
+    };
+    let filled = (ratio * BAR_WIDTH as f64).round() as usize;
+    format!(
+        "[{}{}] {:>3.0}% ({}/{} finished)",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        ratio * 100.0,
+        finished,
+        total
+    )
+}
+
+fn print_snapshot(
+    job_id: &str,
+    job_executions: &[JobExecution],
+    finish_times: &HashMap<String, DateTime<Utc>>,
+    recent: usize,
+) {
+    let now = Utc::now();
+    let finished = job_executions
+        .iter()
+        .filter(|job_execution| is_finished(job_execution))
+        .count();
+
+    outln!(
+        "Job {}: {} job executions at {}",
+        job_id,
+        job_executions.len(),
+        now.format("%H:%M:%S")
+    );
+    outln!("{}", completion_bar(finished, job_executions.len()));
+
+    let counts: Vec<String> = [
+        JobExecutionStatus::Pending,
+        JobExecutionStatus::Running,
+        JobExecutionStatus::Completed,
+        JobExecutionStatus::Failed,
+    ]
+    .iter()
+    .map(|status| {
+        let count = job_executions
+            .iter()
+            .filter(|job_execution| &job_execution.status == status)
+            .count();
+        format!("{} {}", color::job_execution_status(status), count)
+    })
+    .collect();
+    outln!("{}", counts.join("  "));
+    outln!();
+
+    let mut updates: Vec<_> = job_executions
+        .iter()
+        .map(|job_execution| (last_update(job_execution, finish_times), job_execution))
+        .collect();
+    updates.sort_by(|(a, _), (b, _)| b.cmp(a));
+    // The status comes last, so its color codes do not shift the other columns
+    let rows = updates
+        .into_iter()
+        .take(recent)
+        .map(|(updated, job_execution)| {
+            vec![
+                job_execution.id.to_string(),
+                format_duration((now - updated).num_seconds().max(0)),
+                color::job_execution_status(&job_execution.status).to_string(),
+            ]
+        })
+        .collect();
+    print_table(&["ID", "UPDATED", "STATUS"], rows);
+}
+
+/// Table of executions, each row holding the ID, creation and elapsed time of an execution
+/// with its status
+fn executions_table(rows: Vec<([String; 3], &JobExecutionStatus)>) -> Table {
+    let mut table = Table::new();
+    table
+        .load_preset(presets::UTF8_FULL_CONDENSED)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(["ID", "CREATED", "ELAPSED", "STATUS"]);
+    if !console::colors_enabled() {
+        table.force_no_tty();
+    }
+    for ([id, created, elapsed], status) in rows {
+        table.add_row(vec![
+            id.into(),
+            created.into(),
+            elapsed.into(),
+            color::job_execution_status_cell(status),
+        ]);
+    }
+
+    table
+}
+
+/// One row per execution with its creation time and how long it ran, or has been running
+fn print_executions(
+    job_id: &str,
+    job_executions: &[JobExecution],
+    finish_times: &HashMap<String, DateTime<Utc>>,
+) {
+    let now = Utc::now();
+    let finished = job_executions
+        .iter()
+        .filter(|job_execution| is_finished(job_execution))
+        .count();
+    outln!(
+        "Job {}: {} of {} job executions finished at {}",
+        job_id,
+        finished,
+        job_executions.len(),
+        now.format("%H:%M:%S")
+    );
+    outln!();
+
+    let rows = job_executions
+        .iter()
+        .map(|job_execution| {
+            let created = created_at(&job_execution.id);
+            let ended = if is_finished(job_execution) {
+                stats::finished_at(job_execution, finish_times)
+            } else {
+                Some(now)
+            };
+            let elapsed = ended.map_or("-".to_string(), |ended| {
+                format_duration((ended - created).num_seconds().max(0))
+            });
+            (
+                [
+                    job_execution.id.to_string(),
+                    created.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    elapsed,
+                ],
+                &job_execution.status,
+            )
+        })
+        .collect();
+    outln!("{}", executions_table(rows));
+}
+
+/// Shows the executions matching `filter` with `render` every `interval`, until all of them
+/// have finished or, with `forever`, until interrupted. The view is redrawn in place on a
+/// terminal and printed as successive snapshots otherwise.
+async fn refresh(
+    filter: Document,
+    interval: Duration,
+    forever: bool,
+    render: impl Fn(&[JobExecution], &HashMap<String, DateTime<Utc>>),
+) -> Result<(), DiceCliError> {
+    let redraw = io::stdout().is_tty();
+
+    loop {
+        let job_executions = try_sdk("list job executions", || {
+            rust_sdk::api::job_execution::list(filter.clone())
+        })
+        .await?;
+        let finish_times = stats::fetch_finish_times(&job_executions).await?;
+
+        if redraw {
+            let _ = execute!(io::stdout(), MoveTo(0, 0), Clear(ClearType::All));
+        }
+        render(&job_executions, &finish_times);
+        if !redraw {
+            outln!();
+        }
+
+        if !forever && job_executions.iter().all(is_finished) {
+            return Ok(());
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Shows the status counts and the recently updated executions of a job matching `filter`
+pub async fn job_execution_top(
+    job_id: String,
+    filter: Document,
+    interval: Duration,
+    recent: usize,
+    forever: bool,
+) -> Result<(), DiceCliError> {
+    refresh(filter, interval, forever, |job_executions, finish_times| {
+        print_snapshot(&job_id, job_executions, finish_times, recent)
+    })
+    .await
+}
+
+/// Shows every execution of a job in a table until all of them have finished
+pub async fn watch_job(job_id: String, interval: Duration) -> Result<(), DiceCliError> {
+    refresh(
+        doc! { "job_id": job_id.clone() },
+        interval,
+        false,
+        |job_executions, finish_times| print_executions(&job_id, job_executions, finish_times),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabulates_one_row_per_execution() {
+        console::set_colors_enabled(false);
+        let row = |id: &str, status| {
+            (
+                [
+                    id.to_string(),
+                    "2024-01-31 12:00:00".to_string(),
+                    "1m 5s".to_string(),
+                ],
+                status,
+            )
+        };
+
+        let table = executions_table(vec![
+            row("first", &JobExecutionStatus::Running),
+            row("second", &JobExecutionStatus::Failed),
+        ])
+        .to_string();
+
+        let lines: Vec<_> = table.lines().collect();
+        assert!(lines[1].contains("ID") && lines[1].contains("STATUS"));
+        assert!(lines[3].contains("first") && lines[3].contains("Running"));
+        assert!(lines[4].contains("second") && lines[4].contains("Failed"));
+        assert!(lines[4].contains("2024-01-31 12:00:00") && lines[4].contains("1m 5s"));
+        assert!(!table.contains('\x1b'));
+    }
+
+    #[test]
+    fn fills_the_completion_bar_with_the_finished_share() {
+        assert_eq!(
+            completion_bar(1, 3),
+            format!("[{}{}]  33% (1/3 finished)", "#".repeat(10), "-".repeat(20))
+        );
+        assert_eq!(
+            completion_bar(0, 4),
+            format!("[{}]   0% (0/4 finished)", "-".repeat(BAR_WIDTH))
+        );
+        assert_eq!(
+            completion_bar(4, 4),
+            format!("[{}] 100% (4/4 finished)", "#".repeat(BAR_WIDTH))
+        );
+        // A job without executions has nothing left to run
+        assert_eq!(
+            completion_bar(0, 0),
+            format!("[{}] 100% (0/0 finished)", "#".repeat(BAR_WIDTH))
+        );
+    }
+}
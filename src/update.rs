@@ -0,0 +1,286 @@
+use serde_derive::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{env, fs, path::Path};
+use uuid::Uuid;
+
+use crate::{error::DiceCliError, get_for_download, http, output::outln, retry};
+
+const CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Target triple the CLI was built for, recorded by the build script; release binaries are
+/// named after it
+const TARGET: &str = env!("DICE_TARGET");
+
+/// GitHub releases of the CLI, overridden by `DICE_RELEASES_URL` for mirrors
+const DEFAULT_RELEASES_URL: &str =
+    "https://api.github.com/repos/DICE-volunteer-computing/cli/releases";
+
+#[derive(Deserialize, Debug)]
+struct Release {
+    tag_name: String,
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReleaseAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    fn asset_url(&self, name: &str) -> Result<&str, DiceCliError> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .map(|asset| asset.browser_download_url.as_str())
+            .ok_or_else(|| DiceCliError::ReleaseUnavailable {
+                message: format!("release {} has no {}", self.tag_name, name),
+            })
+    }
+}
+
+/// Numeric parts of a version, so 0.10.0 sorts after 0.9.1
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['.', '-'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+/// Name of the release binary for this platform
+fn binary_name() -> String {
+    format!("dice-cli-{}{}", TARGET, env::consts::EXE_SUFFIX)
+}
+
+/// The latest release, or the one of `version`
+async fn fetch_release(version: Option<&str>) -> Result<Release, DiceCliError> {
+    let unavailable = |message: String| DiceCliError::ReleaseUnavailable { message };
+
+    let releases_url =
+        env::var("DICE_RELEASES_URL").unwrap_or_else(|_| DEFAULT_RELEASES_URL.to_string());
+    let uri = match version {
+        Some(version) => format!(
+            "{}/tags/v{}",
+            releases_url.trim_end_matches('/'),
+            version.trim_start_matches('v')
+        ),
+        None => format!("{}/latest", releases_url.trim_end_matches('/')),
+    };
+    // GitHub rejects requests without a user agent
+    let response = http::client()
+        .get(&uri)
+        .header(
+            reqwest::header::USER_AGENT,
+            format!("dice-cli/{}", CLI_VERSION),
+        )
+        .send()
+        .await
+        .map_err(|err| unavailable(err.to_string()))?;
+    if !response.status().is_success() {
+        return Err(unavailable(format!(
+            "{} answered with status {}",
+            uri,
+            response.status()
+        )));
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(|err| unavailable(err.to_string()))?;
+    serde_json::from_str(&body)
+        .map_err(|err| unavailable(format!("{} did not return a release: {}", uri, err)))
+}
+
+async fn download(uri: &str) -> Result<Vec<u8>, DiceCliError> {
+    let response = retry::with_configured_retry(|| get_for_download(http::client(), uri)).await?;
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|err| DiceCliError::ReleaseUnavailable {
+            message: format!("could not download {}: {}", uri, err),
+        })?;
+    Ok(bytes.to_vec())
+}
+
+/// Checks the binary of a release against the contents of its `.sha256` file
+fn verify_checksum(version: &str, binary: &[u8], checksum_file: &[u8]) -> Result<(), DiceCliError> {
+    // Checksum files hold the digest, optionally followed by the file name
+    let expected = String::from_utf8_lossy(checksum_file)
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let actual = format!("{:x}", Sha256::digest(binary));
+    if actual != expected {
+        return Err(DiceCliError::UpdateChecksumMismatch {
+            version: version.to_string(),
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Writes an executable next to `path` and renames it over it, so the file is never left half
+/// written
+fn replace_executable(path: &Path, contents: &[u8]) -> Result<(), DiceCliError> {
+    let temp_path = path.with_file_name(format!(".dice-cli.update-{}", Uuid::new_v4()));
+    let write_failed = |source| DiceCliError::Io {
+        action: "write",
+        path: path.to_path_buf(),
+        source,
+    };
+
+    fs::write(&temp_path, contents).map_err(write_failed)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&temp_path, fs::Permissions::from_mode(0o755)).map_err(write_failed)?;
+    }
+    fs::rename(&temp_path, path).map_err(|source| {
+        let _ = fs::remove_file(&temp_path);
+        write_failed(source)
+    })
+}
+
+/// Replaces the running binary
+fn replace_binary(contents: &[u8]) -> Result<(), DiceCliError> {
+    let current = env::current_exe()
+        .and_then(|path| path.canonicalize())
+        .map_err(|source| DiceCliError::Io {
+            action: "locate",
+            path: Path::new("dice").to_path_buf(),
+            source,
+        })?;
+    replace_executable(&current, contents)
+}
+
+/// Updates the CLI to the latest release, or to `version`; with `check` only reports whether
+/// an update is available
+pub async fn self_update(check: bool, version: Option<&str>) -> Result<(), DiceCliError> {
+    let release = fetch_release(version).await?;
+    let release_version = release.version();
+    let newer = version_parts(release_version) > version_parts(CLI_VERSION);
+
+    if check {
+        if newer {
+            outln!(
+                "dice-cli {} is available, {} is installed; run `dice --self-update` to update",
+                release_version,
+                CLI_VERSION
+            );
+        } else {
+            outln!("dice-cli {} is up to date", CLI_VERSION);
+        }
+        return Ok(());
+    }
+    // A pinned version is installed even when it is older, to roll back
+    if version.is_none() && !newer {
+        outln!("dice-cli {} is up to date", CLI_VERSION);
+        return Ok(());
+    }
+    if release_version == CLI_VERSION {
+        outln!("dice-cli {} is already installed", CLI_VERSION);
+        return Ok(());
+    }
+
+    let binary_name = binary_name();
+    let binary = download(release.asset_url(&binary_name)?).await?;
+    let checksum_file = download(release.asset_url(&format!("{}.sha256", binary_name))?).await?;
+    verify_checksum(release_version, &binary, &checksum_file)?;
+
+    replace_binary(&binary)?;
+    outln!("Updated dice-cli {} -> {}", CLI_VERSION, release_version);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SHA-256 of `new binary`
+    const DIGEST: &str = "2f17c9ffb972a6c5da72c2b3df01f7e2ccf52dad2c0059dac631232a15126d2e";
+
+    fn digest(contents: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(contents))
+    }
+
+    #[test]
+    fn accepts_a_binary_matching_its_checksum_file() {
+        verify_checksum("0.2.0", b"new binary", DIGEST.as_bytes()).unwrap();
+        // sha256sum output, in any case
+        let line = format!(
+            "{}  dice-cli-x86_64-unknown-linux-gnu\n",
+            DIGEST.to_uppercase()
+        );
+        verify_checksum("0.2.0", b"new binary", line.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_binary_not_matching_its_checksum_file() {
+        let err = verify_checksum("0.2.0", b"tampered binary", DIGEST.as_bytes()).unwrap_err();
+        match err {
+            DiceCliError::UpdateChecksumMismatch {
+                version,
+                expected,
+                actual,
+            } => {
+                assert_eq!(version, "0.2.0");
+                assert_eq!(expected, DIGEST);
+                assert_eq!(actual, digest(b"tampered binary"));
+            }
+            other => panic!("expected a checksum mismatch, got {:?}", other),
+        }
+        assert!(matches!(
+            verify_checksum("0.2.0", b"new binary", b""),
+            Err(DiceCliError::UpdateChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn renames_the_new_binary_over_the_old_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dice");
+        fs::write(&path, "old binary").unwrap();
+
+        replace_executable(&path, b"new binary").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"new binary");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(
+                fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+                0o755
+            );
+        }
+        // Only the binary is left, no temporary file
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn removes_the_new_binary_when_it_cannot_be_renamed() {
+        let dir = tempfile::tempdir().unwrap();
+        // A directory that is not empty cannot be replaced by a file
+        let path = dir.path().join("dice");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("kept"), "").unwrap();
+
+        assert!(matches!(
+            replace_executable(&path, b"new binary"),
+            Err(DiceCliError::Io {
+                action: "write",
+                ..
+            })
+        ));
+        assert!(path.join("kept").exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,206 @@
+use serde_derive::Serialize;
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+use wasmparser::{ExternalKind, Parser, Payload};
+
+use crate::{archive, error::DiceCliError};
+
+/// Magic bytes and version 1 at the start of every WebAssembly binary
+const WASM_HEADER: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+/// Import module names of the WASI versions a wasm32-wasi binary may target
+const WASI_MODULES: [&[u8]; 2] = [b"wasi_snapshot_preview1", b"wasi_unstable"];
+
+fn check_wasm(name: &str, contents: &[u8], target: &str) -> Result<(), String> {
+    if !contents.starts_with(&WASM_HEADER[..4]) {
+        return Err(format!("{} is not a WebAssembly binary", name));
+    }
+    if !contents.starts_with(&WASM_HEADER) {
+        return Err(format!(
+            "{} has unsupported WebAssembly version {:?}",
+            name,
+            contents.get(4..8).unwrap_or_default()
+        ));
+    }
+
+    if !target.contains("wasi") {
+        return Ok(());
+    }
+
+    // Binaries built for wasm32-unknown-unknown have no WASI imports to run with
+    let imports_wasi = WASI_MODULES.iter().any(|module| {
+        contents
+            .windows(module.len())
+            .any(|window| window == *module)
+    });
+    if !imports_wasi {
+        return Err(format!(
+            "{} does not import WASI, it may have been built for another target than {}",
+            name, target
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that a runtime archive is a non-empty tarball and that any WebAssembly binaries
+/// it contains match the supported target of the runtime, wasm32-wasi unless given
+pub fn validate_runtime(path: &Path, target: Option<&str>) -> Result<(), DiceCliError> {
+    let target = target.unwrap_or("wasm32-wasi");
+    let invalid = |reason: String| DiceCliError::InvalidRuntime {
+        path: path.to_path_buf(),
+        reason,
+    };
+    let read_failed = |source: io::Error| invalid(format!("could not read archive: {}", source));
+
+    let metadata = fs::metadata(path).map_err(|source| DiceCliError::Io {
+        action: "read",
+        path: path.to_path_buf(),
+        source,
+    })?;
+    if metadata.len() == 0 {
+        return Err(invalid("the archive is empty".to_string()));
+    }
+
+    let mut archive = archive::open_archive(path)?;
+    let mut files = 0;
+    for entry in archive.entries().map_err(read_failed)? {
+        let mut entry = entry.map_err(read_failed)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        files += 1;
+
+        let name = entry.path().map_err(read_failed)?.display().to_string();
+        if name.ends_with(".wasm") {
+            let mut contents = vec![];
+            entry.read_to_end(&mut contents).map_err(read_failed)?;
+            check_wasm(&name, &contents, target).map_err(invalid)?;
+        }
+    }
+    if files == 0 {
+        return Err(invalid("the archive contains no files".to_string()));
+    }
+
+    Ok(())
+}
+
+/// A WebAssembly module of a verified runtime
+#[derive(Serialize, Debug)]
+pub struct VerifiedModule {
+    pub name: String,
+    pub bytes: u64,
+    pub exports_entry_point: bool,
+}
+
+/// Outcome of `--verify-runtime`
+#[derive(Serialize, Debug)]
+pub struct RuntimeVerification {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub entry_point: String,
+    pub modules: Vec<VerifiedModule>,
+}
+
+/// Validates a module fully and reports whether it exports the function `entry_point`
+fn verify_module(
+    name: &str,
+    contents: &[u8],
+    target: &str,
+    entry_point: &str,
+) -> Result<bool, String> {
+    check_wasm(name, contents, target)?;
+    wasmparser::validate(contents)
+        .map_err(|err| format!("{} is not a valid WebAssembly module: {}", name, err))?;
+
+    for payload in Parser::new(0).parse_all(contents) {
+        let payload = payload.map_err(|err| format!("could not parse {}: {}", name, err))?;
+        if let Payload::ExportSection(exports) = payload {
+            for export in exports {
+                let export = export.map_err(|err| format!("could not parse {}: {}", name, err))?;
+                if export.name == entry_point && export.kind == ExternalKind::Func {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+/// Checks a built runtime, an archive or a bare WebAssembly binary, more thoroughly than
+/// before uploads: every module is validated and one of them must export `entry_point`
+pub fn verify_runtime(
+    path: &Path,
+    target: &str,
+    entry_point: &str,
+) -> Result<RuntimeVerification, DiceCliError> {
+    let invalid = |reason: String| DiceCliError::InvalidRuntime {
+        path: path.to_path_buf(),
+        reason,
+    };
+    let read_failed = |source: io::Error| invalid(format!("could not read archive: {}", source));
+
+    let bytes = fs::metadata(path)
+        .map_err(|source| DiceCliError::Io {
+            action: "read",
+            path: path.to_path_buf(),
+            source,
+        })?
+        .len();
+
+    let mut modules = vec![];
+    let mut verify = |name: String, contents: Vec<u8>| {
+        let exports_entry_point =
+            verify_module(&name, &contents, target, entry_point).map_err(invalid)?;
+        modules.push(VerifiedModule {
+            name,
+            bytes: contents.len() as u64,
+            exports_entry_point,
+        });
+        Ok::<_, DiceCliError>(())
+    };
+
+    if path.extension() == Some(OsStr::new("wasm")) {
+        let contents = fs::read(path).map_err(|source| DiceCliError::Io {
+            action: "read",
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        verify(name.into_owned(), contents)?;
+    } else {
+        validate_runtime(path, Some(target))?;
+        let mut archive = archive::open_archive(path)?;
+        for entry in archive.entries().map_err(read_failed)? {
+            let mut entry = entry.map_err(read_failed)?;
+            let name = entry.path().map_err(read_failed)?.display().to_string();
+            if entry.header().entry_type().is_file() && name.ends_with(".wasm") {
+                let mut contents = vec![];
+                entry.read_to_end(&mut contents).map_err(read_failed)?;
+                verify(name, contents)?;
+            }
+        }
+    }
+
+    if modules.is_empty() {
+        return Err(invalid("it contains no WebAssembly module".to_string()));
+    }
+    if !modules.iter().any(|module| module.exports_entry_point) {
+        return Err(invalid(format!(
+            "no WebAssembly module exports the entry point function `{}`, set entry_point in .dice/runtime.toml if the runtime uses another one",
+            entry_point
+        )));
+    }
+
+    Ok(RuntimeVerification {
+        path: path.to_path_buf(),
+        bytes,
+        entry_point: entry_point.to_string(),
+        modules,
+    })
+}
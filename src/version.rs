@@ -0,0 +1,226 @@
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    env, fs,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{error::DiceCliError, http, output::outln, write_atomically, OutputFormat};
+
+const CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of the rust-sdk the CLI was built against, recorded by the build script
+const SDK_VERSION: &str = env!("RUST_SDK_VERSION");
+
+/// Path of the API endpoint reporting the server version, relative to API_URL
+const VERSION_ENDPOINT: &str = "version";
+
+/// File in the cache directory remembering the last server version check
+const CACHE_FILE: &str = "server-version.json";
+
+/// How long a server version check is reused before the server is asked again
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Longest the automatic check may delay a command
+const AUTOMATIC_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+const EXPLICIT_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Result of the last server version check; failed checks are cached as well, so an
+/// unreachable version endpoint does not slow down every command
+#[derive(Serialize, Deserialize, Debug)]
+struct CachedServerVersion {
+    api_url: String,
+    version: Option<String>,
+    checked_at: u64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Part of a version that changes on incompatible releases: the major version, and the
+/// minor version as well before 1.0
+fn compatibility(version: &str) -> Option<(u64, u64)> {
+    let mut parts = version.trim().trim_start_matches('v').split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = match major {
+        0 => parts.next()?.parse().ok()?,
+        _ => 0,
+    };
+    Some((major, minor))
+}
+
+/// Whether the CLI and the server are on incompatible releases, e.g. CLI 1.x and server 2.x
+fn is_mismatch(cli_version: &str, server_version: &str) -> bool {
+    match (compatibility(cli_version), compatibility(server_version)) {
+        (Some(cli), Some(server)) => cli != server,
+        _ => false,
+    }
+}
+
+fn mismatch_warning(server_version: &str) -> String {
+    format!(
+        "Warning: the DICE API is version {} but this is dice-cli {}, requests may fail; update the CLI or check API_URL",
+        server_version, CLI_VERSION
+    )
+}
+
+/// Version in a JSON response of the version endpoint, either {"version": "1.2.3"} or the bare
+/// version
+fn reported_version(response: Value) -> Option<String> {
+    match response {
+        Value::Object(object) => object
+            .get("version")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        Value::String(version) => Some(version),
+        _ => None,
+    }
+}
+
+async fn fetch_server_version(api_url: &str, timeout: Duration) -> Result<String, DiceCliError> {
+    let unavailable = |message: String| DiceCliError::ServerVersionUnavailable { message };
+
+    let uri = format!("{}/{}", api_url.trim_end_matches('/'), VERSION_ENDPOINT);
+    let response = http::client()
+        .get(&uri)
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|err| unavailable(err.to_string()))?;
+    if !response.status().is_success() {
+        return Err(unavailable(format!(
+            "{} answered with status {}",
+            uri,
+            response.status()
+        )));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|err| unavailable(err.to_string()))?;
+
+    // A plain text body is taken as the version when it looks like one
+    let version = match serde_json::from_str::<Value>(&body) {
+        Ok(response) => reported_version(response),
+        Err(_) => Some(body.trim().to_string()).filter(|version| compatibility(version).is_some()),
+    };
+    version.ok_or_else(|| unavailable(format!("{} did not report a version", uri)))
+}
+
+fn read_cache(cache_dir: &Path, api_url: &str) -> Option<CachedServerVersion> {
+    let contents = fs::read_to_string(cache_dir.join(CACHE_FILE)).ok()?;
+    let cached: CachedServerVersion = serde_json::from_str(&contents).ok()?;
+    (cached.api_url == api_url && now().saturating_sub(cached.checked_at) < CACHE_TTL.as_secs())
+        .then_some(cached)
+}
+
+fn write_cache(cache_dir: &Path, api_url: &str, version: Option<&str>) {
+    let cached = CachedServerVersion {
+        api_url: api_url.to_string(),
+        version: version.map(str::to_string),
+        checked_at: now(),
+    };
+    let contents = serde_json::to_string(&cached).unwrap();
+    // The next command simply checks again when the cache cannot be written
+    let _ = fs::create_dir_all(cache_dir);
+    let _ = write_atomically(&cache_dir.join(CACHE_FILE), contents.as_bytes());
+}
+
+/// Warns on stderr when the server version does not match the CLI, using the cached version
+/// when it is recent. Never fails: the command runs whether or not the check succeeds.
+pub async fn warn_on_mismatch(cache_dir: &Path) {
+    let Ok(api_url) = env::var("API_URL") else {
+        return;
+    };
+
+    let version = match read_cache(cache_dir, &api_url) {
+        Some(cached) => cached.version,
+        None => {
+            let version = fetch_server_version(&api_url, AUTOMATIC_CHECK_TIMEOUT)
+                .await
+                .ok();
+            write_cache(cache_dir, &api_url, version.as_deref());
+            version
+        }
+    };
+
+    if let Some(version) = version.filter(|version| is_mismatch(CLI_VERSION, version)) {
+        eprintln!("{}", mismatch_warning(&version));
+    }
+}
+
+/// Prints the CLI and SDK versions and, with `check`, asks the server for its version
+pub async fn print_version(
+    cache_dir: &Path,
+    check: bool,
+    format: OutputFormat,
+) -> Result<(), DiceCliError> {
+    let server_version = if check {
+        let api_url = env::var("API_URL").map_err(|_| DiceCliError::ServerVersionUnavailable {
+            message: "API_URL is not set".to_string(),
+        })?;
+        let version = fetch_server_version(&api_url, EXPLICIT_CHECK_TIMEOUT).await?;
+        write_cache(cache_dir, &api_url, Some(&version));
+        Some(version)
+    } else {
+        None
+    };
+
+    match format {
+        OutputFormat::Json => outln!(
+            "{}",
+            serde_json::to_string_pretty(&json!({
+                "cli": CLI_VERSION,
+                "rust_sdk": SDK_VERSION,
+                "server": server_version,
+                "compatible": server_version
+                    .as_deref()
+                    .map(|version| !is_mismatch(CLI_VERSION, version)),
+            }))
+            .unwrap()
+        ),
+        OutputFormat::Table | OutputFormat::Raw => {
+            outln!("dice-cli {}", CLI_VERSION);
+            outln!("rust-sdk {}", SDK_VERSION);
+            if let Some(version) = &server_version {
+                outln!("DICE API {}", version);
+            }
+        }
+    }
+    if let Some(version) = server_version.filter(|version| is_mismatch(CLI_VERSION, version)) {
+        eprintln!("{}", mismatch_warning(&version));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_the_cli_version_with_the_server() {
+        assert!(is_mismatch("1.4.0", "2.0.1"));
+        assert!(!is_mismatch("1.4.0", "v1.9.2"));
+        // Before 1.0 a minor release is incompatible
+        assert!(is_mismatch("0.1.0", "0.2.0"));
+        assert!(!is_mismatch("0.1.0", "0.1.7"));
+        // An unparsable version is never reported as a mismatch
+        assert!(!is_mismatch("1.4.0", "nightly"));
+    }
+
+    #[test]
+    fn reads_the_version_the_api_reports() {
+        assert_eq!(
+            reported_version(json!({ "version": "2.1.0" })).as_deref(),
+            Some("2.1.0")
+        );
+        assert_eq!(reported_version(json!("2.1.0")).as_deref(), Some("2.1.0"));
+        assert_eq!(reported_version(json!({ "name": "dice" })), None);
+    }
+}
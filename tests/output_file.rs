@@ -0,0 +1,57 @@
+use std::{fs, process::Command};
+
+fn dice() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_dice-cli"));
+    command.env_remove("DICE_OUTPUT_FILE");
+    command
+}
+
+#[test]
+fn writes_the_output_to_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("codes.txt");
+
+    let output = dice()
+        .args(["--help-exit-codes", "--output-file"])
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let contents = fs::read_to_string(&path).unwrap();
+    assert!(contents.starts_with("CODE  MEANING\n"), "{}", contents);
+    assert!(contents.contains("An interrupted command exits with 130"));
+}
+
+#[test]
+fn truncates_an_existing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("codes.txt");
+    fs::write(&path, "stale\n".repeat(10_000)).unwrap();
+
+    let status = dice()
+        .args(["--help-exit-codes", "--output-file"])
+        .arg(&path)
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+    assert!(!fs::read_to_string(&path).unwrap().contains("stale"));
+}
+
+#[test]
+fn reports_an_output_file_that_cannot_be_created() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing").join("codes.txt");
+
+    let output = dice()
+        .args(["--help-exit-codes", "--output-file"])
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("codes.txt"));
+}
@@ -0,0 +1,68 @@
+use serde_json::Value;
+use std::process::Command;
+
+/// Runs the CLI against an API that refuses connections
+fn dice_without_api(home: &std::path::Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_dice-cli"));
+    command
+        .env_remove("DICE_OUTPUT_FILE")
+        .env_remove("DICE_API_URL")
+        .env("API_URL", "http://127.0.0.1:9")
+        .env("API_KEY", "test")
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home)
+        .env("XDG_CACHE_HOME", home);
+    command
+}
+
+#[test]
+fn reports_a_failed_api_call_as_one_json_error() {
+    let home = tempfile::tempdir().unwrap();
+
+    let output = dice_without_api(home.path())
+        .args([
+            "--format",
+            "json",
+            "--no-version-check",
+            "--retries",
+            "0",
+            "--get-job-execution",
+            "--job-execution-id",
+            "000000000000000000000000",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+    // The whole of stderr is the error object
+    let error: Value =
+        serde_json::from_str(&stderr).unwrap_or_else(|err| panic!("{}: {}", err, stderr));
+    assert_eq!(error["error"]["code"], "api_error", "{}", stderr);
+}
+
+#[test]
+fn reports_a_failed_api_call_without_panic_text() {
+    let home = tempfile::tempdir().unwrap();
+
+    let output = dice_without_api(home.path())
+        .args([
+            "--no-version-check",
+            "--retries",
+            "1",
+            "--retry-delay",
+            "1",
+            "--get-job-execution",
+            "--job-execution-id",
+            "000000000000000000000000",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("Error: could not "), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+    assert!(!stderr.contains("RUST_BACKTRACE"), "{}", stderr);
+}